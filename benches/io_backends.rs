@@ -0,0 +1,46 @@
+//! Throughput of each `--io` mode when hashing one large file.
+//!
+//! Run with `cargo bench --bench io_backends`. The file is written once and
+//! then stays in the page cache, so this measures the cost of getting the
+//! bytes to the hasher rather than the speed of the disk.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use hashy::algorithm::Algorithm;
+use hashy::io::{hash_file_io, IoMode};
+use std::hint::black_box;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const FILE_SIZE: usize = 64 * 1024 * 1024; // 64 MiB
+
+fn io_modes() -> Vec<(&'static str, IoMode)> {
+    let mut modes = vec![("read", IoMode::Read), ("mmap", IoMode::Mmap)];
+    if cfg!(target_os = "linux") {
+        modes.push(("uring", IoMode::Uring));
+    }
+    modes
+}
+
+fn bench_io_backends(c: &mut Criterion) {
+    let mut file = NamedTempFile::new().expect("Failed to create temp file");
+    let data: Vec<u8> = (0..FILE_SIZE).map(|i| (i % 251) as u8).collect();
+    file.write_all(&data).expect("Failed to write temp file");
+    file.flush().expect("Failed to flush temp file");
+    let path = file.path().to_str().unwrap().to_string();
+
+    for algorithm in [Algorithm::Sha256, Algorithm::Blake3] {
+        let mut group = c.benchmark_group(algorithm.name());
+        group.throughput(Throughput::Bytes(FILE_SIZE as u64));
+        group.sample_size(20);
+
+        for (name, mode) in io_modes() {
+            group.bench_with_input(BenchmarkId::from_parameter(name), &mode, |b, &mode| {
+                b.iter(|| hash_file_io(&[algorithm], black_box(&path), None, mode).unwrap())
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_io_backends);
+criterion_main!(benches);
@@ -1,7 +1,13 @@
+use crate::hash::{new_hasher, Hasher};
 use anyhow::{anyhow, Result};
 use std::fmt;
 use std::str::FromStr;
 
+/// Largest `--length` accepted for extendable-output functions. Digests are
+/// held in memory and printed whole, so a bound keeps a mistyped length from
+/// aborting on allocation.
+pub const MAX_OUTPUT_LENGTH: usize = 64 * 1024; // 64 KiB
+
 /// Supported hash algorithms
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Algorithm {
@@ -10,14 +16,47 @@ pub enum Algorithm {
     Sha512,
     Blake3,
     Md5,
+    Sha3_224,
+    Sha3_256,
+    Sha3_384,
+    Sha3_512,
+    Keccak256,
+    Keccak512,
+    Shake128,
+    Shake256,
 }
 
 impl Algorithm {
+    /// Every supported algorithm
+    pub const ALL: [Algorithm; 13] = [
+        Algorithm::Sha256,
+        Algorithm::Sha512,
+        Algorithm::Blake3,
+        Algorithm::Sha3_224,
+        Algorithm::Sha3_256,
+        Algorithm::Sha3_384,
+        Algorithm::Sha3_512,
+        Algorithm::Keccak256,
+        Algorithm::Keccak512,
+        Algorithm::Shake128,
+        Algorithm::Shake256,
+        Algorithm::Sha1,
+        Algorithm::Md5,
+    ];
+
     /// Check if algorithm is considered insecure
     pub fn is_insecure(&self) -> bool {
         matches!(self, Algorithm::Sha1 | Algorithm::Md5)
     }
 
+    /// Check if algorithm is an extendable-output function (arbitrary digest length)
+    pub fn is_xof(&self) -> bool {
+        matches!(
+            self,
+            Algorithm::Blake3 | Algorithm::Shake128 | Algorithm::Shake256
+        )
+    }
+
     /// Get display name for the algorithm
     pub fn name(&self) -> &'static str {
         match self {
@@ -26,8 +65,87 @@ impl Algorithm {
             Algorithm::Sha512 => "sha512",
             Algorithm::Blake3 => "blake3",
             Algorithm::Md5 => "md5",
+            Algorithm::Sha3_224 => "sha3-224",
+            Algorithm::Sha3_256 => "sha3-256",
+            Algorithm::Sha3_384 => "sha3-384",
+            Algorithm::Sha3_512 => "sha3-512",
+            Algorithm::Keccak256 => "keccak256",
+            Algorithm::Keccak512 => "keccak512",
+            Algorithm::Shake128 => "shake128",
+            Algorithm::Shake256 => "shake256",
+        }
+    }
+
+    /// BSD-style tag used in tagged checksum lines (`SHA256 (file) = ...`)
+    pub fn tag(&self) -> String {
+        self.name().to_uppercase()
+    }
+
+    /// Parse the tag of a BSD-style checksum line, as written by `hashy hash
+    /// --tag`, `shasum --tag`, BSD `sha256` and `openssl dgst`
+    pub fn from_tag(tag: &str) -> Result<Self> {
+        match tag.to_lowercase().as_str() {
+            "sha-1" => Ok(Algorithm::Sha1),
+            "sha2-256" | "sha-256" => Ok(Algorithm::Sha256),
+            "sha2-512" | "sha-512" => Ok(Algorithm::Sha512),
+            _ => tag
+                .parse()
+                .map_err(|_| anyhow!("Unsupported algorithm tag: {}", tag)),
+        }
+    }
+
+    /// Get the digest size in bytes
+    ///
+    /// SHAKE128 and SHAKE256 are extendable-output functions; they default to
+    /// 32 and 64 bytes respectively, giving their full 128/256-bit security.
+    pub fn output_size(&self) -> usize {
+        match self {
+            Algorithm::Sha1 => 20,
+            Algorithm::Sha256 => 32,
+            Algorithm::Sha512 => 64,
+            Algorithm::Blake3 => 32,
+            Algorithm::Md5 => 16,
+            Algorithm::Sha3_224 => 28,
+            Algorithm::Sha3_256 => 32,
+            Algorithm::Sha3_384 => 48,
+            Algorithm::Sha3_512 => 64,
+            Algorithm::Keccak256 => 32,
+            Algorithm::Keccak512 => 64,
+            Algorithm::Shake128 => 32,
+            Algorithm::Shake256 => 64,
+        }
+    }
+
+    /// Resolve the digest size in bytes for an optional requested `--length`.
+    /// Only extendable-output functions accept a custom length, of at most
+    /// `MAX_OUTPUT_LENGTH` bytes.
+    pub fn resolve_output_size(&self, length: Option<usize>) -> Result<usize> {
+        match length {
+            None => Ok(self.output_size()),
+            Some(0) => Err(anyhow!("Output length must be at least 1 byte")),
+            Some(len) if self.is_xof() && len > MAX_OUTPUT_LENGTH => Err(anyhow!(
+                "Output length must be at most {} bytes (got {})",
+                MAX_OUTPUT_LENGTH,
+                len
+            )),
+            Some(len) if self.is_xof() => Ok(len),
+            Some(_) => Err(anyhow!(
+                "Algorithm '{}' has a fixed output size; --length is only supported for blake3, shake128 and shake256",
+                self.name()
+            )),
         }
     }
+
+    /// Create a streaming hasher with the default digest size
+    pub fn hasher(&self) -> Box<dyn Hasher> {
+        new_hasher(*self, self.output_size())
+    }
+
+    /// Create a streaming hasher with an optional `--length` in bytes.
+    /// Fails for fixed-output algorithms when a length is given.
+    pub fn hasher_with_length(&self, length: Option<usize>) -> Result<Box<dyn Hasher>> {
+        Ok(new_hasher(*self, self.resolve_output_size(length)?))
+    }
 }
 
 impl FromStr for Algorithm {
@@ -41,6 +159,14 @@ impl FromStr for Algorithm {
             "sha512" => Ok(Algorithm::Sha512),
             "blake3" => Ok(Algorithm::Blake3),
             "md5" => Ok(Algorithm::Md5),
+            "sha3-224" | "sha3_224" => Ok(Algorithm::Sha3_224),
+            "sha3-256" | "sha3_256" => Ok(Algorithm::Sha3_256),
+            "sha3-384" | "sha3_384" => Ok(Algorithm::Sha3_384),
+            "sha3-512" | "sha3_512" => Ok(Algorithm::Sha3_512),
+            "keccak256" | "keccak-256" => Ok(Algorithm::Keccak256),
+            "keccak512" | "keccak-512" => Ok(Algorithm::Keccak512),
+            "shake128" => Ok(Algorithm::Shake128),
+            "shake256" => Ok(Algorithm::Shake256),
             _ => Err(anyhow!("Unsupported algorithm: {}", s)),
         }
     }
@@ -0,0 +1,160 @@
+use crate::algorithm::Algorithm;
+use crate::hash::{hash_reader_multi, hashers_for};
+use anyhow::{anyhow, Context, Result};
+use flate2::read::MultiGzDecoder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use xz2::read::XzDecoder;
+
+/// Container formats `hashy hash --archive` can read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    TarXz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Detect the format from the first bytes of a file
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(ArchiveFormat::TarGz)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(ArchiveFormat::TarXz)
+        } else if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+            Some(ArchiveFormat::Zip)
+        } else if header.get(257..262) == Some(b"ustar") {
+            Some(ArchiveFormat::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// A file inside an archive and its digests
+#[derive(Debug)]
+pub struct ArchiveMember {
+    /// Path inside the archive, without a leading `./`
+    pub path: String,
+    /// One digest per algorithm and the member's size in bytes, or the
+    /// reason this member could not be read
+    pub digests: Result<(Vec<Vec<u8>>, u64)>,
+}
+
+/// Hash every regular file inside a tar, tar.gz, tar.xz or zip archive with
+/// several algorithms, in archive order, without extracting anything.
+///
+/// Directories and symbolic links are skipped. Tar hard links get the
+/// digests of the member they link to. A corrupt tar stream is an error for
+/// the whole archive, while a zip member that cannot be read (for example
+/// an encrypted one) is reported on its own.
+pub fn hash_archive(
+    archive_path: &str,
+    algorithms: &[Algorithm],
+    length: Option<usize>,
+) -> Result<Vec<ArchiveMember>> {
+    // Reject --length for fixed-output algorithms before reading anything
+    hashers_for(algorithms, length)?;
+
+    let mut file = File::open(archive_path)
+        .with_context(|| format!("Failed to open archive: {}", archive_path))?;
+    let mut header = Vec::with_capacity(512);
+    (&mut file)
+        .take(512)
+        .read_to_end(&mut header)
+        .with_context(|| format!("Failed to read archive: {}", archive_path))?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let format = ArchiveFormat::detect(&header).ok_or_else(|| {
+        anyhow!(
+            "Unsupported archive format: {} (expected tar, tar.gz, tar.xz or zip)",
+            archive_path
+        )
+    })?;
+
+    let reader = BufReader::new(file);
+    let members = match format {
+        ArchiveFormat::Tar => hash_tar(reader, algorithms, length),
+        ArchiveFormat::TarGz => hash_tar(MultiGzDecoder::new(reader), algorithms, length),
+        ArchiveFormat::TarXz => hash_tar(XzDecoder::new_multi_decoder(reader), algorithms, length),
+        ArchiveFormat::Zip => hash_zip(reader, algorithms, length),
+    };
+    members.with_context(|| format!("Failed to read archive: {}", archive_path))
+}
+
+/// Strip the `./` prefix tar adds when an archive is created from `.`
+fn member_path(path: &str) -> String {
+    let mut path = path;
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest;
+    }
+    path.to_string()
+}
+
+fn hash_tar<R: Read>(
+    reader: R,
+    algorithms: &[Algorithm],
+    length: Option<usize>,
+) -> Result<Vec<ArchiveMember>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut members = Vec::new();
+    // Digests of regular members by path, for hard links to them
+    let mut by_path: HashMap<String, (Vec<Vec<u8>>, u64)> = HashMap::new();
+
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let path = member_path(&String::from_utf8_lossy(&entry.path_bytes()));
+        let entry_type = entry.header().entry_type();
+
+        if entry_type.is_file() {
+            let (digests, size) = hash_reader_multi(hashers_for(algorithms, length)?, entry)?;
+            by_path.insert(path.clone(), (digests.clone(), size));
+            members.push(ArchiveMember {
+                path,
+                digests: Ok((digests, size)),
+            });
+        } else if entry_type.is_hard_link() {
+            let target = entry
+                .link_name_bytes()
+                .map(|name| member_path(&String::from_utf8_lossy(&name)))
+                .unwrap_or_default();
+            let digests = by_path
+                .get(&target)
+                .cloned()
+                .ok_or_else(|| anyhow!("Hard link to {}, which is not in the archive", target));
+            members.push(ArchiveMember { path, digests });
+        }
+    }
+
+    Ok(members)
+}
+
+fn hash_zip<R: Read + Seek>(
+    reader: R,
+    algorithms: &[Algorithm],
+    length: Option<usize>,
+) -> Result<Vec<ArchiveMember>> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let mut members = Vec::new();
+
+    for index in 0..archive.len() {
+        // Read the name from the central directory, so it is known even if
+        // the member itself cannot be opened
+        let path = archive
+            .name_for_index(index)
+            .map(member_path)
+            .unwrap_or_else(|| format!("#{}", index));
+
+        let digests = match archive.by_index(index) {
+            Ok(member) if member.is_dir() || member.is_symlink() => continue,
+            Ok(member) => hashers_for(algorithms, length)
+                .and_then(|hashers| hash_reader_multi(hashers, member)),
+            Err(e) => Err(anyhow!(e)),
+        };
+        members.push(ArchiveMember { path, digests });
+    }
+
+    Ok(members)
+}
@@ -0,0 +1,271 @@
+use crate::algorithm::Algorithm;
+use crate::walk::WalkOptions;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Database format version written by this build
+const FORMAT_VERSION: u32 = 1;
+
+/// Which `baseline` subcommand to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaselineMode {
+    /// Scan and create a new database
+    Init,
+    /// Compare the filesystem against the database
+    Check,
+    /// Rescan and replace the recorded state
+    Update,
+}
+
+/// Attributes recorded for one file.
+/// Mode, owner and inode are only available on Unix and are `None` elsewhere.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub digest: String,
+    pub size: u64,
+    /// File type and permission bits (`st_mode`)
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    /// Modification time in nanoseconds since the Unix epoch
+    pub mtime_ns: Option<i64>,
+    pub inode: Option<u64>,
+}
+
+/// One attribute that differs between the baseline and the filesystem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AttributeChange {
+    /// `digest`, `size`, `mode`, `owner`, `mtime` or `inode`
+    pub attribute: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+impl FileRecord {
+    /// Build a record from a file's digest and metadata
+    pub fn new(digest: String, metadata: &fs::Metadata) -> Self {
+        let mtime_ns = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .and_then(|d| i64::try_from(d.as_nanos()).ok());
+
+        #[cfg(unix)]
+        let (mode, uid, gid, inode) = {
+            use std::os::unix::fs::MetadataExt;
+            (
+                Some(metadata.mode()),
+                Some(metadata.uid()),
+                Some(metadata.gid()),
+                Some(metadata.ino()),
+            )
+        };
+        #[cfg(not(unix))]
+        let (mode, uid, gid, inode) = (None, None, None, None);
+
+        FileRecord {
+            digest,
+            size: metadata.len(),
+            mode,
+            uid,
+            gid,
+            mtime_ns,
+            inode,
+        }
+    }
+
+    /// List the attributes of `current` that differ from this record,
+    /// in the order digest, size, mode, owner, mtime, inode
+    pub fn changes(&self, current: &FileRecord) -> Vec<AttributeChange> {
+        let mut changes = Vec::new();
+        let mut compare = |attribute: &'static str, old: String, new: String| {
+            if old != new {
+                changes.push(AttributeChange {
+                    attribute,
+                    old,
+                    new,
+                });
+            }
+        };
+
+        compare("digest", self.digest.clone(), current.digest.clone());
+        compare("size", self.size.to_string(), current.size.to_string());
+        compare("mode", format_mode(self.mode), format_mode(current.mode));
+        compare(
+            "owner",
+            format_owner(self.uid, self.gid),
+            format_owner(current.uid, current.gid),
+        );
+        compare(
+            "mtime",
+            format_mtime(self.mtime_ns),
+            format_mtime(current.mtime_ns),
+        );
+        compare(
+            "inode",
+            format_optional(self.inode),
+            format_optional(current.inode),
+        );
+
+        changes
+    }
+}
+
+fn format_optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+/// Format a mode in octal, like `ls`/`stat` (e.g. `100644`)
+fn format_mode(mode: Option<u32>) -> String {
+    mode.map_or_else(|| "-".to_string(), |m| format!("{:o}", m))
+}
+
+fn format_owner(uid: Option<u32>, gid: Option<u32>) -> String {
+    format!("{}:{}", format_optional(uid), format_optional(gid))
+}
+
+/// Format a modification time as `<seconds>.<nanoseconds>`
+fn format_mtime(mtime_ns: Option<i64>) -> String {
+    mtime_ns.map_or_else(
+        || "-".to_string(),
+        |ns| format!("{}.{:09}", ns / 1_000_000_000, ns % 1_000_000_000),
+    )
+}
+
+/// A baseline database: the scan settings plus one record per file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub algorithm: String,
+    /// Absolute paths of the scanned directories
+    pub roots: Vec<String>,
+    pub exclude: Vec<String>,
+    pub hidden: bool,
+    pub follow_symlinks: bool,
+    /// Records keyed by absolute file path
+    pub files: BTreeMap<String, FileRecord>,
+}
+
+impl Baseline {
+    /// Create a database for the given scan settings
+    pub fn new(algorithm: Algorithm, roots: Vec<String>, walk: &WalkOptions) -> Self {
+        Baseline {
+            version: FORMAT_VERSION,
+            algorithm: algorithm.name().to_string(),
+            roots,
+            exclude: walk.exclude.clone(),
+            hidden: walk.hidden,
+            follow_symlinks: walk.follow_symlinks,
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Read a database written by `save`
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read(path)
+            .with_context(|| format!("Failed to read baseline: {}", path.display()))?;
+        let baseline: Baseline = serde_json::from_slice(&contents)
+            .with_context(|| format!("Invalid baseline database: {}", path.display()))?;
+
+        if baseline.version != FORMAT_VERSION {
+            return Err(anyhow!(
+                "Unsupported baseline version {} in {} (expected {})",
+                baseline.version,
+                path.display(),
+                FORMAT_VERSION
+            ));
+        }
+
+        Ok(baseline)
+    }
+
+    /// Write the database atomically: a temporary file next to `path` is
+    /// renamed over it, so an interrupted run never leaves a truncated database
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| anyhow!("Invalid baseline path: {}", path.display()))?;
+        let tmp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+        let write = || -> Result<()> {
+            let mut file = fs::File::create(&tmp_path)?;
+            serde_json::to_writer(&mut file, self)?;
+            file.write_all(b"\n")?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, path)?;
+            Ok(())
+        };
+
+        write().map_err(|e| {
+            let _ = fs::remove_file(&tmp_path);
+            e.context(format!("Failed to write baseline: {}", path.display()))
+        })
+    }
+
+    /// Algorithm the digests were recorded with
+    pub fn algorithm(&self) -> Result<Algorithm> {
+        self.algorithm.parse()
+    }
+
+    /// Walk options that reproduce the original scan
+    pub fn walk_options(&self) -> WalkOptions {
+        WalkOptions {
+            exclude: self.exclude.clone(),
+            hidden: self.hidden,
+            follow_symlinks: self.follow_symlinks,
+            ..WalkOptions::default()
+        }
+    }
+}
+
+/// How a file differs from the baseline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BaselineChange {
+    Changed(Vec<AttributeChange>),
+    Added,
+    Removed,
+    /// The file could not be hashed or stat'ed; holds the error
+    Unreadable(String),
+}
+
+/// Compare a fresh scan with the recorded files. `errors` maps the files the
+/// scan could not read to the error, so they are not mistaken for removed
+/// files. Unchanged files are omitted; the result is sorted by path.
+pub fn compare(
+    recorded: &BTreeMap<String, FileRecord>,
+    current: &BTreeMap<String, FileRecord>,
+    errors: &BTreeMap<String, String>,
+) -> Vec<(String, BaselineChange)> {
+    let mut differences = Vec::new();
+
+    for (path, old) in recorded {
+        match current.get(path) {
+            Some(new) => {
+                let changes = old.changes(new);
+                if !changes.is_empty() {
+                    differences.push((path.clone(), BaselineChange::Changed(changes)));
+                }
+            }
+            None if errors.contains_key(path) => {}
+            None => differences.push((path.clone(), BaselineChange::Removed)),
+        }
+    }
+
+    for (path, error) in errors {
+        differences.push((path.clone(), BaselineChange::Unreadable(error.clone())));
+    }
+
+    for path in current.keys() {
+        if !recorded.contains_key(path) {
+            differences.push((path.clone(), BaselineChange::Added));
+        }
+    }
+
+    differences.sort_by(|a, b| a.0.cmp(&b.0));
+    differences
+}
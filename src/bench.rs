@@ -0,0 +1,291 @@
+use crate::algorithm::Algorithm;
+use crate::io::{hash_file_io, IoMode};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::hint::black_box;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// In-memory buffer sizes measured by default: short messages, one read
+/// chunk and large buffers
+pub const DEFAULT_BUFFER_SIZES: [usize; 4] = [64, 1024, 64 * 1024, 1024 * 1024];
+
+/// Size of the temp file measured by default
+pub const DEFAULT_FILE_SIZE: u64 = 64 * 1024 * 1024; // 64 MiB
+
+/// Bytes hashed between two reads of the clock, so that timing small
+/// buffers does not mostly measure the clock
+const BATCH_BYTES: usize = 1024 * 1024; // 1 MiB
+
+/// What this machine offers for hashing
+#[derive(Debug, Clone, Serialize)]
+pub struct HostInfo {
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub threads: usize,
+    /// CPU extensions that speed up some algorithms, e.g. `sha-ni` for
+    /// SHA-1 and SHA-256 or `avx512f` for BLAKE3
+    pub cpu_features: Vec<&'static str>,
+}
+
+impl HostInfo {
+    pub fn detect() -> Self {
+        HostInfo {
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            cpu_features: cpu_features(),
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn cpu_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if is_x86_feature_detected!("sha") {
+        features.push("sha-ni");
+    }
+    if is_x86_feature_detected!("avx2") {
+        features.push("avx2");
+    }
+    if is_x86_feature_detected!("avx512f") {
+        features.push("avx512f");
+    }
+    features
+}
+
+#[cfg(target_arch = "aarch64")]
+fn cpu_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if std::arch::is_aarch64_feature_detected!("sha2") {
+        features.push("sha2");
+    }
+    if std::arch::is_aarch64_feature_detected!("sha3") {
+        features.push("sha3");
+    }
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        features.push("neon");
+    }
+    features
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn cpu_features() -> Vec<&'static str> {
+    Vec::new()
+}
+
+/// Result of timing one algorithm on one kind of input
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Measurement {
+    pub iterations: u64,
+    pub bytes: u64,
+    pub seconds: f64,
+    /// Throughput in megabytes (10^6 bytes) per second
+    pub mb_per_sec: f64,
+}
+
+impl Measurement {
+    /// Call `run`, which hashes `bytes_per_run` bytes, until `duration` has
+    /// passed, reading the clock every `batch` calls. One untimed call
+    /// first takes page faults and lazy setup out of the numbers.
+    fn time(
+        duration: Duration,
+        batch: u64,
+        bytes_per_run: u64,
+        mut run: impl FnMut() -> Result<()>,
+    ) -> Result<Self> {
+        run()?;
+
+        let start = Instant::now();
+        let mut iterations = 0u64;
+        let elapsed = loop {
+            for _ in 0..batch {
+                run()?;
+            }
+            iterations += batch;
+            let elapsed = start.elapsed();
+            if elapsed >= duration {
+                break elapsed;
+            }
+        };
+
+        let seconds = elapsed.as_secs_f64();
+        let bytes = iterations * bytes_per_run;
+        Ok(Measurement {
+            iterations,
+            bytes,
+            seconds,
+            mb_per_sec: bytes as f64 / seconds / 1e6,
+        })
+    }
+}
+
+/// Throughput of one algorithm on in-memory buffers of one size
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryResult {
+    pub algo: String,
+    pub buffer_size: usize,
+    #[serde(flatten)]
+    pub measurement: Measurement,
+}
+
+/// Throughput of one algorithm on the temp file with one I/O mode
+#[derive(Debug, Clone, Serialize)]
+pub struct FileResult {
+    pub algo: String,
+    pub io: String,
+    #[serde(flatten)]
+    pub measurement: Measurement,
+}
+
+/// Hash a buffer of `buffer_size` bytes over and over for `duration`. Every
+/// run starts a new hasher, as hashing many separate messages would.
+pub fn bench_memory(
+    algorithm: Algorithm,
+    buffer_size: usize,
+    duration: Duration,
+) -> Result<MemoryResult> {
+    let data: Vec<u8> = (0..buffer_size).map(|i| (i % 251) as u8).collect();
+    let batch = (BATCH_BYTES / buffer_size.max(1)).max(1) as u64;
+
+    let measurement = Measurement::time(duration, batch, buffer_size as u64, || {
+        let mut hasher = algorithm.hasher();
+        hasher.update(black_box(&data));
+        black_box(hasher.finalize());
+        Ok(())
+    })?;
+
+    Ok(MemoryResult {
+        algo: algorithm.name().to_string(),
+        buffer_size,
+        measurement,
+    })
+}
+
+/// Hash the file at `path` over and over with the given I/O mode for
+/// `duration`. The file stays in the page cache after the first run, so
+/// this measures getting its bytes to the hasher rather than the disk.
+pub fn bench_file(
+    algorithm: Algorithm,
+    path: &Path,
+    io_name: &str,
+    io: IoMode,
+    duration: Duration,
+) -> Result<FileResult> {
+    let path_str = path.to_str().context("Temp file path is not valid UTF-8")?;
+    let size = fs::metadata(path)?.len();
+
+    let measurement = Measurement::time(duration, 1, size, || {
+        black_box(hash_file_io(&[algorithm], path_str, None, io)?);
+        Ok(())
+    })?;
+
+    Ok(FileResult {
+        algo: algorithm.name().to_string(),
+        io: io_name.to_string(),
+        measurement,
+    })
+}
+
+/// Check that `io` can read the file at `path`, e.g. that the kernel
+/// allows io_uring
+pub fn check_io_mode(path: &Path, io: IoMode) -> Result<()> {
+    let path_str = path.to_str().context("Temp file path is not valid UTF-8")?;
+    hash_file_io(&[Algorithm::Blake3], path_str, None, io)?;
+    Ok(())
+}
+
+/// Attempts at finding an unused temp file name before giving up
+const CREATE_ATTEMPTS: u32 = 16;
+
+/// Temp file for the file benchmarks, deleted when dropped
+pub struct BenchFile {
+    path: PathBuf,
+}
+
+impl BenchFile {
+    /// Write `size` bytes to a new file in `dir`.
+    ///
+    /// The name has a random suffix and the file is created exclusively, so
+    /// a file or symlink planted in a shared temp dir is never written to or
+    /// deleted.
+    pub fn create(dir: &Path, size: u64) -> Result<Self> {
+        let (mut file, bench_file) = Self::create_new(dir)?;
+
+        let block: Vec<u8> = (0..BATCH_BYTES).map(|i| (i % 251) as u8).collect();
+        let mut remaining = size;
+        while remaining > 0 {
+            let n = remaining.min(block.len() as u64) as usize;
+            file.write_all(&block[..n])?;
+            remaining -= n as u64;
+        }
+        // Keep writeback of the new file from overlapping the measurements
+        file.sync_all()?;
+
+        Ok(bench_file)
+    }
+
+    /// Create an empty file under an unused name in `dir`. The guard that
+    /// deletes it is only built once the file is known to be ours.
+    fn create_new(dir: &Path) -> Result<(File, Self)> {
+        let mut attempts = 0;
+        loop {
+            let path = dir.join(format!(
+                ".hashy-bench-{}-{:016x}.tmp",
+                std::process::id(),
+                random_suffix()
+            ));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok((file, BenchFile { path })),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists && attempts < CREATE_ATTEMPTS => {
+                    attempts += 1;
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!("Failed to create benchmark file: {}", path.display())
+                    })
+                }
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for BenchFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Unpredictable number for temp file names. `RandomState` is seeded from
+/// the OS, which saves a dependency on a random number crate.
+fn random_suffix() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    if let Ok(elapsed) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        hasher.write_u128(elapsed.as_nanos());
+    }
+    hasher.finish()
+}
+
+/// Short label for a byte count: `64 B`, `1 KiB`, `64 MiB`
+pub fn size_label(bytes: u64) -> String {
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * 1024;
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    match bytes {
+        0 => "0 B".to_string(),
+        b if b % GIB == 0 => format!("{} GiB", b / GIB),
+        b if b % MIB == 0 => format!("{} MiB", b / MIB),
+        b if b % KIB == 0 => format!("{} KiB", b / KIB),
+        b => format!("{} B", b),
+    }
+}
@@ -0,0 +1,397 @@
+use crate::algorithm::Algorithm;
+use crate::hash::hash_data;
+use crate::io::{hash_file_io, IoMode};
+use crate::manifest::normalize_path;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Directory of the current entry layout inside the cache
+const ENTRIES_DIR: &str = "v1";
+
+/// Directory for entries being written
+const TMP_DIR: &str = "tmp";
+
+/// Files modified this recently are hashed but not cached: a write landing
+/// in the same mtime tick as the one we saw would otherwise go unnoticed
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Leftover temporary files older than this are removed by `prune`
+const STALE_TMP_AGE: Duration = Duration::from_secs(3600);
+
+/// Which `cache` subcommand to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    Stats,
+    Prune,
+    Clear,
+}
+
+/// What a file's metadata says about its contents. Two equal states are
+/// assumed to mean identical contents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileState {
+    /// `<device>:<inode>` on Unix, the canonical path elsewhere
+    file_id: String,
+    size: u64,
+    /// Modification time in nanoseconds since the Unix epoch
+    mtime_ns: i64,
+}
+
+impl FileState {
+    /// Read the state of a file
+    fn of(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path)?;
+
+        #[cfg(unix)]
+        let (file_id, mtime_ns) = {
+            use std::os::unix::fs::MetadataExt;
+            (
+                format!("{}:{}", metadata.dev(), metadata.ino()),
+                metadata.mtime() * 1_000_000_000 + metadata.mtime_nsec(),
+            )
+        };
+        #[cfg(not(unix))]
+        let (file_id, mtime_ns) = {
+            let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?;
+            (
+                fs::canonicalize(path)?.to_string_lossy().into_owned(),
+                i64::try_from(modified.as_nanos())?,
+            )
+        };
+
+        Ok(FileState {
+            file_id,
+            size: metadata.len(),
+            mtime_ns,
+        })
+    }
+
+    /// Whether the file was modified within `RACY_WINDOW` of `now`
+    fn is_racy(&self, now: SystemTime) -> bool {
+        let now_ns = now
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as i64);
+        now_ns - self.mtime_ns < RACY_WINDOW.as_nanos() as i64
+    }
+}
+
+/// One cached digest, stored as a small JSON file
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Path the digest was computed for (used by `prune`)
+    path: String,
+    #[serde(flatten)]
+    state: FileState,
+    algorithm: String,
+    /// Digest size in bytes
+    length: usize,
+    digest: String,
+}
+
+/// Summary of the cache contents
+#[derive(Debug, Default, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    /// Total size of the entry files
+    pub bytes: u64,
+    /// Number of entries per algorithm
+    pub algorithms: BTreeMap<String, usize>,
+}
+
+/// Persistent digest cache keyed by (device, inode, size, mtime_ns, algorithm)
+///
+/// Every entry is a separate file, written to a temporary file and renamed
+/// into place, so several hashy processes can share a cache without locking:
+/// readers see either a complete entry or none. Entries whose metadata no
+/// longer matches their file are never used and are removed by `prune`.
+pub struct DigestCache {
+    dir: PathBuf,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl DigestCache {
+    /// Open (creating if needed) the cache in `dir`
+    pub fn open(dir: &Path) -> Result<Self> {
+        for sub in [ENTRIES_DIR, TMP_DIR] {
+            fs::create_dir_all(dir.join(sub))
+                .with_context(|| format!("Failed to create cache directory: {}", dir.display()))?;
+        }
+
+        Ok(DigestCache {
+            dir: dir.to_path_buf(),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        })
+    }
+
+    /// Open a cache that `open` already created in `dir`, without creating
+    /// anything, for the `cache` maintenance commands. Fails if `dir` does
+    /// not hold a cache, so a mistyped path is reported rather than filled.
+    pub fn open_existing(dir: &Path) -> Result<Self> {
+        if !dir.is_dir() {
+            return Err(anyhow!("Cache directory does not exist: {}", dir.display()));
+        }
+        if !dir.join(ENTRIES_DIR).is_dir() || !dir.join(TMP_DIR).is_dir() {
+            return Err(anyhow!("Not a digest cache directory: {}", dir.display()));
+        }
+
+        Ok(DigestCache {
+            dir: dir.to_path_buf(),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        })
+    }
+
+    /// Number of files answered entirely from the cache
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of files that had to be read
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Location of the entry for a file state and algorithm, sharded by the
+    /// first two hex digits of the key like git's object store
+    fn entry_path(&self, state: &FileState, algorithm: Algorithm, length: usize) -> PathBuf {
+        let key = format!(
+            "{}\0{}\0{}\0{}\0{}",
+            state.file_id,
+            state.size,
+            state.mtime_ns,
+            algorithm.name(),
+            length
+        );
+        let name = hex::encode(hash_data(Algorithm::Sha256, key.as_bytes()));
+        self.dir.join(ENTRIES_DIR).join(&name[..2]).join(&name[2..])
+    }
+
+    /// Look up a digest; any unreadable or mismatching entry is a miss
+    fn lookup(&self, state: &FileState, algorithm: Algorithm, length: usize) -> Option<Vec<u8>> {
+        let contents = fs::read(self.entry_path(state, algorithm, length)).ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&contents).ok()?;
+
+        if entry.state != *state || entry.algorithm != algorithm.name() || entry.length != length {
+            return None;
+        }
+
+        hex::decode(&entry.digest)
+            .ok()
+            .filter(|digest| digest.len() == length)
+    }
+
+    /// Store a digest atomically
+    fn store(&self, path: &Path, entry: &CacheEntry, algorithm: Algorithm) -> Result<()> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let target = self.entry_path(&entry.state, algorithm, entry.length);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let tmp = self.dir.join(TMP_DIR).join(format!(
+            "{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let result = (|| -> Result<()> {
+            let mut file = fs::File::create(&tmp)?;
+            serde_json::to_writer(&mut file, entry)?;
+            file.flush()?;
+            fs::rename(&tmp, &target)?;
+            Ok(())
+        })();
+
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        result.with_context(|| format!("Failed to write cache entry for {}", path.display()))
+    }
+
+    /// Hash a file with several algorithms, reusing cached digests.
+    ///
+    /// The file is only read when some digest is missing, and then only for
+    /// the missing algorithms. New digests are cached unless the file changed
+    /// while it was read or was modified too recently to trust its mtime.
+    /// Failing to write the cache never fails the hash.
+    pub fn hash_file(
+        &self,
+        algorithms: &[Algorithm],
+        file_path: &str,
+        length: Option<usize>,
+        io: IoMode,
+    ) -> Result<Vec<Vec<u8>>> {
+        let path = Path::new(file_path);
+        let started = SystemTime::now();
+
+        let Ok(state) = FileState::of(path) else {
+            // Let the regular code path report the error
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return hash_file_io(algorithms, file_path, length, io).map(|(digests, _)| digests);
+        };
+
+        let mut digests = Vec::with_capacity(algorithms.len());
+        let mut missing = Vec::new();
+        for algorithm in algorithms {
+            let size = algorithm.resolve_output_size(length)?;
+            let cached = self.lookup(&state, *algorithm, size);
+            if cached.is_none() {
+                missing.push(*algorithm);
+            }
+            digests.push(cached);
+        }
+
+        if missing.is_empty() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(digests.into_iter().flatten().collect());
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let (computed, _) = hash_file_io(&missing, file_path, length, io)?;
+
+        let cacheable =
+            !state.is_racy(started) && FileState::of(path).is_ok_and(|after| after == state);
+
+        // Recorded so `prune` can find the file from any working directory
+        let absolute_path = normalize_path(path).map_or_else(
+            |_| file_path.to_string(),
+            |p| p.to_string_lossy().into_owned(),
+        );
+
+        let mut computed = missing.into_iter().zip(computed);
+        let digests = digests
+            .into_iter()
+            .map(|cached| match cached {
+                Some(digest) => digest,
+                None => {
+                    let (algorithm, digest) = computed.next().expect("one digest per algorithm");
+                    if cacheable {
+                        let entry = CacheEntry {
+                            path: absolute_path.clone(),
+                            state: state.clone(),
+                            algorithm: algorithm.name().to_string(),
+                            length: digest.len(),
+                            digest: hex::encode(&digest),
+                        };
+                        let _ = self.store(path, &entry, algorithm);
+                    }
+                    digest
+                }
+            })
+            .collect();
+
+        Ok(digests)
+    }
+
+    /// Paths of every entry file
+    fn entry_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let entries_dir = self.dir.join(ENTRIES_DIR);
+
+        for shard in fs::read_dir(&entries_dir)
+            .with_context(|| format!("Failed to read cache: {}", self.dir.display()))?
+        {
+            let shard = shard?.path();
+            if !shard.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&shard)? {
+                files.push(entry?.path());
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    /// Count entries, their size on disk and entries per algorithm
+    pub fn stats(&self) -> Result<CacheStats> {
+        let mut stats = CacheStats::default();
+
+        for file in self.entry_files()? {
+            // Entries may disappear while another process prunes the cache
+            let Ok(contents) = fs::read(&file) else {
+                continue;
+            };
+            stats.entries += 1;
+            stats.bytes += contents.len() as u64;
+
+            let algorithm = serde_json::from_slice::<CacheEntry>(&contents)
+                .map_or_else(|_| "invalid".to_string(), |entry| entry.algorithm);
+            *stats.algorithms.entry(algorithm).or_insert(0) += 1;
+        }
+
+        Ok(stats)
+    }
+
+    /// Remove entries whose file was deleted or changed, unreadable entries
+    /// and stale temporary files. Returns the number of entries removed.
+    pub fn prune(&self) -> Result<usize> {
+        let mut removed = 0;
+
+        for file in self.entry_files()? {
+            let valid = fs::read(&file)
+                .ok()
+                .and_then(|contents| serde_json::from_slice::<CacheEntry>(&contents).ok())
+                .is_some_and(|entry| {
+                    FileState::of(Path::new(&entry.path)).is_ok_and(|state| state == entry.state)
+                });
+
+            if !valid && fs::remove_file(&file).is_ok() {
+                removed += 1;
+            }
+        }
+
+        let now = SystemTime::now();
+        for tmp in fs::read_dir(self.dir.join(TMP_DIR))? {
+            let tmp = tmp?.path();
+            let stale = fs::metadata(&tmp)
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| {
+                    now.duration_since(modified)
+                        .is_ok_and(|age| age > STALE_TMP_AGE)
+                });
+            if stale {
+                let _ = fs::remove_file(&tmp);
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Remove every entry. Returns the number of entries removed.
+    pub fn clear(&self) -> Result<usize> {
+        let mut removed = 0;
+
+        for file in self.entry_files()? {
+            if fs::remove_file(&file).is_ok() {
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+/// Hash a file with several algorithms, through the cache when one is given
+pub fn hash_file_cached(
+    cache: Option<&DigestCache>,
+    algorithms: &[Algorithm],
+    file_path: &str,
+    length: Option<usize>,
+    io: IoMode,
+) -> Result<Vec<Vec<u8>>> {
+    match cache {
+        Some(cache) => cache.hash_file(algorithms, file_path, length, io),
+        None => hash_file_io(algorithms, file_path, length, io).map(|(digests, _)| digests),
+    }
+}
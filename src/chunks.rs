@@ -0,0 +1,115 @@
+use crate::algorithm::Algorithm;
+use crate::hash::hash_data;
+use anyhow::{anyhow, Context, Result};
+use fastcdc::v2020::{self, StreamCDC};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::File;
+
+/// Chunk size bounds for FastCDC, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkSizes {
+    pub min: u32,
+    pub avg: u32,
+    pub max: u32,
+}
+
+impl ChunkSizes {
+    /// Check the sizes against each other and the limits of FastCDC
+    pub fn validate(&self) -> Result<()> {
+        let check = |name: &str, value: u32, lower: u32, upper: u32| {
+            if value < lower || value > upper {
+                Err(anyhow!(
+                    "--{}-size must be between {} and {} bytes, got {}",
+                    name,
+                    lower,
+                    upper,
+                    value
+                ))
+            } else {
+                Ok(())
+            }
+        };
+        check("min", self.min, v2020::MINIMUM_MIN, v2020::MINIMUM_MAX)?;
+        check("avg", self.avg, v2020::AVERAGE_MIN, v2020::AVERAGE_MAX)?;
+        check("max", self.max, v2020::MAXIMUM_MIN, v2020::MAXIMUM_MAX)?;
+
+        if self.min > self.avg || self.avg > self.max {
+            return Err(anyhow!(
+                "Chunk sizes must satisfy min <= avg <= max, got {} / {} / {}",
+                self.min,
+                self.avg,
+                self.max
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// One content-defined chunk of a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Chunk {
+    pub offset: u64,
+    pub length: usize,
+    /// Hex-encoded digest of the chunk's bytes
+    pub digest: String,
+}
+
+/// Split a file into chunks with FastCDC (2020) and hash each chunk.
+/// `sizes` must have been validated.
+pub fn chunk_file(file_path: &str, algorithm: Algorithm, sizes: ChunkSizes) -> Result<Vec<Chunk>> {
+    let file =
+        File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
+
+    StreamCDC::new(file, sizes.min, sizes.avg, sizes.max)
+        .map(|chunk| {
+            let chunk = chunk.with_context(|| format!("Failed to read file: {}", file_path))?;
+            Ok(Chunk {
+                offset: chunk.offset,
+                length: chunk.length,
+                digest: hex::encode(hash_data(algorithm, &chunk.data)),
+            })
+        })
+        .collect()
+}
+
+/// Totals across every chunked file
+#[derive(Debug, Default, Serialize)]
+pub struct DedupSummary {
+    pub files: usize,
+    pub chunks: usize,
+    pub unique_chunks: usize,
+    pub total_bytes: u64,
+    /// Bytes left after storing every distinct chunk once
+    pub unique_bytes: u64,
+    /// `total_bytes / unique_bytes` (1.0 when there is nothing to store)
+    pub dedup_ratio: f64,
+}
+
+impl DedupSummary {
+    /// Count chunks, treating chunks with the same digest as one
+    pub fn from_chunks<'a>(files: usize, chunks: impl IntoIterator<Item = &'a Chunk>) -> Self {
+        let mut summary = DedupSummary {
+            files,
+            ..DedupSummary::default()
+        };
+        let mut seen = HashSet::new();
+
+        for chunk in chunks {
+            summary.chunks += 1;
+            summary.total_bytes += chunk.length as u64;
+            if seen.insert(chunk.digest.as_str()) {
+                summary.unique_chunks += 1;
+                summary.unique_bytes += chunk.length as u64;
+            }
+        }
+
+        summary.dedup_ratio = if summary.unique_bytes == 0 {
+            1.0
+        } else {
+            summary.total_bytes as f64 / summary.unique_bytes as f64
+        };
+        summary
+    }
+}
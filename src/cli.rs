@@ -1,4 +1,16 @@
-use clap::{Parser, Subcommand};
+use crate::baseline::BaselineMode;
+use crate::bench::DEFAULT_FILE_SIZE;
+use crate::cache::CacheMode;
+use crate::chunks::ChunkSizes;
+use crate::command::{
+    AuditArgs, BenchArgs, DupesArgs, HashArgs, HmacArgs, MerkleArgs, VerifyArgs, VerifyOptions,
+};
+use crate::mac::KeySource;
+use crate::merkle::MerkleMode;
+use crate::tree::TreeOptions;
+use crate::walk::WalkOptions;
+use clap::{ArgGroup, Parser, Subcommand};
+use zeroize::Zeroizing;
 
 /// A fast, flexible CLI for hashing with multiple algorithms
 #[derive(Parser, Debug)]
@@ -21,10 +33,16 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Compute hash of input (text, file, or stdin)
+    #[command(group(ArgGroup::new("walk").args(["recursive", "tree"]).multiple(true)))]
+    #[command(group(ArgGroup::new("batch").args(["files", "archive"])))]
     Hash {
         /// Hash algorithm to use
         ///
-        /// Supported algorithms: sha256 (default), sha512, blake3
+        /// Supported algorithms: sha256 (default), sha512, blake3, sha3-224,
+        /// sha3-256, sha3-384, sha3-512, keccak256, keccak512, shake128, shake256
+        ///
+        /// Several algorithms can be given comma-separated or by repeating
+        /// --algo; each input is then read once and fed to every hasher.
         ///
         /// Insecure algorithms (sha1, md5) require --allow-insecure flag.
         /// WARNING: SHA-1 and MD5 are cryptographically broken and should
@@ -33,10 +51,11 @@ pub enum Commands {
             short,
             long,
             value_name = "ALGORITHM",
+            value_delimiter = ',',
             default_value = "sha256",
-            help = "Hash algorithm to use [possible values: sha256, sha512, blake3, sha1, md5]"
+            help = "Hash algorithm(s) to use, comma-separated or repeated [possible values: sha256, sha512, blake3, sha3-224, sha3-256, sha3-384, sha3-512, keccak256, keccak512, shake128, shake256, sha1, md5]"
         )]
-        algo: String,
+        algo: Vec<String>,
 
         /// Allow use of insecure algorithms (SHA-1 and MD5)
         ///
@@ -46,6 +65,10 @@ pub enum Commands {
         #[arg(long)]
         allow_insecure: bool,
 
+        /// Digest length in bytes (blake3, shake128 and shake256 only)
+        #[arg(long, value_name = "BYTES")]
+        length: Option<usize>,
+
         /// Text to hash directly
         #[arg(short, long, value_name = "TEXT", conflicts_with_all = &["file", "files"])]
         text: Option<String>,
@@ -73,31 +96,213 @@ pub enum Commands {
         /// Output results as JSON
         #[arg(long, conflicts_with = "format")]
         json: bool,
+
+        /// Print BSD-style tagged lines: `SHA256 (FILE) = DIGEST`
+        ///
+        /// With --manifest, every algorithm is written to the one manifest.
+        #[arg(long, conflicts_with_all = &["json", "text"])]
+        tag: bool,
+
+        /// Recurse into directories given as positional arguments
+        #[arg(short, long, requires = "files")]
+        recursive: bool,
+
+        /// Skip files and directories matching a glob (repeatable)
+        #[arg(long, value_name = "GLOB", requires = "walk")]
+        exclude: Vec<String>,
+
+        /// Only hash files matching a glob (repeatable)
+        #[arg(long, value_name = "GLOB", requires = "recursive")]
+        include: Vec<String>,
+
+        /// Maximum directory depth to descend into
+        #[arg(long, value_name = "DEPTH", requires = "recursive")]
+        max_depth: Option<usize>,
+
+        /// Include hidden files and directories
+        #[arg(long, requires = "recursive")]
+        hidden: bool,
+
+        /// Follow symbolic links (skipped by default)
+        #[arg(long, requires = "recursive")]
+        follow_symlinks: bool,
+
+        /// Write a sha256sum-compatible manifest to FILE
+        ///
+        /// Paths are recorded relative to the manifest's directory so the
+        /// manifest can be checked with `hashy verify`. With several
+        /// algorithms one manifest is written per algorithm: `{algo}` in FILE
+        /// is replaced by the algorithm name, otherwise `.<algo>` is appended.
+        #[arg(long, value_name = "FILE", requires = "batch")]
+        manifest: Option<String>,
+
+        /// Reuse digests of unchanged files from a cache directory
+        ///
+        /// Files are looked up by device, inode, size, modification time and
+        /// algorithm, and only re-read when one of those changed.
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Reuse digests of unchanged files from a cache directory [env: HASHY_CACHE]"
+        )]
+        cache: Option<String>,
+
+        /// Store each digest in a `user.hashy.<algo>` extended attribute
+        ///
+        /// The attribute also records the algorithm and the file's mtime at
+        /// hashing time, so `hashy verify --xattr` can check the file later
+        /// without a manifest.
+        #[arg(long, conflicts_with = "text")]
+        write_xattr: bool,
+
+        /// Compute one deterministic digest for a whole directory
+        ///
+        /// Covers every name, file content, executable bit and symlink
+        /// target under DIR, hidden files included, independently of the
+        /// order the filesystem lists them in.
+        #[arg(
+            long,
+            value_name = "DIR",
+            conflicts_with_all = &["text", "file", "files", "write_xattr"]
+        )]
+        tree: Option<String>,
+
+        /// Hash every file inside a tar, tar.gz, tar.xz or zip archive
+        ///
+        /// Members are streamed from the archive without unpacking it and
+        /// printed like recursive output (`<DIGEST>  <PATH>`), so the result
+        /// can be compared with a manifest of the extracted tree.
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = &["text", "file", "files", "tree", "write_xattr"]
+        )]
+        archive: Option<String>,
+
+        /// Leave executable bits out of the --tree digest
+        #[arg(long, requires = "tree")]
+        no_permissions: bool,
+
+        /// Leave symlink targets out of the --tree digest
+        #[arg(long, requires = "tree")]
+        no_symlink_targets: bool,
+
+        /// Also hash --file in fixed-size pieces of BYTES each
+        ///
+        /// Prints one digest per piece after the whole-file digest, like the
+        /// piecewise mode of dcfldd and hashdeep. Save the --json output to
+        /// find corrupted byte ranges later with `hashy verify --pieces`.
+        #[arg(
+            long,
+            value_name = "BYTES",
+            requires = "file",
+            conflicts_with_all = &["format", "tag", "write_xattr"]
+        )]
+        piece_size: Option<u64>,
+
+        /// Only hash --file from this byte offset on
+        #[arg(
+            long,
+            value_name = "BYTES",
+            requires = "file",
+            conflicts_with_all = &["tag", "write_xattr", "piece_size"]
+        )]
+        offset: Option<u64>,
+
+        /// Only hash this many bytes of --file (default: up to the end)
+        ///
+        /// Named apart from --length, which sets the digest length.
+        #[arg(
+            long,
+            value_name = "BYTES",
+            requires = "file",
+            conflicts_with_all = &["tag", "write_xattr", "piece_size"]
+        )]
+        range_length: Option<u64>,
+
+        /// Hash the decompressed contents of gzip, zstd, xz or bzip2 input
+        ///
+        /// `auto` detects the format by its magic bytes and hashes
+        /// uncompressed input as it is; naming a format rejects anything
+        /// else. Applies to --file, STDIN and batch mode. The digest cache
+        /// is not used, so --cache is rejected and HASHY_CACHE ignored.
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            conflicts_with_all = &["text", "tree", "archive", "write_xattr", "piece_size", "offset", "range_length", "cache"]
+        )]
+        decompress: Option<String>,
+
+        /// How files are read: auto, mmap, uring or read [default: auto]
+        ///
+        /// `auto` memory-maps regular files of 1 MiB or more and reads
+        /// smaller ones in 64 KiB chunks. `uring` keeps several reads in
+        /// flight with io_uring (Linux only). Applies to --file and batch
+        /// mode.
+        #[arg(
+            long,
+            value_name = "MODE",
+            conflicts_with_all = &["text", "tree", "archive", "decompress", "piece_size", "offset", "range_length"]
+        )]
+        io: Option<String>,
     },
 
     /// Verify file integrity against checksum manifest
     Verify {
         /// Path to checksum manifest file
-        checksums_file: String,
+        #[arg(required_unless_present_any = &["xattr", "pieces"])]
+        checksums_file: Option<String>,
 
-        /// Hash algorithm to use
+        /// Verify files against digests stored in extended attributes by
+        /// `hash --write-xattr` instead of a manifest
+        ///
+        /// Directories are walked recursively. With --algo, only that
+        /// algorithm's attribute is checked.
+        #[arg(
+            long,
+            value_name = "FILE",
+            num_args = 1..,
+            conflicts_with_all = &["checksums_file", "format", "length", "ignore_missing", "strict", "warn", "jobs", "cache"]
+        )]
+        xattr: Vec<String>,
+
+        /// Check a file against a piece list saved from
+        /// `hash --file FILE --piece-size N --json`
+        ///
+        /// Reports the byte ranges whose pieces no longer match. FILE
+        /// defaults to the path recorded in the list.
+        #[arg(
+            long,
+            value_names = ["LIST", "FILE"],
+            num_args = 1..=2,
+            conflicts_with_all = &["checksums_file", "xattr", "algo", "format", "length", "ignore_missing", "strict", "warn", "jobs", "continue_on_error", "cache", "decompress", "io"]
+        )]
+        pieces: Vec<String>,
+
+        /// Hash algorithm of untagged lines, or `auto`
         ///
-        /// Supported algorithms: sha256 (default), sha512, blake3
+        /// `auto` infers the algorithm from the manifest file name (SHA256SUMS,
+        /// *.sha512, *.md5, *.b3) or the digest length, and fails if that is
+        /// ambiguous. Without --algo, detection is used but falls back to
+        /// sha256 when SHA-256 is one of several candidates.
         ///
         /// Insecure algorithms (sha1, md5) require --allow-insecure flag.
         #[arg(
             short,
             long,
             value_name = "ALGORITHM",
-            default_value = "sha256",
-            help = "Hash algorithm to use [possible values: sha256, sha512, blake3, sha1, md5]"
+            help = "Hash algorithm of untagged lines, or auto [default: auto, preferring sha256] [possible values: auto, sha256, sha512, blake3, sha3-224, sha3-256, sha3-384, sha3-512, keccak256, keccak512, shake128, shake256, sha1, md5]"
         )]
-        algo: String,
+        algo: Option<String>,
 
         /// Allow use of insecure algorithms (SHA-1 and MD5)
         #[arg(long)]
         allow_insecure: bool,
 
+        /// Digest length in bytes (blake3, shake128 and shake256 only)
+        #[arg(long, value_name = "BYTES")]
+        length: Option<usize>,
+
         /// Manifest digest format [possible values: hex, base64, raw]
         #[arg(long, value_name = "FORMAT")]
         format: Option<String>,
@@ -105,33 +310,540 @@ pub enum Commands {
         /// Continue verification even if some files fail
         #[arg(long)]
         continue_on_error: bool,
+
+        /// Don't fail or report status for missing files
+        #[arg(long)]
+        ignore_missing: bool,
+
+        /// Exit non-zero for improperly formatted checksum lines
+        #[arg(long)]
+        strict: bool,
+
+        /// Warn about improperly formatted checksum lines
+        #[arg(short, long)]
+        warn: bool,
+
+        /// Don't output anything; the exit code shows success
+        #[arg(long)]
+        status: bool,
+
+        /// Number of files to hash in parallel (default: all CPU cores)
+        #[arg(short, long, value_name = "N")]
+        jobs: Option<usize>,
+
+        // HASHY_CACHE is read by cache_dir rather than through clap, so an
+        // exported cache does not conflict with --xattr, --pieces or --decompress
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Reuse digests of unchanged files from a cache directory [env: HASHY_CACHE]"
+        )]
+        cache: Option<String>,
+
+        /// Check the decompressed contents of gzip, zstd, xz or bzip2 files
+        ///
+        /// When a listed file does not exist, a compressed copy next to it
+        /// (FILE.gz, FILE.zst, FILE.xz or FILE.bz2) is checked instead, so
+        /// upstream checksums of uncompressed images can verify compressed
+        /// copies. The digest cache is not used, so --cache is rejected and
+        /// HASHY_CACHE ignored.
+        #[arg(long, value_name = "FORMAT", conflicts_with_all = &["xattr", "pieces", "cache"])]
+        decompress: Option<String>,
+
+        /// How listed files are read: auto, mmap, uring or read [default: auto]
+        #[arg(
+            long,
+            value_name = "MODE",
+            conflicts_with_all = &["xattr", "pieces", "decompress"]
+        )]
+        io: Option<String>,
+    },
+
+    /// Audit a directory against a manifest: report unchanged, modified,
+    /// missing and new files
+    Audit {
+        /// Path to checksum manifest file
+        checksums_file: String,
+
+        /// Directory to audit
+        dir: String,
+
+        /// Hash algorithm of untagged lines, or `auto`
+        ///
+        /// Detected like `hashy verify` does. New files are hashed with this
+        /// algorithm.
+        #[arg(
+            short,
+            long,
+            value_name = "ALGORITHM",
+            help = "Hash algorithm of untagged lines, or auto [default: auto, preferring sha256]"
+        )]
+        algo: Option<String>,
+
+        /// Allow use of insecure algorithms (SHA-1 and MD5)
+        #[arg(long)]
+        allow_insecure: bool,
+
+        /// Digest length in bytes (blake3, shake128 and shake256 only)
+        #[arg(long, value_name = "BYTES")]
+        length: Option<usize>,
+
+        /// Output results as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Don't report files and directories matching a glob as new (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Include hidden files and directories
+        #[arg(long)]
+        hidden: bool,
+
+        /// Follow symbolic links (skipped by default)
+        #[arg(long)]
+        follow_symlinks: bool,
+    },
+
+    /// Record file digests and attributes in a baseline database and check
+    /// them for tampering
+    Baseline {
+        #[command(subcommand)]
+        action: BaselineAction,
+    },
+
+    /// Inspect or clean up the digest cache used by --cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Find files with identical contents
+    ///
+    /// Files are grouped by size, then by a hash of their first and last
+    /// 4 KiB, and only the remaining candidates are hashed in full.
+    Dupes {
+        /// Files and directories to search (directories are walked recursively)
+        #[arg(required = true)]
+        paths: Vec<String>,
+
+        /// Hash algorithm used to confirm duplicates
+        #[arg(short, long, value_name = "ALGORITHM", default_value = "blake3")]
+        algo: String,
+
+        /// Allow use of insecure algorithms (SHA-1 and MD5)
+        #[arg(long)]
+        allow_insecure: bool,
+
+        /// Ignore files smaller than BYTES (empty files are skipped by default)
+        #[arg(long, value_name = "BYTES", default_value_t = 1)]
+        min_size: u64,
+
+        /// Output duplicate groups as JSON
+        #[arg(long, conflicts_with = "script")]
+        json: bool,
+
+        /// Print a shell script that handles the duplicates instead of listing them
+        ///
+        /// `hardlink` replaces every duplicate with a hard link to the first
+        /// file of its group; `delete` removes every duplicate but the first.
+        /// Review the script before running it.
+        #[arg(long, value_name = "ACTION")]
+        script: Option<String>,
+
+        /// Skip files and directories matching a glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Only consider files matching a glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        include: Vec<String>,
+
+        /// Include hidden files and directories
+        #[arg(long)]
+        hidden: bool,
+
+        /// Follow symbolic links (skipped by default)
+        #[arg(long)]
+        follow_symlinks: bool,
+    },
+
+    /// Split files into content-defined chunks (FastCDC) and report how well
+    /// they deduplicate
+    Chunks {
+        /// Files and directories to chunk (directories are walked recursively)
+        #[arg(required = true)]
+        paths: Vec<String>,
+
+        /// Hash algorithm for chunk digests
+        #[arg(short, long, value_name = "ALGORITHM", default_value = "blake3")]
+        algo: String,
+
+        /// Allow use of insecure algorithms (SHA-1 and MD5)
+        #[arg(long)]
+        allow_insecure: bool,
+
+        /// Minimum chunk size in bytes
+        #[arg(long, value_name = "BYTES", default_value_t = 16 * 1024)]
+        min_size: u32,
+
+        /// Target average chunk size in bytes
+        #[arg(long, value_name = "BYTES", default_value_t = 64 * 1024)]
+        avg_size: u32,
+
+        /// Maximum chunk size in bytes
+        #[arg(long, value_name = "BYTES", default_value_t = 256 * 1024)]
+        max_size: u32,
+
+        /// Output every chunk (offset, length, digest) and the summary as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Skip files and directories matching a glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Include hidden files and directories
+        #[arg(long)]
+        hidden: bool,
+
+        /// Follow symbolic links (skipped by default)
+        #[arg(long)]
+        follow_symlinks: bool,
+    },
+
+    /// Build a Merkle tree over files and produce or check inclusion proofs
+    Merkle {
+        #[command(subcommand)]
+        action: MerkleAction,
+    },
+
+    /// Compute a keyed hash (HMAC or BLAKE3 keyed mode) of input
+    #[command(group(ArgGroup::new("key").required(true).args(["key_file", "key_env", "key_hex"])))]
+    Hmac {
+        /// MAC algorithm to use
+        ///
+        /// sha256 (default), sha512 and sha1 compute HMAC; blake3 uses BLAKE3's
+        /// native keyed mode and requires a 32-byte key.
+        #[arg(
+            short,
+            long,
+            value_name = "ALGORITHM",
+            default_value = "sha256",
+            help = "MAC algorithm to use [possible values: sha256, sha512, blake3, sha1]"
+        )]
+        algo: String,
+
+        /// Allow use of insecure algorithms (SHA-1)
+        #[arg(long)]
+        allow_insecure: bool,
+
+        /// Read the key (raw bytes) from FILE
+        #[arg(long, value_name = "FILE")]
+        key_file: Option<String>,
+
+        /// Read the key from environment variable VAR
+        #[arg(long, value_name = "VAR")]
+        key_env: Option<String>,
+
+        /// Hex-encoded key on the command line (requires --insecure-key-arg)
+        ///
+        /// WARNING: command-line arguments are visible to other users via the
+        /// process list and are often saved in shell history.
+        #[arg(long, value_name = "HEX")]
+        key_hex: Option<String>,
+
+        /// Allow passing the key on the command line with --key-hex
+        #[arg(long)]
+        insecure_key_arg: bool,
+
+        /// Text to authenticate directly
+        #[arg(short, long, value_name = "TEXT", conflicts_with_all = &["file", "files"])]
+        text: Option<String>,
+
+        /// File to authenticate (use positional arguments for multiple files)
+        #[arg(short, long, value_name = "FILE", conflicts_with = "files")]
+        file: Option<String>,
+
+        /// Files to authenticate in batch mode (positional arguments)
+        #[arg(conflicts_with_all = &["text", "file"])]
+        files: Vec<String>,
+
+        /// Check the computed tag against TAG (hex) in constant time
+        #[arg(long, value_name = "TAG", conflicts_with = "files")]
+        verify: Option<String>,
+
+        /// Continue processing even if some files fail
+        #[arg(long)]
+        continue_on_error: bool,
+
+        /// Output format [possible values: hex, base64, raw]
+        #[arg(long, value_name = "FORMAT", conflicts_with = "json")]
+        format: Option<String>,
+
+        /// Use uppercase letters in hex output
+        #[arg(long)]
+        uppercase: bool,
+
+        /// Output results as JSON
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+    },
+
+    /// Measure the throughput of each algorithm on this machine
+    ///
+    /// Every algorithm hashes in-memory buffers of several sizes, then a
+    /// temp file with each I/O mode. SHA-1 and MD5 are measured too, since
+    /// nothing is hashed for real.
+    Bench {
+        /// Algorithms to measure, comma-separated or repeated [default: all]
+        #[arg(short, long, value_name = "ALGORITHM", value_delimiter = ',')]
+        algo: Vec<String>,
+
+        /// In-memory buffer sizes in bytes, comma-separated [default: 64,1024,65536,1048576]
+        #[arg(long, value_name = "BYTES", value_delimiter = ',')]
+        sizes: Vec<usize>,
+
+        /// Size of the temp file in bytes; 0 skips the file benchmarks
+        #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_FILE_SIZE)]
+        file_size: u64,
+
+        /// I/O modes for the temp file, comma-separated [default: read, mmap and uring on Linux]
+        #[arg(long, value_name = "MODE", value_delimiter = ',')]
+        io: Vec<String>,
+
+        /// Directory for the temp file, to measure a particular disk [default: the system temp directory]
+        #[arg(long, value_name = "DIR")]
+        dir: Option<String>,
+
+        /// Time spent measuring each algorithm and size or I/O mode, in milliseconds
+        #[arg(long, value_name = "MS", default_value_t = 200)]
+        duration: u64,
+
+        /// Output results as JSON
+        #[arg(long)]
+        json: bool,
     },
 }
 
-/// Parameters returned from hash command
-type HashParams<'a> = (
-    &'a str,
-    bool,
-    Option<&'a str>,
-    Option<&'a str>,
-    &'a [String],
-    bool,
-    Option<&'a str>,
-    bool,
-    bool,
-);
+/// Subcommands of `hashy baseline`
+#[derive(Subcommand, Debug)]
+pub enum BaselineAction {
+    /// Scan directories and create a new baseline database
+    Init {
+        /// Baseline database to create
+        #[arg(long, value_name = "FILE")]
+        db: String,
+
+        /// Directories to monitor
+        #[arg(required = true)]
+        dirs: Vec<String>,
 
-/// Parameters returned from verify command
-type VerifyParams<'a> = (
-    &'a str,         // algo
+        /// Hash algorithm to record digests with
+        #[arg(
+            short,
+            long,
+            value_name = "ALGORITHM",
+            default_value = "sha256",
+            help = "Hash algorithm to record digests with [possible values: sha256, sha512, blake3, sha3-224, sha3-256, sha3-384, sha3-512, keccak256, keccak512, shake128, shake256, sha1, md5]"
+        )]
+        algo: String,
+
+        /// Allow use of insecure algorithms (SHA-1 and MD5)
+        #[arg(long)]
+        allow_insecure: bool,
+
+        /// Skip files and directories matching a glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Include hidden files and directories
+        #[arg(long)]
+        hidden: bool,
+
+        /// Follow symbolic links (skipped by default)
+        #[arg(long)]
+        follow_symlinks: bool,
+    },
+
+    /// Compare the filesystem against the baseline and report what changed
+    Check {
+        /// Baseline database to check against
+        #[arg(long, value_name = "FILE")]
+        db: String,
+
+        /// Output results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Rescan and record the current state as the new baseline
+    Update {
+        /// Baseline database to update
+        #[arg(long, value_name = "FILE")]
+        db: String,
+    },
+}
+
+/// Subcommands of `hashy merkle`
+#[derive(Subcommand, Debug)]
+pub enum MerkleAction {
+    /// Hash files, print the Merkle root and write the tree to a file
+    ///
+    /// Leaves are the sorted (path, digest) pairs of every file, with paths
+    /// relative to the tree file's directory.
+    Build {
+        /// Tree file to write; `merkle prove` reads it
+        #[arg(short, long, value_name = "FILE")]
+        output: String,
+
+        /// Files and directories to include (directories are walked recursively)
+        #[arg(required = true)]
+        paths: Vec<String>,
+
+        /// Hash algorithm for file digests and tree nodes
+        #[arg(
+            short,
+            long,
+            value_name = "ALGORITHM",
+            default_value = "sha256",
+            help = "Hash algorithm for file digests and tree nodes [possible values: sha256, sha512, blake3, sha3-224, sha3-256, sha3-384, sha3-512, keccak256, keccak512, shake128, shake256, sha1, md5]"
+        )]
+        algo: String,
+
+        /// Allow use of insecure algorithms (SHA-1 and MD5)
+        #[arg(long)]
+        allow_insecure: bool,
+
+        /// Skip files and directories matching a glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Include hidden files and directories
+        #[arg(long)]
+        hidden: bool,
+
+        /// Follow symbolic links (skipped by default)
+        #[arg(long)]
+        follow_symlinks: bool,
+    },
+
+    /// Produce an inclusion proof for one file of a tree
+    Prove {
+        /// Tree file written by `merkle build`
+        #[arg(long, value_name = "FILE")]
+        tree: String,
+
+        /// File to prove, as a path on disk
+        path: String,
+
+        /// Write the proof to FILE instead of standard output
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Check a file against an inclusion proof and a published root
+    Verify {
+        /// Proof written by `merkle prove`
+        #[arg(long, value_name = "FILE")]
+        proof: String,
+
+        /// Trusted Merkle root (hex), e.g. from the release announcement
+        #[arg(long, value_name = "HEX")]
+        root: String,
+
+        /// File to check
+        path: String,
+
+        /// Allow use of insecure algorithms (SHA-1 and MD5)
+        #[arg(long)]
+        allow_insecure: bool,
+    },
+}
+
+/// Subcommands of `hashy cache`
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// Show the number of cached digests and their size
+    Stats {
+        /// Cache directory
+        #[arg(long, value_name = "DIR", env = "HASHY_CACHE")]
+        cache: String,
+    },
+
+    /// Remove entries for files that were deleted or changed
+    Prune {
+        /// Cache directory
+        #[arg(long, value_name = "DIR", env = "HASHY_CACHE")]
+        cache: String,
+    },
+
+    /// Remove every cached digest
+    Clear {
+        /// Cache directory
+        #[arg(long, value_name = "DIR", env = "HASHY_CACHE")]
+        cache: String,
+    },
+}
+
+/// Parameters returned from verify --xattr
+type VerifyXattrParams<'a> = (
+    &'a [String],    // files and directories
+    Option<&'a str>, // algo
     bool,            // allow_insecure
-    &'a str,         // checksums_file
     bool,            // continue_on_error
-    Option<&'a str>, // format
+    bool,            // status
+);
+
+/// Parameters returned from verify command with --pieces
+type VerifyPiecesParams<'a> = (
+    &'a str,         // piece list
+    Option<&'a str>, // file (default: the path recorded in the list)
+    bool,            // allow_insecure
+    bool,            // status
 );
 
+/// Parameters returned from baseline command.
+/// Directories, algorithm and walk options are only set for `init`.
+type BaselineParams<'a> = (
+    BaselineMode, // init, check or update
+    &'a str,      // db
+    &'a [String], // dirs
+    &'a str,      // algo
+    bool,         // allow_insecure
+    WalkOptions,  // scan settings
+    bool,         // json
+);
+
+/// Parameters returned from cache command
+type CacheParams<'a> = (
+    CacheMode, // stats, prune or clear
+    &'a str,   // cache directory
+);
+
+/// Parameters returned from chunks command
+type ChunksParams<'a> = (
+    &'a [String], // paths
+    &'a str,      // algo
+    bool,         // allow_insecure
+    ChunkSizes,   // min/avg/max chunk size
+    bool,         // json
+    WalkOptions,  // which files to chunk
+);
+
+/// Cache directory from --cache, falling back to HASHY_CACHE. The variable is
+/// ignored with --decompress, which never uses the cache, rather than
+/// conflicting with it like the flag does.
+fn cache_dir(cache: &Option<String>, decompress: &Option<String>) -> Option<String> {
+    match (cache, decompress) {
+        (Some(dir), _) => Some(dir.clone()),
+        (None, Some(_)) => None,
+        (None, None) => std::env::var("HASHY_CACHE").ok(),
+    }
+}
+
 impl Commands {
-    pub fn get_hash_params(&self) -> Option<HashParams<'_>> {
+    pub fn get_hash_params(&self) -> Option<HashArgs<'_>> {
         match self {
             Commands::Hash {
                 algo,
@@ -143,37 +855,428 @@ impl Commands {
                 format,
                 uppercase,
                 json,
-            } => Some((
+                recursive,
+                exclude,
+                include,
+                max_depth,
+                hidden,
+                follow_symlinks,
+                manifest,
+                length,
+                tag,
+                cache,
+                write_xattr,
+                tree,
+                no_permissions,
+                no_symlink_targets,
+                archive,
+                piece_size,
+                offset,
+                range_length,
+                decompress,
+                io,
+            } => Some(HashArgs {
+                algo: algo.as_slice(),
+                allow_insecure: *allow_insecure,
+                text: text.as_deref(),
+                file: file.as_deref(),
+                files: files.as_slice(),
+                continue_on_error: *continue_on_error,
+                format: format.as_deref(),
+                uppercase: *uppercase,
+                json: *json,
+                walk: recursive.then(|| WalkOptions {
+                    include: include.clone(),
+                    exclude: exclude.clone(),
+                    max_depth: *max_depth,
+                    hidden: *hidden,
+                    follow_symlinks: *follow_symlinks,
+                }),
+                manifest: manifest.as_deref(),
+                length: *length,
+                tag: *tag,
+                cache: cache_dir(cache, decompress),
+                write_xattr: *write_xattr,
+                tree: tree.as_deref().map(|dir| {
+                    (
+                        dir,
+                        TreeOptions {
+                            permissions: !*no_permissions,
+                            symlink_targets: !*no_symlink_targets,
+                            exclude: exclude.clone(),
+                        },
+                    )
+                }),
+                archive: archive.as_deref(),
+                piece_size: *piece_size,
+                range: (offset.is_some() || range_length.is_some())
+                    .then(|| (offset.unwrap_or(0), *range_length)),
+                decompress: decompress.as_deref(),
+                io: io.as_deref(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn get_verify_params(&self) -> Option<VerifyArgs<'_>> {
+        match self {
+            Commands::Verify {
+                checksums_file,
                 algo,
+                allow_insecure,
+                format,
+                continue_on_error,
+                length,
+                ignore_missing,
+                strict,
+                warn,
+                status,
+                jobs,
+                cache,
+                decompress,
+                io,
+                ..
+            } => Some(VerifyArgs {
+                algo: algo.as_deref(),
+                allow_insecure: *allow_insecure,
+                checksums_file: checksums_file.as_deref()?,
+                continue_on_error: *continue_on_error,
+                format: format.as_deref(),
+                length: *length,
+                options: VerifyOptions {
+                    ignore_missing: *ignore_missing,
+                    strict: *strict,
+                    warn: *warn,
+                    status: *status,
+                    jobs: *jobs,
+                },
+                cache: cache_dir(cache, decompress),
+                decompress: decompress.as_deref(),
+                io: io.as_deref(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn get_verify_xattr_params(&self) -> Option<VerifyXattrParams<'_>> {
+        match self {
+            Commands::Verify {
+                xattr,
+                algo,
+                allow_insecure,
+                continue_on_error,
+                status,
+                ..
+            } if !xattr.is_empty() => Some((
+                xattr.as_slice(),
+                algo.as_deref(),
                 *allow_insecure,
-                text.as_deref(),
-                file.as_deref(),
-                files.as_slice(),
                 *continue_on_error,
-                format.as_deref(),
-                *uppercase,
-                *json,
+                *status,
             )),
             _ => None,
         }
     }
 
-    pub fn get_verify_params(&self) -> Option<VerifyParams<'_>> {
+    pub fn get_verify_pieces_params(&self) -> Option<VerifyPiecesParams<'_>> {
         match self {
             Commands::Verify {
+                pieces,
+                allow_insecure,
+                status,
+                ..
+            } if !pieces.is_empty() => Some((
+                pieces[0].as_str(),
+                pieces.get(1).map(String::as_str),
+                *allow_insecure,
+                *status,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn get_audit_params(&self) -> Option<AuditArgs<'_>> {
+        match self {
+            Commands::Audit {
                 checksums_file,
+                dir,
                 algo,
                 allow_insecure,
-                format,
-                continue_on_error,
-            } => Some((
+                length,
+                json,
+                exclude,
+                hidden,
+                follow_symlinks,
+            } => Some(AuditArgs {
+                checksums_file: checksums_file.as_str(),
+                dir: dir.as_str(),
+                algo: algo.as_deref(),
+                allow_insecure: *allow_insecure,
+                length: *length,
+                json: *json,
+                walk: WalkOptions {
+                    exclude: exclude.clone(),
+                    hidden: *hidden,
+                    follow_symlinks: *follow_symlinks,
+                    ..WalkOptions::default()
+                },
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn get_baseline_params(&self) -> Option<BaselineParams<'_>> {
+        match self {
+            Commands::Baseline { action } => Some(match action {
+                BaselineAction::Init {
+                    db,
+                    dirs,
+                    algo,
+                    allow_insecure,
+                    exclude,
+                    hidden,
+                    follow_symlinks,
+                } => (
+                    BaselineMode::Init,
+                    db.as_str(),
+                    dirs.as_slice(),
+                    algo.as_str(),
+                    *allow_insecure,
+                    WalkOptions {
+                        exclude: exclude.clone(),
+                        hidden: *hidden,
+                        follow_symlinks: *follow_symlinks,
+                        ..WalkOptions::default()
+                    },
+                    false,
+                ),
+                BaselineAction::Check { db, json } => (
+                    BaselineMode::Check,
+                    db.as_str(),
+                    &[],
+                    "",
+                    false,
+                    WalkOptions::default(),
+                    *json,
+                ),
+                BaselineAction::Update { db } => (
+                    BaselineMode::Update,
+                    db.as_str(),
+                    &[],
+                    "",
+                    false,
+                    WalkOptions::default(),
+                    false,
+                ),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn get_cache_params(&self) -> Option<CacheParams<'_>> {
+        match self {
+            Commands::Cache { action } => Some(match action {
+                CacheAction::Stats { cache } => (CacheMode::Stats, cache.as_str()),
+                CacheAction::Prune { cache } => (CacheMode::Prune, cache.as_str()),
+                CacheAction::Clear { cache } => (CacheMode::Clear, cache.as_str()),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn get_dupes_params(&self) -> Option<DupesArgs<'_>> {
+        match self {
+            Commands::Dupes {
+                paths,
+                algo,
+                allow_insecure,
+                min_size,
+                json,
+                script,
+                exclude,
+                include,
+                hidden,
+                follow_symlinks,
+            } => Some(DupesArgs {
+                paths: paths.as_slice(),
+                algo: algo.as_str(),
+                allow_insecure: *allow_insecure,
+                min_size: *min_size,
+                json: *json,
+                script: script.as_deref(),
+                walk: WalkOptions {
+                    include: include.clone(),
+                    exclude: exclude.clone(),
+                    hidden: *hidden,
+                    follow_symlinks: *follow_symlinks,
+                    ..WalkOptions::default()
+                },
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn get_chunks_params(&self) -> Option<ChunksParams<'_>> {
+        match self {
+            Commands::Chunks {
+                paths,
                 algo,
+                allow_insecure,
+                min_size,
+                avg_size,
+                max_size,
+                json,
+                exclude,
+                hidden,
+                follow_symlinks,
+            } => Some((
+                paths.as_slice(),
+                algo.as_str(),
                 *allow_insecure,
-                checksums_file.as_str(),
-                *continue_on_error,
-                format.as_deref(),
+                ChunkSizes {
+                    min: *min_size,
+                    avg: *avg_size,
+                    max: *max_size,
+                },
+                *json,
+                WalkOptions {
+                    exclude: exclude.clone(),
+                    hidden: *hidden,
+                    follow_symlinks: *follow_symlinks,
+                    ..WalkOptions::default()
+                },
             )),
             _ => None,
         }
     }
+
+    pub fn get_merkle_params(&self) -> Option<MerkleArgs<'_>> {
+        match self {
+            Commands::Merkle { action } => Some(match action {
+                MerkleAction::Build {
+                    output,
+                    paths,
+                    algo,
+                    allow_insecure,
+                    exclude,
+                    hidden,
+                    follow_symlinks,
+                } => MerkleArgs {
+                    mode: MerkleMode::Build,
+                    paths: paths.as_slice(),
+                    tree: None,
+                    proof: None,
+                    root: None,
+                    output: Some(output.as_str()),
+                    algo: algo.as_str(),
+                    allow_insecure: *allow_insecure,
+                    walk: WalkOptions {
+                        exclude: exclude.clone(),
+                        hidden: *hidden,
+                        follow_symlinks: *follow_symlinks,
+                        ..WalkOptions::default()
+                    },
+                },
+                MerkleAction::Prove { tree, path, output } => MerkleArgs {
+                    mode: MerkleMode::Prove,
+                    paths: std::slice::from_ref(path),
+                    tree: Some(tree.as_str()),
+                    proof: None,
+                    root: None,
+                    output: output.as_deref(),
+                    algo: "",
+                    allow_insecure: false,
+                    walk: WalkOptions::default(),
+                },
+                MerkleAction::Verify {
+                    proof,
+                    root,
+                    path,
+                    allow_insecure,
+                } => MerkleArgs {
+                    mode: MerkleMode::Verify,
+                    paths: std::slice::from_ref(path),
+                    tree: None,
+                    proof: Some(proof.as_str()),
+                    root: Some(root.as_str()),
+                    output: None,
+                    algo: "",
+                    allow_insecure: *allow_insecure,
+                    walk: WalkOptions::default(),
+                },
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn get_hmac_params(&mut self) -> Option<HmacArgs<'_>> {
+        match self {
+            Commands::Hmac {
+                algo,
+                allow_insecure,
+                key_file,
+                key_env,
+                key_hex,
+                insecure_key_arg,
+                text,
+                file,
+                files,
+                verify,
+                continue_on_error,
+                format,
+                uppercase,
+                json,
+            } => {
+                let key_source = if let Some(path) = key_file {
+                    Some(KeySource::File(path.clone()))
+                } else if let Some(var) = key_env {
+                    Some(KeySource::Env(var.clone()))
+                } else {
+                    // Moved out rather than copied, so the only copy is wiped after use
+                    key_hex
+                        .take()
+                        .map(|hex| KeySource::Hex(Zeroizing::new(hex)))
+                };
+
+                Some(HmacArgs {
+                    algo,
+                    allow_insecure: *allow_insecure,
+                    key_source,
+                    insecure_key_arg: *insecure_key_arg,
+                    text: text.as_deref(),
+                    file: file.as_deref(),
+                    files: files.as_slice(),
+                    verify: verify.as_deref(),
+                    continue_on_error: *continue_on_error,
+                    format: format.as_deref(),
+                    uppercase: *uppercase,
+                    json: *json,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get_bench_params(&self) -> Option<BenchArgs<'_>> {
+        match self {
+            Commands::Bench {
+                algo,
+                sizes,
+                file_size,
+                io,
+                dir,
+                duration,
+                json,
+            } => Some(BenchArgs {
+                algo: algo.as_slice(),
+                sizes: sizes.as_slice(),
+                file_size: *file_size,
+                io: io.as_slice(),
+                dir: dir.as_deref(),
+                duration_ms: *duration,
+                json: *json,
+            }),
+            _ => None,
+        }
+    }
 }
@@ -1,13 +1,41 @@
 use crate::algorithm::Algorithm;
-use crate::hash::{hash_data, hash_file, hash_stdin};
-use crate::output::{BatchHashJsonOutput, HashJsonOutput, OutputFormat};
+use crate::archive;
+use crate::baseline::{self, Baseline, BaselineChange, BaselineMode, FileRecord};
+use crate::bench::{self, BenchFile, HostInfo};
+use crate::cache::{hash_file_cached, CacheMode, DigestCache};
+use crate::chunks::{self, Chunk, ChunkSizes, DedupSummary};
+use crate::decompress::{self, Decompress};
+use crate::detect::{detect_algorithm, DetectionSource};
+use crate::dupes::{self, DupesScript, DuplicateGroup};
+use crate::hash::{
+    hash_data_multi, hash_file_multi, hash_file_range_multi, hash_file_with_length,
+    hash_reader_multi, hash_stdin_multi,
+};
+use crate::io::IoMode;
+use crate::mac::{
+    load_key, mac_data, mac_file, mac_name, mac_stdin, tags_equal, KeySource, KeyedHasher,
+};
+use crate::manifest::{self, ManifestEntry};
+use crate::merkle::{InclusionProof, MerkleLeaf, MerkleMode, MerkleTree};
+use crate::output::{
+    AuditJsonOutput, AuditJsonResult, AuditSummary, BaselineJsonOutput, BaselineJsonResult,
+    BaselineSummary, BatchHashJsonOutput, BenchJsonOutput, ByteRange, ChunkedFileJson,
+    ChunksJsonOutput, DupesJsonOutput, DupesSummary, HashJsonOutput, OutputFormat,
+};
+use crate::pieces::{self, PieceList, PieceStatus};
+use crate::tree::{self, TreeEntryKind, TreeOptions};
 use crate::verbosity::Verbosity;
+use crate::walk::{collect_files, walk_paths, WalkEntry, WalkOptions};
+use crate::xattrs;
 use anyhow::{anyhow, Context, Result};
 use atty::Stream;
 use rayon::prelude::*;
 use serde_json;
-use std::io::BufRead;
+use std::collections::BTreeMap;
 use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
 
 /// Input source for hashing
 enum InputSource {
@@ -43,23 +71,12 @@ fn resolve_input_source(text: Option<&str>, file: Option<&str>) -> Result<InputS
     Ok(InputSource::Stdin)
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn handle_hash(
-    algo_str: &str,
+/// Refuse insecure algorithms unless explicitly allowed, and warn when they are
+fn check_insecure_allowed(
+    algorithm: Algorithm,
     allow_insecure: bool,
-    text: Option<&str>,
-    file: Option<&str>,
-    files: &[String],
-    continue_on_error: bool,
-    format: Option<&str>,
-    uppercase: bool,
-    json: bool,
     verbosity: Verbosity,
 ) -> Result<()> {
-    // Parse algorithm
-    let algorithm: Algorithm = algo_str.parse()?;
-
-    // Check security gating
     if algorithm.is_insecure() && !allow_insecure {
         eprintln!(
             "WARNING: {} is considered cryptographically insecure and vulnerable to collision attacks.",
@@ -80,34 +97,221 @@ pub fn handle_hash(
         );
     }
 
+    Ok(())
+}
+
+/// Parse a `--format` value
+fn parse_output_format(format_str: &str) -> Result<OutputFormat> {
+    match format_str.to_lowercase().as_str() {
+        "hex" => Ok(OutputFormat::Hex),
+        "base64" => Ok(OutputFormat::Base64),
+        "raw" => Ok(OutputFormat::Raw),
+        _ => Err(anyhow!(
+            "Invalid format '{}'. Supported formats: hex, base64, raw",
+            format_str
+        )),
+    }
+}
+
+/// Parse `--algo` values, dropping duplicates while keeping the given order
+fn parse_algorithms(algo_strs: &[String]) -> Result<Vec<Algorithm>> {
+    let mut algorithms: Vec<Algorithm> = Vec::with_capacity(algo_strs.len());
+    for algo_str in algo_strs {
+        let algorithm: Algorithm = algo_str.trim().parse()?;
+        if !algorithms.contains(&algorithm) {
+            algorithms.push(algorithm);
+        }
+    }
+
+    if algorithms.is_empty() {
+        return Err(anyhow!("No algorithm given"));
+    }
+
+    Ok(algorithms)
+}
+
+/// Arguments of `hash`, as given on the command line
+#[derive(Debug, Clone, Default)]
+pub struct HashArgs<'a> {
+    pub algo: &'a [String],
+    pub allow_insecure: bool,
+    pub text: Option<&'a str>,
+    pub file: Option<&'a str>,
+    pub files: &'a [String],
+    pub continue_on_error: bool,
+    pub format: Option<&'a str>,
+    pub uppercase: bool,
+    pub json: bool,
+    /// Walk directory arguments (`--recursive`)
+    pub walk: Option<WalkOptions>,
+    pub manifest: Option<&'a str>,
+    pub length: Option<usize>,
+    pub tag: bool,
+    /// Digest cache directory
+    pub cache: Option<String>,
+    pub write_xattr: bool,
+    /// Directory to hash as a single tree digest
+    pub tree: Option<(&'a str, TreeOptions)>,
+    pub archive: Option<&'a str>,
+    pub piece_size: Option<u64>,
+    /// Byte range of the input: (offset, length)
+    pub range: Option<(u64, Option<u64>)>,
+    pub decompress: Option<&'a str>,
+    pub io: Option<&'a str>,
+}
+
+pub fn handle_hash(args: HashArgs<'_>, verbosity: Verbosity) -> Result<()> {
+    let HashArgs {
+        algo: algo_strs,
+        allow_insecure,
+        text,
+        file,
+        files,
+        continue_on_error,
+        format,
+        uppercase,
+        json,
+        walk,
+        manifest,
+        length,
+        tag,
+        cache,
+        write_xattr,
+        tree,
+        archive,
+        piece_size,
+        range,
+        decompress,
+        io,
+    } = args;
+
+    // Parse algorithms
+    let algorithms = parse_algorithms(algo_strs)?;
+    let decompress = decompress.map(str::parse::<Decompress>).transpose()?;
+    let io_mode = io
+        .map(str::parse::<IoMode>)
+        .transpose()?
+        .unwrap_or(IoMode::Auto);
+
+    // Reject --length for fixed-output algorithms before doing any work
+    for algorithm in &algorithms {
+        algorithm.resolve_output_size(length)?;
+    }
+
+    // Check security gating
+    for algorithm in &algorithms {
+        check_insecure_allowed(*algorithm, allow_insecure, verbosity)?;
+    }
+
     // Determine output format
     let output_format = if json {
         None // JSON output doesn't use format enum
     } else if let Some(format_str) = format {
-        match format_str.to_lowercase().as_str() {
-            "hex" => Some(OutputFormat::Hex),
-            "base64" => Some(OutputFormat::Base64),
-            "raw" => Some(OutputFormat::Raw),
-            _ => {
-                return Err(anyhow!(
-                    "Invalid format '{}'. Supported formats: hex, base64, raw",
-                    format_str
-                ));
-            }
-        }
+        Some(parse_output_format(format_str)?)
     } else {
         None // Default multi-line format
     };
 
+    // Concatenated raw digests could not be told apart
+    if algorithms.len() > 1 && output_format.is_some_and(|f| f.is_raw()) {
+        return Err(anyhow!(
+            "--format raw cannot be used with multiple algorithms"
+        ));
+    }
+
+    if tag && output_format.is_some_and(|f| f.is_raw()) {
+        return Err(anyhow!("--format raw cannot be used with --tag"));
+    }
+
+    let algo_names: Vec<String> = algorithms.iter().map(|a| a.name().to_string()).collect();
+
+    let cache = cache
+        .as_deref()
+        .map(|dir| DigestCache::open(std::path::Path::new(dir)))
+        .transpose()?;
+
+    // Handle tree mode (one digest for a whole directory)
+    if let Some((dir, options)) = tree {
+        let (digests, input_size) = hash_tree(
+            &algorithms,
+            dir,
+            &options,
+            length,
+            cache.as_ref(),
+            verbosity,
+        )?;
+        return print_digests(
+            &algorithms,
+            &algo_names,
+            "tree".to_string(),
+            Some(format!("Tree: {}", dir)),
+            dir,
+            &digests,
+            input_size,
+            length,
+            None,
+            output_format,
+            uppercase,
+            json,
+            tag,
+            verbosity,
+        );
+    }
+
+    // One manifest per algorithm when several are requested, unless tagged
+    // lines let them share a single manifest
+    let manifests: Vec<PathBuf> = match manifest {
+        Some(path) if algorithms.len() > 1 && !tag => algo_names
+            .iter()
+            .map(|name| manifest::per_algorithm_path(path, name))
+            .collect(),
+        Some(path) => vec![PathBuf::from(path)],
+        None => Vec::new(),
+    };
+
+    // Handle archive mode (every file inside a tar or zip archive)
+    if let Some(archive) = archive {
+        return handle_archive_hash(
+            &algorithms,
+            archive,
+            continue_on_error,
+            output_format,
+            uppercase,
+            json,
+            tag,
+            &manifests,
+            length,
+            verbosity,
+        );
+    }
+
     // Handle batch mode (multiple files)
     if !files.is_empty() {
+        let recursive = walk.is_some();
+        let expanded;
+        let (files, walk_failures) = if let Some(options) = &walk {
+            expanded = expand_directories(files, options, &manifests, verbosity)?;
+            (expanded.0.as_slice(), expanded.1.as_slice())
+        } else {
+            (files, &[][..])
+        };
+
         return handle_batch_hash(
-            algorithm,
+            &algorithms,
             files,
+            walk_failures,
             continue_on_error,
             output_format,
             uppercase,
             json,
+            recursive,
+            tag,
+            &manifests,
+            length,
+            cache.as_ref(),
+            write_xattr,
+            decompress,
+            io_mode,
             verbosity,
         );
     }
@@ -115,6 +319,57 @@ pub fn handle_hash(
     // Resolve input source for single input
     let input_source = resolve_input_source(text, file)?;
 
+    if write_xattr && !matches!(input_source, InputSource::File(_)) {
+        return Err(anyhow!("--write-xattr requires file inputs"));
+    }
+
+    if io.is_some() && !matches!(input_source, InputSource::File(_)) {
+        return Err(anyhow!("--io requires file inputs"));
+    }
+
+    // Handle piecewise mode (one digest per fixed-size block of a file)
+    if let Some(piece_size) = piece_size {
+        let InputSource::File(f) = &input_source else {
+            return Err(anyhow!("--piece-size requires --file"));
+        };
+        return hash_pieces(&algorithms, f, piece_size, length, json, verbosity);
+    }
+
+    // Handle byte-range mode (only part of a file)
+    if let Some((offset, count)) = range {
+        let InputSource::File(f) = &input_source else {
+            return Err(anyhow!("--offset and --range-length require --file"));
+        };
+        if matches!(verbosity, Verbosity::Verbose) {
+            eprintln!("Using algorithm: {}", algo_names.join(", "));
+            eprintln!("Hashing file from offset {}: {}", offset, f);
+        }
+        let (digests, bytes) = hash_file_range_multi(&algorithms, f, offset, count, length)
+            .with_context(|| format!("Failed to hash file: {}", f))?;
+        return print_digests(
+            &algorithms,
+            &algo_names,
+            "file".to_string(),
+            Some(format!(
+                "File: {} ({} bytes at offset {})",
+                f, bytes, offset
+            )),
+            f,
+            &digests,
+            bytes as usize,
+            length,
+            Some(ByteRange {
+                offset,
+                length: bytes,
+            }),
+            output_format,
+            uppercase,
+            json,
+            tag,
+            verbosity,
+        );
+    }
+
     // Store source name (will get size later for stdin)
     let source_name = match &input_source {
         InputSource::Text(_) => "text".to_string(),
@@ -128,8 +383,14 @@ pub fn handle_hash(
         InputSource::Stdin => None, // Don't print label for STDIN
     };
 
+    // Path shown in tagged lines (`-` for STDIN, like shasum)
+    let tag_path = match &input_source {
+        InputSource::File(f) => f.clone(),
+        InputSource::Text(_) | InputSource::Stdin => "-".to_string(),
+    };
+
     if matches!(verbosity, Verbosity::Verbose) {
-        eprintln!("Using algorithm: {}", algorithm);
+        eprintln!("Using algorithm: {}", algo_names.join(", "));
         match &input_source {
             InputSource::Text(t) => eprintln!("Hashing text: {}", t),
             InputSource::File(f) => eprintln!("Hashing file: {}", f),
@@ -137,59 +398,338 @@ pub fn handle_hash(
         }
     }
 
-    // Compute hash based on algorithm and input type, also get input size
-    let (hash_bytes, input_size) = match input_source {
+    // Compute every digest in one pass over the input, also get input size
+    let (digests, input_size) = match input_source {
         InputSource::Text(t) => {
             let size = t.len();
-            (hash_data(algorithm, t.as_bytes()), size)
+            (hash_data_multi(&algorithms, t.as_bytes(), length)?, size)
         }
         InputSource::File(f) => {
-            // Hash first - this will give proper error if file doesn't exist
-            let hash =
-                hash_file(algorithm, &f).with_context(|| format!("Failed to hash file: {}", f))?;
-            // Then get metadata for size (ignore errors, use 0 if we can't get it)
-            let size = std::fs::metadata(&f).map(|m| m.len() as usize).unwrap_or(0);
-            (hash, size)
+            if let Some(mode) = decompress {
+                let (digests, size) =
+                    decompress::hash_file_decompressed(&algorithms, &f, length, mode)
+                        .with_context(|| format!("Failed to hash file: {}", f))?;
+                (digests, size as usize)
+            } else {
+                // Hash first - this will give proper error if file doesn't exist
+                let mtime_ns = xattrs::file_mtime_ns(std::path::Path::new(&f));
+                let digests = hash_file_cached(cache.as_ref(), &algorithms, &f, length, io_mode)
+                    .with_context(|| format!("Failed to hash file: {}", f))?;
+                if write_xattr {
+                    store_xattr_digests(&algorithms, &f, &digests, mtime_ns)?;
+                }
+                // Then get metadata for size (ignore errors, use 0 if we can't get it)
+                let size = std::fs::metadata(&f).map(|m| m.len() as usize).unwrap_or(0);
+                (digests, size)
+            }
         }
-        InputSource::Stdin => hash_stdin(algorithm).context("Failed to hash STDIN")?,
+        InputSource::Stdin => match decompress {
+            Some(mode) => {
+                let (digests, size) =
+                    decompress::hash_stdin_decompressed(&algorithms, length, mode)
+                        .context("Failed to hash STDIN")?;
+                (digests, size as usize)
+            }
+            None => hash_stdin_multi(&algorithms, length).context("Failed to hash STDIN")?,
+        },
+    };
+
+    print_digests(
+        &algorithms,
+        &algo_names,
+        source_name,
+        display_label,
+        &tag_path,
+        &digests,
+        input_size,
+        length,
+        None,
+        output_format,
+        uppercase,
+        json,
+        tag,
+        verbosity,
+    )
+}
+
+/// Hash a file whole and in fixed-size pieces, printing the whole-file
+/// digest followed by one line per piece (or a piece list as JSON)
+fn hash_pieces(
+    algorithms: &[Algorithm],
+    file: &str,
+    piece_size: u64,
+    length: Option<usize>,
+    json: bool,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let [algorithm] = algorithms else {
+        return Err(anyhow!("--piece-size supports a single algorithm"));
     };
+    if piece_size == 0 {
+        return Err(anyhow!("--piece-size must be at least 1 byte"));
+    }
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!("Using algorithm: {}", algorithm.name());
+        eprintln!("Hashing file in pieces of {} bytes: {}", piece_size, file);
+    }
+
+    let (digest, pieces, bytes) = pieces::hash_file_pieces(*algorithm, length, file, piece_size)
+        .with_context(|| format!("Failed to hash file: {}", file))?;
+
+    if matches!(verbosity, Verbosity::Quiet) {
+        return Ok(());
+    }
+
+    if json {
+        let list = PieceList {
+            algo: algorithm.name().to_string(),
+            source: "file".to_string(),
+            digest: hex::encode(&digest),
+            bytes,
+            length,
+            file_path: file.to_string(),
+            piece_size,
+            pieces,
+        };
+        println!("{}", serde_json::to_string(&list)?);
+    } else {
+        println!("Algorithm: {}", algorithm.name());
+        println!("File: {}", file);
+        println!("Digest: {}", OutputFormat::Hex.format_bytes(&digest, false));
+        println!("Piece size: {} bytes ({} pieces)", piece_size, pieces.len());
+        for piece in &pieces {
+            println!("{}-{}: {}", piece.offset, piece.end(), piece.digest);
+        }
+    }
+
+    Ok(())
+}
+
+/// Compute the tree digest of a directory for each algorithm, hashing its
+/// files in parallel like batch mode. Returns the digests and the number of
+/// content bytes read.
+fn hash_tree(
+    algorithms: &[Algorithm],
+    dir: &str,
+    options: &TreeOptions,
+    length: Option<usize>,
+    cache: Option<&DigestCache>,
+    verbosity: Verbosity,
+) -> Result<(Vec<Vec<u8>>, usize)> {
+    let root = std::path::Path::new(dir);
+    let entries = tree::collect_tree(root, options)?;
+    let files: Vec<String> = entries
+        .iter()
+        .filter(|entry| matches!(entry.kind, TreeEntryKind::File { .. }))
+        .map(|entry| root.join(&entry.path).to_string_lossy().into_owned())
+        .collect();
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        let names: Vec<&str> = algorithms.iter().map(|a| a.name()).collect();
+        eprintln!("Using algorithm: {}", names.join(", "));
+        eprintln!(
+            "Hashing {} files in {} tree entries",
+            files.len(),
+            entries.len()
+        );
+    }
+
+    // File contents always use the default digest size; --length only
+    // applies to the tree digest itself
+    let results = batch_hash_files(&files, verbosity, |file_path| {
+        hash_file_cached(cache, algorithms, file_path, None, IoMode::Auto)
+            .map(|digests| with_file_size(file_path, digests))
+    });
+
+    let mut file_digests = Vec::with_capacity(results.len());
+    let mut input_size = 0;
+    for result in results {
+        let Some(digests) = result.digests else {
+            return Err(anyhow!(
+                "Failed to hash file '{}': {}",
+                result.file_path,
+                result.error.unwrap_or_default()
+            ));
+        };
+        input_size += result.input_size.unwrap_or(0);
+        file_digests.push(digests);
+    }
+
+    let digests = algorithms
+        .iter()
+        .enumerate()
+        .map(|(i, algorithm)| {
+            let per_file: Vec<Vec<u8>> = file_digests.iter().map(|d| d[i].clone()).collect();
+            tree::tree_digest(*algorithm, length, &entries, &per_file, options)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((digests, input_size))
+}
 
-    // Output the hash
+/// Print the digests of a single input (text, file, STDIN or tree) in the
+/// requested layout
+#[allow(clippy::too_many_arguments)]
+fn print_digests(
+    algorithms: &[Algorithm],
+    algo_names: &[String],
+    source_name: String,
+    display_label: Option<String>,
+    tag_path: &str,
+    digests: &[Vec<u8>],
+    input_size: usize,
+    length: Option<usize>,
+    range: Option<ByteRange>,
+    output_format: Option<OutputFormat>,
+    uppercase: bool,
+    json: bool,
+    tag: bool,
+    verbosity: Verbosity,
+) -> Result<()> {
     if !matches!(verbosity, Verbosity::Quiet) {
-        if json {
-            // JSON output
-            let digest_str = OutputFormat::Hex.format_bytes(&hash_bytes, false);
-            let json_output = HashJsonOutput {
-                algo: algorithm.name().to_string(),
-                source: source_name,
-                digest: digest_str,
-                bytes: input_size,
-                file_path: None,
-                success: None,
-                error: None,
-            };
-            let json_str = serde_json::to_string(&json_output)?;
-            println!("{}", json_str);
-        } else if let Some(fmt) = output_format {
-            // Simplified single-line format output
-            if fmt.is_raw() {
-                // For raw format, write bytes directly to stdout
-                std::io::stdout()
-                    .write_all(&hash_bytes)
-                    .context("Failed to write raw bytes to stdout")?;
-            } else {
-                let formatted = fmt.format_bytes(&hash_bytes, uppercase);
-                println!("{}", formatted);
+        if tag {
+            let fmt = output_format.unwrap_or(OutputFormat::Hex);
+            for (algorithm, digest) in algorithms.iter().zip(digests) {
+                println!(
+                    "{}",
+                    manifest::format_tagged_line(
+                        &algorithm.tag(),
+                        tag_path,
+                        &fmt.format_bytes(digest, uppercase)
+                    )
+                );
             }
+        } else if let [digest] = digests {
+            print_single_digest(
+                &algo_names[0],
+                source_name,
+                display_label,
+                digest,
+                input_size,
+                length,
+                range,
+                output_format,
+                uppercase,
+                json,
+            )?;
         } else {
-            // Default multi-line format
-            println!("Algorithm: {}", algorithm);
-            if let Some(label) = display_label {
-                println!("{}", label);
-            }
+            print_multi_digest(
+                algo_names,
+                source_name,
+                display_label,
+                digests,
+                input_size,
+                length,
+                range,
+                output_format,
+                uppercase,
+                json,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Print a single (non-batch) digest as JSON, a single formatted line, or the
+/// default multi-line layout
+#[allow(clippy::too_many_arguments)]
+fn print_single_digest(
+    algo_name: &str,
+    source_name: String,
+    display_label: Option<String>,
+    digest: &[u8],
+    input_size: usize,
+    length: Option<usize>,
+    range: Option<ByteRange>,
+    output_format: Option<OutputFormat>,
+    uppercase: bool,
+    json: bool,
+) -> Result<()> {
+    if json {
+        // JSON output
+        let digest_str = OutputFormat::Hex.format_bytes(digest, false);
+        let json_output = HashJsonOutput {
+            algo: algo_name.to_string(),
+            source: source_name,
+            digest: digest_str,
+            digests: None,
+            bytes: input_size,
+            length,
+            range,
+            file_path: None,
+            success: None,
+            error: None,
+        };
+        let json_str = serde_json::to_string(&json_output)?;
+        println!("{}", json_str);
+    } else if let Some(fmt) = output_format {
+        // Simplified single-line format output
+        if fmt.is_raw() {
+            // For raw format, write bytes directly to stdout
+            std::io::stdout()
+                .write_all(digest)
+                .context("Failed to write raw bytes to stdout")?;
+        } else {
+            let formatted = fmt.format_bytes(digest, uppercase);
+            println!("{}", formatted);
+        }
+    } else {
+        // Default multi-line format
+        println!("Algorithm: {}", algo_name);
+        if let Some(label) = display_label {
+            println!("{}", label);
+        }
+        println!("Digest: {}", OutputFormat::Hex.format_bytes(digest, false));
+    }
+
+    Ok(())
+}
+
+/// Print one digest per algorithm for a single input
+#[allow(clippy::too_many_arguments)]
+fn print_multi_digest(
+    algo_names: &[String],
+    source_name: String,
+    display_label: Option<String>,
+    digests: &[Vec<u8>],
+    input_size: usize,
+    length: Option<usize>,
+    range: Option<ByteRange>,
+    output_format: Option<OutputFormat>,
+    uppercase: bool,
+    json: bool,
+) -> Result<()> {
+    if json {
+        let json_output = HashJsonOutput {
+            algo: algo_names.join(","),
+            source: source_name,
+            digest: OutputFormat::Hex.format_bytes(&digests[0], false),
+            digests: Some(digest_map(algo_names, digests)),
+            bytes: input_size,
+            length,
+            range,
+            file_path: None,
+            success: None,
+            error: None,
+        };
+        println!("{}", serde_json::to_string(&json_output)?);
+    } else if let Some(fmt) = output_format {
+        // One `<algo>: <digest>` line per algorithm (raw is rejected earlier)
+        for (name, digest) in algo_names.iter().zip(digests) {
+            println!("{}: {}", name, fmt.format_bytes(digest, uppercase));
+        }
+    } else {
+        println!("Algorithms: {}", algo_names.join(", "));
+        if let Some(label) = display_label {
+            println!("{}", label);
+        }
+        for (name, digest) in algo_names.iter().zip(digests) {
             println!(
-                "Digest: {}",
-                OutputFormat::Hex.format_bytes(&hash_bytes, false)
+                "{}: {}",
+                name,
+                OutputFormat::Hex.format_bytes(digest, false)
             );
         }
     }
@@ -197,67 +737,302 @@ pub fn handle_hash(
     Ok(())
 }
 
+/// Map each algorithm name to its hex digest for JSON output
+fn digest_map(algo_names: &[String], digests: &[Vec<u8>]) -> BTreeMap<String, String> {
+    algo_names
+        .iter()
+        .zip(digests)
+        .map(|(name, digest)| (name.clone(), OutputFormat::Hex.format_bytes(digest, false)))
+        .collect()
+}
+
+/// A path the recursive walk could not read, reported like a file that
+/// failed to hash
+#[derive(Debug)]
+struct WalkFailure {
+    /// Number of files to hash that precede it in walk order
+    position: usize,
+    path: String,
+    message: String,
+}
+
+/// Expand directory arguments into the list of files to hash in recursive
+/// mode, and the paths that could not be walked
+fn expand_directories(
+    paths: &[String],
+    options: &WalkOptions,
+    manifests: &[PathBuf],
+    verbosity: Verbosity,
+) -> Result<(Vec<String>, Vec<WalkFailure>)> {
+    let mut files = Vec::new();
+    let mut failures = Vec::new();
+    for entry in walk_paths(paths, options)? {
+        match entry {
+            // Never hash the manifests we are about to (re)write
+            WalkEntry::File(f) if manifests.iter().any(|m| manifest::is_manifest_path(&f, m)) => {}
+            WalkEntry::File(f) => files.push(f),
+            WalkEntry::Error { path, message } => failures.push(WalkFailure {
+                position: files.len(),
+                path,
+                message,
+            }),
+        }
+    }
+
+    if files.is_empty() && failures.is_empty() {
+        return Err(anyhow!("No files found to hash"));
+    }
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!("Found {} files in {} paths", files.len(), paths.len());
+    }
+
+    Ok((files, failures))
+}
+
 /// Result of hashing a single file in batch mode
 #[derive(Debug)]
 struct BatchHashResult {
     file_path: String,
     success: bool,
-    hash_bytes: Option<Vec<u8>>,
+    /// One digest per requested algorithm
+    digests: Option<Vec<Vec<u8>>>,
     input_size: Option<usize>,
     error: Option<String>,
 }
 
 /// Handle batch hashing of multiple files
+///
+/// In recursive mode lines are printed sha256sum-style (`<DIGEST>  <PATH>`)
+/// so the output can be redirected into a manifest directly.
+#[allow(clippy::too_many_arguments)]
 fn handle_batch_hash(
-    algorithm: Algorithm,
+    algorithms: &[Algorithm],
     files: &[String],
+    walk_failures: &[WalkFailure],
     continue_on_error: bool,
     output_format: Option<OutputFormat>,
     uppercase: bool,
     json: bool,
+    recursive: bool,
+    tag: bool,
+    manifests: &[PathBuf],
+    length: Option<usize>,
+    cache: Option<&DigestCache>,
+    write_xattr: bool,
+    decompress: Option<Decompress>,
+    io: IoMode,
     verbosity: Verbosity,
 ) -> Result<()> {
+    let algo_names: Vec<String> = algorithms.iter().map(|a| a.name().to_string()).collect();
+
     if matches!(verbosity, Verbosity::Verbose) {
-        eprintln!("Using algorithm: {}", algorithm);
+        eprintln!("Using algorithm: {}", algo_names.join(", "));
         eprintln!("Hashing {} files", files.len());
     }
 
-    // Process each file in parallel using rayon
+    let mut results = batch_hash_files(files, verbosity, |file_path| {
+        if let Some(mode) = decompress {
+            return decompress::hash_file_decompressed(algorithms, file_path, length, mode);
+        }
+        let mtime_ns = xattrs::file_mtime_ns(std::path::Path::new(file_path));
+        let digests = hash_file_cached(cache, algorithms, file_path, length, io)?;
+        if write_xattr {
+            store_xattr_digests(algorithms, file_path, &digests, mtime_ns)?;
+        }
+        Ok(with_file_size(file_path, digests))
+    });
+
+    // Paths the walk could not read fail in walk order, like unreadable files;
+    // inserting from the back keeps the earlier positions valid
+    for failure in walk_failures.iter().rev() {
+        results.insert(
+            failure.position,
+            BatchHashResult {
+                file_path: failure.path.clone(),
+                success: false,
+                digests: None,
+                input_size: None,
+                error: Some(failure.message.clone()),
+            },
+        );
+    }
+
+    if let Some(cache) = cache {
+        if matches!(verbosity, Verbosity::Verbose) {
+            eprintln!("Cache: {} hits, {} misses", cache.hits(), cache.misses());
+        }
+    }
+
+    report_batch_results(
+        &algo_names,
+        &results,
+        continue_on_error,
+        output_format,
+        uppercase,
+        json,
+        recursive,
+        tag,
+        manifests,
+        false,
+        length,
+        verbosity,
+    )
+}
+
+/// Hash every member of an archive and report them like recursive batch
+/// mode, with member paths in place of file paths
+#[allow(clippy::too_many_arguments)]
+fn handle_archive_hash(
+    algorithms: &[Algorithm],
+    archive: &str,
+    continue_on_error: bool,
+    output_format: Option<OutputFormat>,
+    uppercase: bool,
+    json: bool,
+    tag: bool,
+    manifests: &[PathBuf],
+    length: Option<usize>,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let algo_names: Vec<String> = algorithms.iter().map(|a| a.name().to_string()).collect();
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!("Using algorithm: {}", algo_names.join(", "));
+        eprintln!("Hashing members of archive: {}", archive);
+    }
+
+    let members = archive::hash_archive(archive, algorithms, length)?;
+    if members.is_empty() {
+        return Err(anyhow!("No files found in archive: {}", archive));
+    }
+
+    let results: Vec<BatchHashResult> = members
+        .into_iter()
+        .map(|member| {
+            if matches!(verbosity, Verbosity::Verbose) {
+                eprintln!("Hashed member: {}", member.path);
+            }
+            match member.digests {
+                Ok((digests, size)) => BatchHashResult {
+                    file_path: member.path,
+                    success: true,
+                    digests: Some(digests),
+                    input_size: Some(size as usize),
+                    error: None,
+                },
+                Err(e) => BatchHashResult {
+                    file_path: member.path,
+                    success: false,
+                    digests: None,
+                    input_size: None,
+                    error: Some(format!("{:#}", e)),
+                },
+            }
+        })
+        .collect();
+
+    // Member paths are relative to the archive root, so print them
+    // sha256sum-style like recursive mode
+    report_batch_results(
+        &algo_names,
+        &results,
+        continue_on_error,
+        output_format,
+        uppercase,
+        json,
+        true,
+        tag,
+        manifests,
+        true,
+        length,
+        verbosity,
+    )
+}
+
+/// Store one `user.hashy.<algo>` attribute per digest on a file
+fn store_xattr_digests(
+    algorithms: &[Algorithm],
+    file_path: &str,
+    digests: &[Vec<u8>],
+    mtime_ns: Option<i64>,
+) -> Result<()> {
+    for (algorithm, digest) in algorithms.iter().zip(digests) {
+        xattrs::write_digest(file_path, *algorithm, digest, mtime_ns)?;
+    }
+    Ok(())
+}
+
+/// Pair digests with the on-disk size of their file, for hashers that do not
+/// report how many bytes they read
+fn with_file_size(file_path: &str, digests: Vec<Vec<u8>>) -> (Vec<Vec<u8>>, u64) {
+    let size = std::fs::metadata(file_path).map(|m| m.len()).unwrap_or(0);
+    (digests, size)
+}
+
+/// Hash each file in parallel using rayon with the given per-file function,
+/// which returns one digest per algorithm and the number of bytes hashed.
+/// Results are returned in the same order as `files`.
+fn batch_hash_files<F>(files: &[String], verbosity: Verbosity, hash_one: F) -> Vec<BatchHashResult>
+where
+    F: Fn(&str) -> Result<(Vec<Vec<u8>>, u64)> + Sync,
+{
     // Using par_iter() which preserves order when collected
-    let results: Vec<BatchHashResult> = files
+    files
         .par_iter()
         .map(|file_path| {
             if matches!(verbosity, Verbosity::Verbose) {
                 eprintln!("Hashing file: {}", file_path);
             }
 
-            match hash_file(algorithm, file_path) {
-                Ok(hash_bytes) => {
-                    let input_size = std::fs::metadata(file_path)
-                        .map(|m| m.len() as usize)
-                        .unwrap_or(0);
-
-                    BatchHashResult {
-                        file_path: file_path.clone(),
-                        success: true,
-                        hash_bytes: Some(hash_bytes),
-                        input_size: Some(input_size),
-                        error: None,
-                    }
-                }
+            match hash_one(file_path) {
+                Ok((digests, input_size)) => BatchHashResult {
+                    file_path: file_path.clone(),
+                    success: true,
+                    digests: Some(digests),
+                    input_size: Some(input_size as usize),
+                    error: None,
+                },
                 Err(e) => {
                     let error_msg = format!("{}", e);
                     BatchHashResult {
                         file_path: file_path.clone(),
                         success: false,
-                        hash_bytes: None,
+                        digests: None,
                         input_size: None,
                         error: Some(error_msg),
                     }
                 }
             }
         })
-        .collect();
+        .collect()
+}
+
+/// Write the manifests (if requested) and print batch results.
+///
+/// With `tag` or several algorithms, text lines use the BSD tagged layout
+/// (`SHA256 (PATH) = DIGEST`) so every line names its algorithm. Tagged
+/// output goes to a single manifest; otherwise `manifests` is either empty
+/// or holds one path per entry in `algo_names`. Paths are recorded relative
+/// to the manifest's directory, except with `archive_members`, whose paths
+/// are already relative to the archive root.
+#[allow(clippy::too_many_arguments)]
+fn report_batch_results(
+    algo_names: &[String],
+    results: &[BatchHashResult],
+    continue_on_error: bool,
+    output_format: Option<OutputFormat>,
+    uppercase: bool,
+    json: bool,
+    recursive: bool,
+    tag: bool,
+    manifests: &[PathBuf],
+    archive_members: bool,
+    length: Option<usize>,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let multi = algo_names.len() > 1;
+    let tagged = tag || multi;
 
     // Collect errors and check if we should fail early
     let errors: Vec<(String, String)> = results
@@ -281,23 +1056,65 @@ fn handle_batch_hash(
         ));
     }
 
-    // Output results
-    if !matches!(verbosity, Verbosity::Quiet) {
+    // Write the manifests of successfully hashed files: a single tagged one
+    // covering every algorithm, or one sha256sum-style manifest per algorithm
+    for (idx, manifest_path) in manifests.iter().enumerate() {
+        let entries: Vec<ManifestEntry> = results
+            .iter()
+            .filter_map(|r| r.digests.as_ref().map(|digests| (r, digests)))
+            .flat_map(|(r, digests)| {
+                algo_names
+                    .iter()
+                    .zip(digests)
+                    .enumerate()
+                    .filter(move |(i, _)| tag || *i == idx)
+                    .map(move |(_, (name, digest))| ManifestEntry {
+                        tag: tag.then(|| name.to_uppercase()),
+                        digest: OutputFormat::Hex.format_bytes(digest, false),
+                        path: r.file_path.clone(),
+                    })
+            })
+            .collect();
+        if archive_members {
+            manifest::write_manifest_entries(manifest_path, &entries)?;
+        } else {
+            manifest::write_manifest(manifest_path, &entries)?;
+        }
+
+        if matches!(verbosity, Verbosity::Verbose) {
+            eprintln!(
+                "Wrote {} entries to manifest: {}",
+                entries.len(),
+                manifest_path.display()
+            );
+        }
+    }
+
+    // Output results
+    if !matches!(verbosity, Verbosity::Quiet) {
         if json {
             // JSON output for batch mode
+            let algo = algo_names.join(",");
             let batch_output = BatchHashJsonOutput {
-                algo: algorithm.name().to_string(),
+                algo: algo.clone(),
                 results: results
                     .iter()
                     .map(|r| HashJsonOutput {
-                        algo: algorithm.name().to_string(),
+                        algo: algo.clone(),
                         source: "file".to_string(),
-                        digest: if let Some(hash) = &r.hash_bytes {
-                            OutputFormat::Hex.format_bytes(hash, false)
+                        digest: if let Some(digests) = &r.digests {
+                            OutputFormat::Hex.format_bytes(&digests[0], false)
                         } else {
                             "ERROR".to_string()
                         },
+                        digests: r
+                            .digests
+                            .as_ref()
+                            .filter(|_| multi)
+                            .map(|digests| digest_map(algo_names, digests)),
                         bytes: r.input_size.unwrap_or(0),
+                        length,
+                        range: None,
                         file_path: Some(r.file_path.clone()),
                         success: Some(r.success),
                         error: r.error.clone(),
@@ -317,26 +1134,39 @@ fn handle_batch_hash(
             println!("{}", json_str);
         } else {
             // Text output for batch mode
-            for result in &results {
+            for result in results {
                 if result.success {
-                    if let Some(fmt) = output_format {
+                    let Some(digests) = &result.digests else {
+                        continue;
+                    };
+
+                    if tagged {
+                        // Tagged line per algorithm (raw is rejected earlier)
+                        let fmt = output_format.unwrap_or(OutputFormat::Hex);
+                        for (name, digest) in algo_names.iter().zip(digests) {
+                            println!(
+                                "{}",
+                                manifest::format_tagged_line(
+                                    &name.to_uppercase(),
+                                    &result.file_path,
+                                    &fmt.format_bytes(digest, uppercase)
+                                )
+                            );
+                        }
+                    } else if let Some(fmt) = output_format {
                         if fmt.is_raw() {
                             // For raw format, write bytes directly to stdout
-                            if let Some(hash_bytes) = &result.hash_bytes {
-                                std::io::stdout()
-                                    .write_all(hash_bytes)
-                                    .context("Failed to write raw bytes to stdout")?;
-                            }
-                        } else if let Some(hash_bytes) = &result.hash_bytes {
-                            let formatted = fmt.format_bytes(hash_bytes, uppercase);
-                            println!("{}  {}", result.file_path, formatted);
+                            std::io::stdout()
+                                .write_all(&digests[0])
+                                .context("Failed to write raw bytes to stdout")?;
+                        } else {
+                            let formatted = fmt.format_bytes(&digests[0], uppercase);
+                            print_batch_line(&result.file_path, &formatted, recursive);
                         }
                     } else {
                         // Default format - one line per file
-                        if let Some(hash_bytes) = &result.hash_bytes {
-                            let digest = OutputFormat::Hex.format_bytes(hash_bytes, uppercase);
-                            println!("{}  {}", result.file_path, digest);
-                        }
+                        let digest = OutputFormat::Hex.format_bytes(&digests[0], uppercase);
+                        print_batch_line(&result.file_path, &digest, recursive);
                     }
                 } else {
                     // Show error for failed files
@@ -366,50 +1196,86 @@ fn handle_batch_hash(
     Ok(())
 }
 
-/// Verify a checksum manifest (sha256sum-style) against the filesystem
-pub fn handle_verify(
-    algo_str: &str,
-    allow_insecure: bool,
-    checksums_file: &str,
-    continue_on_error: bool,
-    format: Option<&str>,
-    verbosity: Verbosity,
-) -> Result<()> {
-    // Parse algorithm
-    let algorithm: Algorithm = algo_str.parse()?;
-
-    // Security gating
-    if algorithm.is_insecure() && !allow_insecure {
-        eprintln!(
-            "WARNING: {} is considered cryptographically insecure and vulnerable to collision attacks.",
-            algorithm.name()
-        );
-        eprintln!("Use --allow-insecure to enable this algorithm (only for legacy compatibility or non-security purposes).");
-        return Err(anyhow!(
-            "Insecure algorithm '{}' requires --allow-insecure flag",
-            algorithm.name()
-        ));
+/// Print one batch result line, sha256sum-style in recursive mode
+fn print_batch_line(file_path: &str, digest: &str, recursive: bool) {
+    if recursive {
+        println!("{}", manifest::format_line(digest, file_path));
+    } else {
+        println!("{}  {}", file_path, digest);
     }
-    if algorithm.is_insecure() && allow_insecure && !matches!(verbosity, Verbosity::Quiet) {
-        eprintln!(
-            "WARNING: {} is cryptographically broken and should not be used for security purposes.",
-            algorithm.name()
-        );
+}
+
+/// Options for `verify`: coreutils-compatible `--check` flags and parallelism
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VerifyOptions {
+    /// Skip manifest entries whose file does not exist
+    pub ignore_missing: bool,
+    /// Fail if any line is improperly formatted
+    pub strict: bool,
+    /// Warn about each improperly formatted line
+    pub warn: bool,
+    /// Print nothing; the exit code reports the result
+    pub status: bool,
+    /// Number of files to hash in parallel (`None` uses every CPU core)
+    pub jobs: Option<usize>,
+}
+
+/// Verification failed and has already been reported (or, with `--status`,
+/// deliberately not). `main` exits with status 1 without printing anything.
+#[derive(Debug)]
+pub struct StatusFailure;
+
+impl std::fmt::Display for StatusFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Verification failed")
     }
+}
+
+impl std::error::Error for StatusFailure {}
+
+/// Arguments of `verify` when checking a manifest, as given on the command line
+#[derive(Debug, Clone, Default)]
+pub struct VerifyArgs<'a> {
+    pub algo: Option<&'a str>,
+    pub allow_insecure: bool,
+    pub checksums_file: &'a str,
+    pub continue_on_error: bool,
+    pub format: Option<&'a str>,
+    pub length: Option<usize>,
+    /// coreutils `--check` flags and parallelism
+    pub options: VerifyOptions,
+    /// Digest cache directory
+    pub cache: Option<String>,
+    pub decompress: Option<&'a str>,
+    pub io: Option<&'a str>,
+}
+
+/// Verify a checksum manifest (sha256sum-style) against the filesystem
+///
+/// The manifest is parsed up front, then entries are hashed in parallel
+/// while results are printed in manifest order.
+pub fn handle_verify(args: VerifyArgs<'_>, verbosity: Verbosity) -> Result<()> {
+    let VerifyArgs {
+        algo: algo_str,
+        allow_insecure,
+        checksums_file,
+        continue_on_error,
+        format,
+        length,
+        options,
+        cache,
+        decompress,
+        io,
+    } = args;
+    let decompress = decompress.map(str::parse::<Decompress>).transpose()?;
+    let io = io
+        .map(str::parse::<IoMode>)
+        .transpose()?
+        .unwrap_or(IoMode::Auto);
 
     // Determine manifest digest format (default hex)
     let digest_format = if let Some(fmt) = format {
-        match fmt.to_lowercase().as_str() {
-            "hex" => OutputFormat::Hex,
-            "base64" => OutputFormat::Base64,
-            "raw" => OutputFormat::Raw,
-            _ => {
-                return Err(anyhow!(
-                    "Invalid format '{}'. Supported formats: hex, base64, raw",
-                    fmt
-                ));
-            }
-        }
+        parse_output_format(fmt)?
     } else {
         OutputFormat::Hex
     };
@@ -417,134 +1283,2259 @@ pub fn handle_verify(
     // Read manifest
     let manifest_path = std::path::Path::new(checksums_file);
     let manifest_dir = manifest_path.parent().unwrap_or(std::path::Path::new("."));
-    let file = std::fs::File::open(manifest_path)
+    let contents = std::fs::read_to_string(manifest_path)
         .with_context(|| format!("Failed to open checksums file: {}", checksums_file))?;
-    let reader = std::io::BufReader::new(file);
+
+    // Parse algorithm, or infer it for untagged lines with `auto` (the default)
+    let algorithm = resolve_manifest_algorithm(
+        algo_str,
+        manifest_path,
+        &contents,
+        digest_format,
+        length,
+        verbosity,
+    )?;
+
+    // Digest size to expect in the manifest (honors --length for XOF algorithms)
+    let output_size = algorithm.resolve_output_size(length)?;
+
+    // Security gating
+    check_insecure_allowed(algorithm, allow_insecure, verbosity)?;
 
     if matches!(verbosity, Verbosity::Verbose) {
         eprintln!("Using algorithm: {}", algorithm);
         eprintln!("Verifying manifest: {}", checksums_file);
     }
 
+    let cache = cache
+        .as_deref()
+        .map(|dir| DigestCache::open(std::path::Path::new(dir)))
+        .transpose()?;
+
+    // Per-line output is suppressed entirely with --status
+    let report = !options.status;
+
+    // Parse the whole manifest before hashing anything
+    let (entries, improperly_formatted) = parse_manifest_entries(
+        &contents,
+        checksums_file,
+        algorithm,
+        output_size,
+        length,
+        digest_format,
+        allow_insecure,
+        options.warn && report,
+        verbosity,
+    )?;
+
     let mut succeeded = 0usize;
     let mut failed = 0usize;
+    // GNU coreutils-style tallies, reported as warnings at the end
+    let mut mismatched = 0usize;
+    let mut unreadable = 0usize;
+    let mut missing_ignored = 0usize;
+
+    // Set when stopping at the first failure (without --continue-on-error)
+    let mut aborted = false;
+
+    // Handle each outcome in manifest order, with the same output as
+    // `sha256sum --check`; returns false to stop early
+    let mut handle_outcome = |entry: &VerifyEntry, outcome: CheckOutcome| -> bool {
+        let name = display_entry_name(&entry.path);
+        let ok = match outcome {
+            CheckOutcome::Matched => {
+                if report && !matches!(verbosity, Verbosity::Quiet) {
+                    println!("{}: OK", name);
+                }
+                succeeded += 1;
+                true
+            }
+            CheckOutcome::Mismatched(actual) => {
+                if report {
+                    println!("{}: FAILED", name);
+                }
+                if matches!(verbosity, Verbosity::Verbose) {
+                    eprintln!("  expected: {}\n  actual:   {}", entry.expected, actual);
+                }
+                mismatched += 1;
+                false
+            }
+            CheckOutcome::Missing => {
+                missing_ignored += 1;
+                if matches!(verbosity, Verbosity::Verbose) {
+                    eprintln!("Ignoring missing file: {}", entry.path);
+                }
+                true
+            }
+            CheckOutcome::Unreadable(e) => {
+                if report {
+                    eprintln!("hashy: {}: {}", name, e);
+                    println!("{}: FAILED open or read", name);
+                }
+                unreadable += 1;
+                false
+            }
+            CheckOutcome::Invalid(e) => {
+                if report {
+                    eprintln!("hashy: {}: {}: {}", checksums_file, entry.line, e);
+                    println!("{}: FAILED", name);
+                }
+                false
+            }
+        };
+
+        if !ok {
+            failed += 1;
+            if !continue_on_error {
+                aborted = true;
+                return false;
+            }
+        }
+        true
+    };
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.jobs.unwrap_or(0))
+        .build()
+        .context("Failed to start verification threads")?;
+
+    let cache = cache.as_ref();
+    check_in_order(
+        &pool,
+        &entries,
+        |entry| {
+            check_entry(
+                entry,
+                manifest_dir,
+                digest_format,
+                options.ignore_missing,
+                cache,
+                decompress,
+                io,
+                verbosity,
+            )
+        },
+        |idx, outcome| handle_outcome(&entries[idx], outcome),
+    );
+
+    // Same conditions and wording as `sha256sum --check`
+    if !aborted && succeeded + failed + missing_ignored == 0 {
+        return Err(verify_failure(
+            report,
+            &format!(
+                "{}: no properly formatted checksum lines found",
+                checksums_file
+            ),
+        ));
+    }
+
+    if report {
+        if improperly_formatted > 0 {
+            eprintln!(
+                "hashy: WARNING: {} {} improperly formatted",
+                improperly_formatted,
+                if improperly_formatted == 1 {
+                    "line is"
+                } else {
+                    "lines are"
+                }
+            );
+        }
+        if unreadable > 0 {
+            eprintln!(
+                "hashy: WARNING: {} listed {} could not be read",
+                unreadable,
+                if unreadable == 1 { "file" } else { "files" }
+            );
+        }
+        if mismatched > 0 {
+            eprintln!(
+                "hashy: WARNING: {} computed {} did NOT match",
+                mismatched,
+                if mismatched == 1 {
+                    "checksum"
+                } else {
+                    "checksums"
+                }
+            );
+        }
+    }
+
+    if aborted || failed > 0 {
+        return Err(anyhow::Error::new(StatusFailure));
+    }
+
+    if options.ignore_missing && succeeded == 0 {
+        return Err(verify_failure(
+            report,
+            &format!("{}: no file was verified", checksums_file),
+        ));
+    }
+
+    // The warning above already names the improperly formatted lines
+    if options.strict && improperly_formatted > 0 {
+        return Err(anyhow::Error::new(StatusFailure));
+    }
+
+    Ok(())
+}
+
+/// Report a verification error like coreutils (`hashy: <message>` on
+/// stderr, unless `--status` silences it) and exit with status 1
+fn verify_failure(report: bool, message: &str) -> anyhow::Error {
+    if report {
+        eprintln!("hashy: {}", message);
+    }
+    anyhow::Error::new(StatusFailure)
+}
+
+/// Name of a manifest entry in `verify` output, escaped like sha256sum does
+/// when it contains a backslash or a newline
+fn display_entry_name(path: &str) -> String {
+    match manifest::escape_path(path) {
+        Some(escaped) => format!("\\{}", escaped),
+        None => path.to_string(),
+    }
+}
+
+/// Run `check` on every item in parallel on `pool`, handing the results to
+/// `handle` in item order as they become available. Once `handle` returns
+/// false, items that have not started are skipped and nothing more is handled.
+fn check_in_order<T: Sync, O: Send>(
+    pool: &rayon::ThreadPool,
+    items: &[T],
+    check: impl Fn(&T) -> O + Sync,
+    mut handle: impl FnMut(usize, O) -> bool,
+) {
+    // Set once handling stops early; queued items are then skipped
+    let cancelled = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel::<(usize, Option<O>)>();
+
+    std::thread::scope(|scope| {
+        let cancelled = &cancelled;
+        let check = &check;
+
+        scope.spawn(move || {
+            pool.install(|| {
+                items
+                    .par_iter()
+                    .enumerate()
+                    .for_each_with(sender, |sender, (idx, item)| {
+                        let outcome = (!cancelled.load(Ordering::Relaxed)).then(|| check(item));
+                        // The receiver only goes away once every result is in
+                        let _ = sender.send((idx, outcome));
+                    });
+            });
+        });
+
+        // Results arrive in completion order; buffer them to handle in item order
+        let mut pending: BTreeMap<usize, Option<O>> = BTreeMap::new();
+        let mut next = 0usize;
+
+        for (idx, outcome) in receiver {
+            pending.insert(idx, outcome);
+
+            while let Some(outcome) = pending.remove(&next) {
+                if !cancelled.load(Ordering::Relaxed) {
+                    if let Some(outcome) = outcome {
+                        if !handle(next, outcome) {
+                            cancelled.store(true, Ordering::Relaxed);
+                        }
+                    }
+                }
+                next += 1;
+            }
+        }
+    });
+}
+
+/// Resolve the algorithm of untagged manifest lines: the explicit `--algo`,
+/// or the one detected from the manifest for `auto` (the default)
+fn resolve_manifest_algorithm(
+    algo_str: Option<&str>,
+    manifest_path: &std::path::Path,
+    contents: &str,
+    digest_format: OutputFormat,
+    length: Option<usize>,
+    verbosity: Verbosity,
+) -> Result<Algorithm> {
+    Ok(match algo_str {
+        Some(s) if !s.eq_ignore_ascii_case("auto") => s.parse()?,
+        requested => {
+            // Digest lengths are only meaningful for hex manifests
+            let detection = detect_algorithm(
+                manifest_path,
+                if digest_format == OutputFormat::Hex {
+                    contents
+                } else {
+                    ""
+                },
+                length,
+                requested.is_some(),
+            )?;
+
+            if matches!(verbosity, Verbosity::Verbose) {
+                let reason = match detection.source {
+                    DetectionSource::FileName => "manifest file name",
+                    DetectionSource::DigestLength => "digest length",
+                    DetectionSource::Default => "default",
+                };
+                eprintln!("Detected algorithm: {} ({})", detection.algorithm, reason);
+            }
+
+            detection.algorithm
+        }
+    })
+}
+
+/// Parse every checksum line of a manifest, gating any algorithm named by a
+/// tagged line. Returns the entries to check and the number of improperly
+/// formatted lines, which `warn` reports as they are found.
+#[allow(clippy::too_many_arguments)]
+fn parse_manifest_entries(
+    contents: &str,
+    checksums_file: &str,
+    algorithm: Algorithm,
+    output_size: usize,
+    length: Option<usize>,
+    digest_format: OutputFormat,
+    allow_insecure: bool,
+    warn: bool,
+    verbosity: Verbosity,
+) -> Result<(Vec<VerifyEntry>, usize)> {
+    let mut entries: Vec<VerifyEntry> = Vec::new();
+    let mut improperly_formatted = 0usize;
 
-    for (idx, line_res) in reader.lines().enumerate() {
-        let line = line_res.with_context(|| format!("Failed to read line {}", idx + 1))?;
+    // Algorithms that already passed security gating (tagged lines may name others)
+    let mut checked_algorithms = vec![algorithm];
+
+    // Helper: validate hex digest length for algorithm
+    let is_valid_hex_for_algo = |s: &str| -> bool {
+        let expected_len = output_size * 2; // 2 hex chars per byte
+        s.len() == expected_len && s.chars().all(|c| c.is_ascii_hexdigit())
+    };
+
+    for (idx, line) in contents.lines().enumerate() {
         let trimmed = line.trim();
         if trimmed.is_empty() || trimmed.starts_with('#') {
             continue;
         }
 
-        // Parse line with two segments separated by at least one space (prefer two spaces)
-        let (left, right_opt) = if let Some(pos) = trimmed.find("  ") {
-            let (l, r) = trimmed.split_at(pos);
-            (l.trim(), Some(r.trim_start_matches(' ').trim()))
-        } else if let Some(pos) = trimmed.find(' ') {
-            let (l, r) = trimmed.split_at(pos);
-            (l.trim(), Some(r.trim_start()))
-        } else {
-            (trimmed, None)
-        };
+        if let Some(entry) = manifest::parse_tagged_line(trimmed) {
+            // BSD-style tagged line: the algorithm comes from the tag itself
+            let tag = entry.tag.unwrap_or_default();
+            let entry_algorithm = match Algorithm::from_tag(&tag) {
+                Ok(a) => a,
+                Err(e) => {
+                    // Reported as a failure in manifest order
+                    entries.push(VerifyEntry {
+                        line: idx + 1,
+                        algorithm,
+                        length: None,
+                        expected: entry.digest,
+                        path: entry.path,
+                        error: Some(e.to_string()),
+                    });
+                    continue;
+                }
+            };
+
+            if !checked_algorithms.contains(&entry_algorithm) {
+                check_insecure_allowed(entry_algorithm, allow_insecure, verbosity)?;
+                checked_algorithms.push(entry_algorithm);
+            }
 
-        // Helper: validate hex digest length for algorithm
-        let is_valid_hex_for_algo = |s: &str| -> bool {
-            let expected_len = match algorithm {
-                Algorithm::Sha1 => 40,
-                Algorithm::Sha256 => 64,
-                Algorithm::Sha512 => 128,
-                Algorithm::Blake3 => 64, // 32 bytes -> 64 hex chars
-                Algorithm::Md5 => 32,
+            // Hex XOF digests carry their own length (`hash --tag --length`)
+            let entry_length = match (entry_algorithm.is_xof(), digest_format) {
+                (false, _) => None,
+                (true, OutputFormat::Hex) => Some(entry.digest.len() / 2).filter(|&n| n > 0),
+                (true, _) => length,
             };
-            s.len() == expected_len && s.chars().all(|c| c.is_ascii_hexdigit())
+
+            entries.push(VerifyEntry {
+                line: idx + 1,
+                algorithm: entry_algorithm,
+                length: entry_length,
+                expected: entry.digest,
+                path: entry.path,
+                error: None,
+            });
+            continue;
+        }
+
+        // sha256sum prefixes lines whose path is escaped with a backslash
+        let (escaped, unprefixed) = match trimmed.strip_prefix('\\') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
         };
+        let (left, right_opt) = manifest::split_line(unprefixed);
 
-        let (expected_digest, path_str): (&str, &str) = match (right_opt, digest_format) {
+        let parsed: Option<(&str, &str)> = match (right_opt, digest_format) {
             (Some(right), OutputFormat::Hex) => {
-                // Try `<DIGEST>  <PATH>` first
                 if is_valid_hex_for_algo(left) {
-                    (left, right)
+                    // `<DIGEST>  <PATH>`
+                    Some((left, right))
                 } else if is_valid_hex_for_algo(right) {
                     // Accept `<PATH>  <DIGEST>`
-                    (right, left)
+                    Some((right, left))
                 } else {
-                    // Not a valid verification line (e.g., "Algorithm: sha256"), skip gracefully
-                    if matches!(verbosity, Verbosity::Verbose) {
-                        eprintln!("Skipping non-checksum line {}: {}", idx + 1, trimmed);
-                    }
-                    continue;
+                    // Not a valid verification line (e.g., "Algorithm: sha256")
+                    None
                 }
             }
-            (Some(right), _) => {
-                // For non-hex formats, assume `<DIGEST>  <PATH>`
-                (left, right)
+            // For non-hex formats, assume `<DIGEST>  <PATH>`
+            (Some(right), _) => Some((left, right)),
+            (None, _) => None,
+        };
+
+        // Handle optional leading '*' in path (binary mode in coreutils)
+        let parsed = parsed.and_then(|(digest, path)| {
+            let path = path.trim_start_matches('*');
+            if escaped {
+                Some((digest, manifest::unescape_path(path)?))
+            } else {
+                Some((digest, path.to_string()))
             }
-            (None, _) => {
-                println!("{}: FAILED (invalid format)", trimmed);
-                failed += 1;
-                if !continue_on_error {
-                    return Err(anyhow!("Invalid checksum line at {}", idx + 1));
-                }
-                continue;
+        });
+
+        let Some((expected_digest, path_clean)) = parsed else {
+            // Skipped like coreutils does, unless --strict turns it into a failure
+            improperly_formatted += 1;
+            if warn {
+                eprintln!(
+                    "hashy: {}: {}: improperly formatted {} checksum line",
+                    checksums_file,
+                    idx + 1,
+                    algorithm.tag()
+                );
+            }
+            if matches!(verbosity, Verbosity::Verbose) {
+                eprintln!("Skipping non-checksum line {}: {}", idx + 1, trimmed);
             }
+            continue;
         };
 
-        // Handle optional leading '*' in path (binary mode in coreutils)
-        let path_clean = path_str.trim_start_matches('*');
+        entries.push(VerifyEntry {
+            line: idx + 1,
+            algorithm,
+            length,
+            expected: expected_digest.to_string(),
+            path: path_clean,
+            error: None,
+        });
+    }
 
-        if matches!(verbosity, Verbosity::Verbose) {
-            eprintln!("Verifying file: {}", path_clean);
+    Ok((entries, improperly_formatted))
+}
+
+/// Result of checking a file against the digests in its extended attributes
+enum XattrOutcome {
+    /// Every stored digest matched; `true` if the mtime changed anyway
+    Matched(bool),
+    /// A digest did not match although the mtime is unchanged (corruption)
+    Mismatched {
+        algorithm: Algorithm,
+        expected: String,
+        actual: String,
+    },
+    /// A digest did not match and the file was modified after it was stored
+    Stale(Algorithm),
+    NoDigest,
+    Unreadable(String),
+}
+
+/// Verify files against the digests `hash --write-xattr` stored in their
+/// extended attributes
+///
+/// A mismatch is reported as STALE when the file's mtime differs from the
+/// one recorded with the digest (the file was edited after hashing), and as
+/// FAILED otherwise (the contents changed without the mtime, e.g. bit rot).
+pub fn handle_verify_xattr(
+    paths: &[String],
+    algo_str: Option<&str>,
+    allow_insecure: bool,
+    continue_on_error: bool,
+    status: bool,
+    verbosity: Verbosity,
+) -> Result<()> {
+    // Only check this algorithm's attribute (`auto` checks all of them)
+    let only: Option<Algorithm> = algo_str
+        .filter(|s| !s.eq_ignore_ascii_case("auto"))
+        .map(str::parse)
+        .transpose()?;
+
+    let files = collect_files(paths, &WalkOptions::default())?;
+    let report = !status;
+
+    // Read every attribute first so insecure algorithms are refused up front
+    let mut stored: Vec<Result<Vec<(Algorithm, xattrs::StoredDigest)>>> =
+        Vec::with_capacity(files.len());
+    let mut checked_algorithms: Vec<Algorithm> = Vec::new();
+    for file in &files {
+        let digests = xattrs::read_digests(file).map(|digests| {
+            digests
+                .into_iter()
+                .filter(|(algorithm, _)| only.is_none_or(|a| a == *algorithm))
+                .collect::<Vec<_>>()
+        });
+        if let Ok(digests) = &digests {
+            for (algorithm, _) in digests {
+                if !checked_algorithms.contains(algorithm) {
+                    check_insecure_allowed(*algorithm, allow_insecure, verbosity)?;
+                    checked_algorithms.push(*algorithm);
+                }
+            }
         }
+        stored.push(digests);
+    }
 
-        // Resolve path relative to manifest
-        let full_path = manifest_dir.join(path_clean);
+    let mut succeeded = 0usize;
+    let mut failed = 0usize;
+    let mut mismatched = 0usize;
+    let mut stale = 0usize;
+    let mut without_digest = 0usize;
+    let mut aborted: Option<anyhow::Error> = None;
 
-        match hash_file(algorithm, full_path.to_str().unwrap_or(path_clean)) {
-            Ok(actual_bytes) => {
-                let actual_str = match digest_format {
-                    OutputFormat::Hex => OutputFormat::Hex.format_bytes(&actual_bytes, false),
-                    OutputFormat::Base64 => OutputFormat::Base64.format_bytes(&actual_bytes, false),
-                    OutputFormat::Raw => {
-                        // Raw expected must match exact bytes; compare using hex fallback (documented limitation)
-                        OutputFormat::Hex.format_bytes(&actual_bytes, false)
+    // Handle each outcome in file order; returns false to stop early
+    let mut handle_outcome = |file: &str, outcome: XattrOutcome| -> bool {
+        let failure = match outcome {
+            XattrOutcome::Matched(mtime_changed) => {
+                if report {
+                    if mtime_changed {
+                        println!("{}: OK (mtime changed since hashing)", file);
+                    } else {
+                        println!("{}: OK", file);
                     }
-                };
-
-                if actual_str == expected_digest {
-                    println!("{}: OK", path_clean);
-                    succeeded += 1;
-                } else {
+                }
+                succeeded += 1;
+                None
+            }
+            XattrOutcome::Mismatched {
+                algorithm,
+                expected,
+                actual,
+            } => {
+                if report {
                     println!(
-                        "{}: FAILED (mismatch)\n  expected: {}\n  actual:   {}",
-                        path_clean, expected_digest, actual_str
+                        "{}: FAILED ({} mismatch)\n  expected: {}\n  actual:   {}",
+                        file, algorithm, expected, actual
                     );
-                    failed += 1;
-                    if !continue_on_error {
-                        return Err(anyhow!("Checksum mismatch for {}", path_clean));
-                    }
                 }
+                mismatched += 1;
+                Some(anyhow!("Checksum mismatch for {}", file))
             }
-            Err(e) => {
-                println!("{}: FAILED ({})", path_clean, e);
-                failed += 1;
-                if !continue_on_error {
-                    return Err(anyhow!("Failed to verify {}: {}", path_clean, e));
+            XattrOutcome::Stale(algorithm) => {
+                if report {
+                    println!(
+                        "{}: STALE (modified after its {} digest was stored)",
+                        file, algorithm
+                    );
+                }
+                stale += 1;
+                Some(anyhow!("{} was modified after it was hashed", file))
+            }
+            XattrOutcome::NoDigest => {
+                if report {
+                    println!("{}: FAILED (no {}* attribute)", file, xattrs::ATTR_PREFIX);
+                }
+                without_digest += 1;
+                Some(anyhow!("No stored digest for {}", file))
+            }
+            XattrOutcome::Unreadable(e) => {
+                if report {
+                    println!("{}: FAILED ({})", file, e);
                 }
+                Some(anyhow!("Failed to verify {}: {}", file, e))
+            }
+        };
+
+        if let Some(error) = failure {
+            failed += 1;
+            if !continue_on_error {
+                aborted = Some(error);
+                return false;
+            }
+        }
+        true
+    };
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .build()
+        .context("Failed to start verification threads")?;
+
+    // Hash in parallel, reporting in the order of `files` and stopping at the
+    // first failure like `verify`
+    let items: Vec<_> = files.iter().zip(stored).collect();
+    check_in_order(
+        &pool,
+        &items,
+        |(file, digests)| {
+            if matches!(verbosity, Verbosity::Verbose) {
+                eprintln!("Verifying file: {}", file);
             }
+            match digests {
+                Ok(digests) if digests.is_empty() => XattrOutcome::NoDigest,
+                Ok(digests) => check_xattr_digests(file, digests),
+                Err(e) => XattrOutcome::Unreadable(e.to_string()),
+            }
+        },
+        |idx, outcome| handle_outcome(items[idx].0, outcome),
+    );
+
+    if report {
+        if without_digest > 0 {
+            eprintln!(
+                "hashy: WARNING: {} {} no stored digest",
+                without_digest,
+                if without_digest == 1 {
+                    "file has"
+                } else {
+                    "files have"
+                }
+            );
+        }
+        if stale > 0 {
+            eprintln!(
+                "hashy: WARNING: {} {} modified after hashing",
+                stale,
+                if stale == 1 { "file was" } else { "files were" }
+            );
+        }
+        if mismatched > 0 {
+            eprintln!(
+                "hashy: WARNING: {} computed {} did NOT match",
+                mismatched,
+                if mismatched == 1 {
+                    "checksum"
+                } else {
+                    "checksums"
+                }
+            );
         }
     }
 
-    if !matches!(verbosity, Verbosity::Quiet) {
-        if failed == 0 {
-            println!("All files verified successfully.");
-        } else {
+    if let Some(error) = aborted {
+        return Err(verify_error(status, error));
+    }
+
+    if report && !matches!(verbosity, Verbosity::Quiet) {
+        if failed > 0 {
             println!("Summary: {} succeeded, {} failed", succeeded, failed);
+        } else {
+            println!("All files verified successfully.");
         }
     }
 
     if failed > 0 {
-        return Err(anyhow!("One or more files failed verification"));
+        return Err(verify_error(
+            status,
+            anyhow!("One or more files failed verification"),
+        ));
     }
 
     Ok(())
 }
+
+/// Re-hash a file in the pieces recorded by `hash --piece-size --json` and
+/// report the byte ranges that no longer match
+pub fn handle_verify_pieces(
+    list_path: &str,
+    file: Option<&str>,
+    allow_insecure: bool,
+    status: bool,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let list = PieceList::load(std::path::Path::new(list_path))?;
+    let algorithm = list.algorithm()?;
+    check_insecure_allowed(algorithm, allow_insecure, verbosity)?;
+    let file = file.unwrap_or(&list.file_path);
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!(
+            "Verifying file in pieces of {} bytes: {}",
+            list.piece_size, file
+        );
+    }
+
+    let (digest, actual, _) =
+        pieces::hash_file_pieces(algorithm, list.length, file, list.piece_size)
+            .map_err(|e| verify_error(status, e))?;
+
+    let ranges = pieces::compare_pieces(&list.pieces, &actual);
+    let report = !status;
+
+    if ranges.is_empty() {
+        // Every piece matched, so the whole-file digest can only differ if
+        // the list itself was edited
+        if !hex::encode(&digest).eq_ignore_ascii_case(&list.digest) {
+            if report {
+                println!("{}: FAILED (whole-file digest differs from the list)", file);
+            }
+            return Err(verify_error(
+                status,
+                anyhow!("Piece list {} is inconsistent", list_path),
+            ));
+        }
+        if report && !matches!(verbosity, Verbosity::Quiet) {
+            println!("{}: OK ({} pieces)", file, list.pieces.len());
+        }
+        return Ok(());
+    }
+
+    let mut bad_pieces = 0usize;
+    for range in &ranges {
+        bad_pieces += range.last_piece - range.first_piece + 1;
+        if !report {
+            continue;
+        }
+        let pieces_label = if range.first_piece == range.last_piece {
+            format!("piece {}", range.first_piece + 1)
+        } else {
+            format!("pieces {}-{}", range.first_piece + 1, range.last_piece + 1)
+        };
+        let (verdict, reason) = match range.status {
+            PieceStatus::Mismatch => ("FAILED", ""),
+            PieceStatus::Missing => ("MISSING", ", file is shorter than recorded"),
+            PieceStatus::Unexpected => ("UNEXPECTED", ", file is longer than recorded"),
+        };
+        println!(
+            "{}: bytes {}-{} {} ({}{})",
+            file, range.start, range.end, verdict, pieces_label, reason
+        );
+    }
+
+    if report {
+        eprintln!(
+            "hashy: WARNING: {} of {} {} did NOT match",
+            bad_pieces,
+            list.pieces.len().max(actual.len()),
+            if list.pieces.len().max(actual.len()) == 1 {
+                "piece"
+            } else {
+                "pieces"
+            }
+        );
+    }
+
+    Err(verify_error(
+        status,
+        anyhow!("Piecewise verification failed: {}", file),
+    ))
+}
+
+/// Re-hash a file with every stored algorithm in one pass and compare
+fn check_xattr_digests(file: &str, stored: &[(Algorithm, xattrs::StoredDigest)]) -> XattrOutcome {
+    let hashers = stored
+        .iter()
+        .map(|(algorithm, digest)| {
+            // XOF digests were stored at the length they were computed with
+            let length = algorithm.is_xof().then_some(digest.digest.len() / 2);
+            algorithm.hasher_with_length(length)
+        })
+        .collect::<Result<Vec<_>>>();
+
+    let mtime_ns = xattrs::file_mtime_ns(std::path::Path::new(file));
+
+    let actual = hashers.and_then(|hashers| {
+        let reader =
+            std::fs::File::open(file).with_context(|| format!("Failed to open file: {}", file))?;
+        hash_reader_multi(hashers, std::io::BufReader::new(reader))
+            .with_context(|| format!("Failed to read from file: {}", file))
+    });
+    let (actual, _) = match actual {
+        Ok(actual) => actual,
+        Err(e) => return XattrOutcome::Unreadable(e.to_string()),
+    };
+
+    let mut mtime_changed = false;
+    for ((algorithm, digest), actual) in stored.iter().zip(actual) {
+        let changed = digest.mtime_ns != mtime_ns;
+        mtime_changed |= changed;
+
+        let actual = hex::encode(actual);
+        if !actual.eq_ignore_ascii_case(&digest.digest) {
+            return if changed {
+                XattrOutcome::Stale(*algorithm)
+            } else {
+                XattrOutcome::Mismatched {
+                    algorithm: *algorithm,
+                    expected: digest.digest.clone(),
+                    actual,
+                }
+            };
+        }
+    }
+
+    XattrOutcome::Matched(mtime_changed)
+}
+
+/// A manifest entry to check, produced by the parse phase of `handle_verify`
+struct VerifyEntry {
+    /// 1-based line number in the manifest
+    line: usize,
+    algorithm: Algorithm,
+    length: Option<usize>,
+    expected: String,
+    path: String,
+    /// Set when the line itself cannot be checked (e.g. an unsupported tag)
+    error: Option<String>,
+}
+
+/// Result of checking one manifest entry against the filesystem
+enum CheckOutcome {
+    Matched,
+    /// Holds the actual digest
+    Mismatched(String),
+    /// Absent file skipped because of --ignore-missing
+    Missing,
+    Unreadable(String),
+    Invalid(String),
+}
+
+/// Hash the file of one manifest entry and compare it with the expected digest
+#[allow(clippy::too_many_arguments)]
+fn check_entry(
+    entry: &VerifyEntry,
+    manifest_dir: &std::path::Path,
+    digest_format: OutputFormat,
+    ignore_missing: bool,
+    cache: Option<&DigestCache>,
+    decompress: Option<Decompress>,
+    io: IoMode,
+    verbosity: Verbosity,
+) -> CheckOutcome {
+    if let Some(error) = &entry.error {
+        return CheckOutcome::Invalid(error.clone());
+    }
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!("Verifying file: {}", entry.path);
+    }
+
+    // Resolve path relative to manifest
+    let full_path = manifest_dir.join(&entry.path);
+
+    let hashed = match decompress {
+        Some(mode) => {
+            // Fall back to a compressed copy when the listed file is absent
+            let path = mode
+                .candidate_paths(&full_path)
+                .into_iter()
+                .find(|candidate| candidate.exists())
+                .unwrap_or(full_path);
+            decompress::hash_file_decompressed(
+                &[entry.algorithm],
+                path.to_str().unwrap_or(&entry.path),
+                entry.length,
+                mode,
+            )
+            .map(|(digests, _)| digests)
+        }
+        None => hash_file_cached(
+            cache,
+            &[entry.algorithm],
+            full_path.to_str().unwrap_or(&entry.path),
+            entry.length,
+            io,
+        ),
+    };
+
+    match hashed {
+        Ok(mut digests) => {
+            let actual_bytes = digests.remove(0);
+            let actual_str = match digest_format {
+                OutputFormat::Hex => OutputFormat::Hex.format_bytes(&actual_bytes, false),
+                OutputFormat::Base64 => OutputFormat::Base64.format_bytes(&actual_bytes, false),
+                OutputFormat::Raw => {
+                    // Raw expected must match exact bytes; compare using hex fallback (documented limitation)
+                    OutputFormat::Hex.format_bytes(&actual_bytes, false)
+                }
+            };
+
+            // Hex digests match regardless of case, like coreutils
+            let matched = if digest_format == OutputFormat::Base64 {
+                actual_str == entry.expected
+            } else {
+                actual_str.eq_ignore_ascii_case(&entry.expected)
+            };
+
+            if matched {
+                CheckOutcome::Matched
+            } else {
+                CheckOutcome::Mismatched(actual_str)
+            }
+        }
+        Err(e) if ignore_missing && is_not_found(&e) => CheckOutcome::Missing,
+        Err(e) => CheckOutcome::Unreadable(os_error_message(&e)),
+    }
+}
+
+/// The reason an I/O operation failed, as `strerror` words it (`No such
+/// file or directory`), for coreutils-style messages
+fn os_error_message(error: &anyhow::Error) -> String {
+    let message = error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .map_or_else(|| error.root_cause().to_string(), |e| e.to_string());
+    match message.rfind(" (os error ") {
+        Some(pos) => message[..pos].to_string(),
+        None => message,
+    }
+}
+
+/// With --status, failures are reported through the exit code only
+fn verify_error(status: bool, error: anyhow::Error) -> anyhow::Error {
+    if status {
+        anyhow::Error::new(StatusFailure)
+    } else {
+        error
+    }
+}
+
+/// Check whether an error was caused by a file that does not exist
+fn is_not_found(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+    })
+}
+
+/// Status of one path in an audit
+enum AuditStatus {
+    Unchanged,
+    Modified {
+        expected: String,
+        actual: String,
+    },
+    Missing,
+    /// Not listed in the manifest; holds the file's digest
+    New(String),
+    Unreadable(String),
+}
+
+impl AuditStatus {
+    /// Name used in JSON output
+    fn name(&self) -> &'static str {
+        match self {
+            AuditStatus::Unchanged => "unchanged",
+            AuditStatus::Modified { .. } => "modified",
+            AuditStatus::Missing => "missing",
+            AuditStatus::New(_) => "new",
+            AuditStatus::Unreadable(_) => "unreadable",
+        }
+    }
+}
+
+/// Arguments of `audit`, as given on the command line
+#[derive(Debug, Clone, Default)]
+pub struct AuditArgs<'a> {
+    pub checksums_file: &'a str,
+    pub dir: &'a str,
+    pub algo: Option<&'a str>,
+    pub allow_insecure: bool,
+    pub length: Option<usize>,
+    pub json: bool,
+    /// Which files under `dir` count as new
+    pub walk: WalkOptions,
+}
+
+/// Audit a directory against a manifest
+///
+/// Every manifest entry is classified as unchanged, modified or missing, and
+/// every file found under `dir` that the manifest does not list is reported
+/// as new. Any drift makes the command fail.
+pub fn handle_audit(args: AuditArgs<'_>, verbosity: Verbosity) -> Result<()> {
+    let AuditArgs {
+        checksums_file,
+        dir,
+        algo: algo_str,
+        allow_insecure,
+        length,
+        json,
+        walk,
+    } = args;
+    let manifest_path = std::path::Path::new(checksums_file);
+    let dir_path = std::path::Path::new(dir);
+    let contents = std::fs::read_to_string(manifest_path)
+        .with_context(|| format!("Failed to open checksums file: {}", checksums_file))?;
+
+    let algorithm = resolve_manifest_algorithm(
+        algo_str,
+        manifest_path,
+        &contents,
+        OutputFormat::Hex,
+        length,
+        verbosity,
+    )?;
+    let output_size = algorithm.resolve_output_size(length)?;
+    check_insecure_allowed(algorithm, allow_insecure, verbosity)?;
+
+    if !dir_path.is_dir() {
+        return Err(anyhow!("Not a directory: {}", dir));
+    }
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!("Using algorithm: {}", algorithm);
+        eprintln!("Auditing {} against manifest: {}", dir, checksums_file);
+    }
+
+    let (entries, improperly_formatted) = parse_manifest_entries(
+        &contents,
+        checksums_file,
+        algorithm,
+        output_size,
+        length,
+        OutputFormat::Hex,
+        allow_insecure,
+        false,
+        verbosity,
+    )?;
+
+    if entries.is_empty() {
+        return Err(anyhow!(
+            "{}: no properly formatted checksum lines found",
+            checksums_file
+        ));
+    }
+
+    // Relative entries name files under the audited directory, wherever the
+    // manifest itself is stored
+    let listed = entries
+        .iter()
+        .map(|entry| manifest::normalize_path(&dir_path.join(&entry.path)))
+        .collect::<Result<std::collections::HashSet<PathBuf>>>()?;
+
+    let mut new_files = Vec::new();
+    for file in collect_files(&[dir.to_string()], &walk)? {
+        // The manifest is not new when it is kept in the directory; a copy
+        // under another name is reported like any other file
+        if manifest::is_manifest_path(&file, manifest_path) {
+            continue;
+        }
+        if !listed.contains(&manifest::normalize_path(std::path::Path::new(&file))?) {
+            new_files.push(file);
+        }
+    }
+
+    // Listed entries in manifest order, then new files in walk order
+    let mut results: Vec<(String, AuditStatus)> = entries
+        .par_iter()
+        .map(|entry| {
+            let status = match check_entry(
+                entry,
+                dir_path,
+                OutputFormat::Hex,
+                true,
+                None,
+                None,
+                IoMode::Read,
+                verbosity,
+            ) {
+                CheckOutcome::Matched => AuditStatus::Unchanged,
+                CheckOutcome::Mismatched(actual) => AuditStatus::Modified {
+                    expected: entry.expected.clone(),
+                    actual,
+                },
+                CheckOutcome::Missing => AuditStatus::Missing,
+                CheckOutcome::Unreadable(e) | CheckOutcome::Invalid(e) => {
+                    AuditStatus::Unreadable(e)
+                }
+            };
+            (entry.path.clone(), status)
+        })
+        .collect();
+
+    let new_results = new_files
+        .par_iter()
+        .map(|file| {
+            if matches!(verbosity, Verbosity::Verbose) {
+                eprintln!("Hashing new file: {}", file);
+            }
+            let status = match hash_file_with_length(algorithm, file, length) {
+                Ok(digest) => AuditStatus::New(OutputFormat::Hex.format_bytes(&digest, false)),
+                Err(e) => AuditStatus::Unreadable(e.to_string()),
+            };
+            // Reported relative to the directory, like the listed entries
+            let relative = std::path::Path::new(file)
+                .strip_prefix(dir_path)
+                .map_or_else(|_| file.clone(), |p| p.to_string_lossy().into_owned());
+            (relative, status)
+        })
+        .collect::<Vec<_>>();
+    results.extend(new_results);
+
+    let mut summary = AuditSummary::default();
+    for (_, status) in &results {
+        match status {
+            AuditStatus::Unchanged => summary.unchanged += 1,
+            AuditStatus::Modified { .. } => summary.modified += 1,
+            AuditStatus::Missing => summary.missing += 1,
+            AuditStatus::New(_) => summary.new += 1,
+            AuditStatus::Unreadable(_) => summary.unreadable += 1,
+        }
+    }
+    let drift = summary.drift();
+
+    if json {
+        let json_output = AuditJsonOutput {
+            algo: algorithm.name().to_string(),
+            manifest: checksums_file.to_string(),
+            directory: dir.to_string(),
+            results: results
+                .iter()
+                .map(|(path, status)| {
+                    let (expected, actual, error) = match status {
+                        AuditStatus::Modified { expected, actual } => {
+                            (Some(expected.clone()), Some(actual.clone()), None)
+                        }
+                        AuditStatus::New(actual) => (None, Some(actual.clone()), None),
+                        AuditStatus::Unreadable(e) => (None, None, Some(e.clone())),
+                        AuditStatus::Unchanged | AuditStatus::Missing => (None, None, None),
+                    };
+                    AuditJsonResult {
+                        path: path.clone(),
+                        status: status.name().to_string(),
+                        expected,
+                        actual,
+                        error,
+                    }
+                })
+                .collect(),
+            summary,
+        };
+        println!("{}", serde_json::to_string(&json_output)?);
+    } else {
+        for (path, status) in &results {
+            match status {
+                AuditStatus::Unchanged => println!("{}: OK", path),
+                AuditStatus::Modified { expected, actual } => println!(
+                    "{}: MODIFIED\n  expected: {}\n  actual:   {}",
+                    path, expected, actual
+                ),
+                AuditStatus::Missing => println!("{}: MISSING", path),
+                AuditStatus::New(_) => println!("{}: NEW", path),
+                AuditStatus::Unreadable(e) => println!("{}: FAILED ({})", path, e),
+            }
+        }
+
+        if improperly_formatted > 0 {
+            eprintln!(
+                "hashy: WARNING: {} {} improperly formatted",
+                improperly_formatted,
+                if improperly_formatted == 1 {
+                    "line is"
+                } else {
+                    "lines are"
+                }
+            );
+        }
+
+        if !matches!(verbosity, Verbosity::Quiet) {
+            let mut line = format!(
+                "Summary: {} unchanged, {} modified, {} missing, {} new",
+                summary.unchanged, summary.modified, summary.missing, summary.new
+            );
+            if summary.unreadable > 0 {
+                line.push_str(&format!(", {} unreadable", summary.unreadable));
+            }
+            println!("{}", line);
+        }
+    }
+
+    if drift > 0 {
+        return Err(anyhow!(
+            "Audit failed: {} of {} paths differ from the manifest",
+            drift,
+            results.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Create, check or update a baseline database
+///
+/// `init` records the digest, size, mode, owner, mtime and inode of every file
+/// under `dirs`. `check` rescans the same directories with the recorded
+/// settings and fails if any file was changed, added or removed; `update`
+/// reports the same differences and then records the current state.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_baseline(
+    mode: BaselineMode,
+    db: &str,
+    dirs: &[String],
+    algo_str: &str,
+    allow_insecure: bool,
+    walk: WalkOptions,
+    json: bool,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let db_path = std::path::Path::new(db);
+
+    if mode == BaselineMode::Init {
+        if db_path.exists() {
+            return Err(anyhow!(
+                "Baseline database already exists: {} (use `hashy baseline update` to refresh it)",
+                db
+            ));
+        }
+
+        let algorithm: Algorithm = algo_str.parse()?;
+        check_insecure_allowed(algorithm, allow_insecure, verbosity)?;
+
+        // Roots are stored absolute so `check` works from any directory
+        let roots = dirs
+            .iter()
+            .map(|dir| {
+                let path = std::path::Path::new(dir);
+                if !path.is_dir() {
+                    return Err(anyhow!("Not a directory: {}", dir));
+                }
+                Ok(manifest::normalize_path(path)?
+                    .to_string_lossy()
+                    .into_owned())
+            })
+            .collect::<Result<Vec<String>>>()?;
+
+        let mut baseline = Baseline::new(algorithm, roots, &walk);
+        let (files, errors) = scan_baseline(&baseline, db_path, verbosity)?;
+        baseline.files = files;
+        baseline.save(db_path)?;
+
+        for (path, error) in &errors {
+            println!("{}: FAILED ({})", path, error);
+        }
+        if !matches!(verbosity, Verbosity::Quiet) {
+            println!(
+                "Recorded {} files in baseline: {}",
+                baseline.files.len(),
+                db
+            );
+        }
+        return unreadable_error(&errors);
+    }
+
+    let mut baseline = Baseline::load(db_path)?;
+    let (mut current, errors) = scan_baseline(&baseline, db_path, verbosity)?;
+    let differences = baseline::compare(&baseline.files, &current, &errors);
+
+    let mut summary = BaselineSummary::default();
+    for (_, change) in &differences {
+        match change {
+            BaselineChange::Changed(_) => summary.changed += 1,
+            BaselineChange::Added => summary.added += 1,
+            BaselineChange::Removed => summary.removed += 1,
+            BaselineChange::Unreadable(_) => summary.unreadable += 1,
+        }
+    }
+    summary.unchanged = current.len() - summary.changed - summary.added;
+
+    if json {
+        let json_output = BaselineJsonOutput {
+            algo: baseline.algorithm.clone(),
+            database: db.to_string(),
+            results: differences
+                .iter()
+                .map(|(path, change)| {
+                    let (status, changes, error) = match change {
+                        BaselineChange::Changed(changes) => ("changed", changes.clone(), None),
+                        BaselineChange::Added => ("added", Vec::new(), None),
+                        BaselineChange::Removed => ("removed", Vec::new(), None),
+                        BaselineChange::Unreadable(e) => {
+                            ("unreadable", Vec::new(), Some(e.clone()))
+                        }
+                    };
+                    BaselineJsonResult {
+                        path: path.clone(),
+                        status: status.to_string(),
+                        changes,
+                        error,
+                    }
+                })
+                .collect(),
+            summary,
+        };
+        println!("{}", serde_json::to_string(&json_output)?);
+    } else {
+        for (path, change) in &differences {
+            match change {
+                BaselineChange::Changed(changes) => {
+                    let names: Vec<&str> = changes.iter().map(|c| c.attribute).collect();
+                    println!("{}: CHANGED ({})", path, names.join(", "));
+                    for change in changes {
+                        println!("  {}: {} -> {}", change.attribute, change.old, change.new);
+                    }
+                }
+                BaselineChange::Added => println!("{}: ADDED", path),
+                BaselineChange::Removed => println!("{}: REMOVED", path),
+                BaselineChange::Unreadable(e) => println!("{}: FAILED ({})", path, e),
+            }
+        }
+
+        if !matches!(verbosity, Verbosity::Quiet) {
+            let unreadable = if summary.unreadable > 0 {
+                format!(", {} unreadable", summary.unreadable)
+            } else {
+                String::new()
+            };
+            println!(
+                "Summary: {} unchanged, {} changed, {} added, {} removed{}",
+                summary.unchanged, summary.changed, summary.added, summary.removed, unreadable
+            );
+        }
+    }
+
+    if mode == BaselineMode::Update {
+        // Keep the last known state of files that could not be read
+        for path in errors.keys() {
+            if let Some(record) = baseline.files.get(path) {
+                current.insert(path.clone(), record.clone());
+            }
+        }
+        baseline.files = current;
+        baseline.save(db_path)?;
+
+        if !matches!(verbosity, Verbosity::Quiet) && !json {
+            println!(
+                "Updated baseline: {} files recorded in {}",
+                baseline.files.len(),
+                db
+            );
+        }
+        return unreadable_error(&errors);
+    }
+
+    if !differences.is_empty() {
+        return Err(anyhow!(
+            "Baseline check failed: {} files differ from the baseline",
+            differences.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Hash and stat every file covered by a baseline, in parallel like batch
+/// mode. Returns the records of the files that were read, and the error for
+/// each file that was not, so one unreadable file does not stop the scan.
+fn scan_baseline(
+    baseline: &Baseline,
+    db_path: &std::path::Path,
+    verbosity: Verbosity,
+) -> Result<(BTreeMap<String, FileRecord>, BTreeMap<String, String>)> {
+    let algorithm = baseline.algorithm()?;
+
+    // A removed root is not an error: its files are reported as removed
+    let mut roots = Vec::new();
+    for root in &baseline.roots {
+        if std::path::Path::new(root).is_dir() {
+            roots.push(root.clone());
+        } else if !matches!(verbosity, Verbosity::Quiet) {
+            eprintln!("hashy: WARNING: {} is no longer a directory", root);
+        }
+    }
+
+    let mut files = collect_files(&roots, &baseline.walk_options())?;
+    files.retain(|f| !manifest::is_manifest_path(f, db_path));
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!("Using algorithm: {}", algorithm);
+        eprintln!("Scanning {} files", files.len());
+    }
+
+    let results = batch_hash_files(&files, verbosity, |file_path| {
+        hash_file_multi(&[algorithm], file_path, None)
+            .map(|digests| with_file_size(file_path, digests))
+    });
+
+    let mut records = BTreeMap::new();
+    let mut errors = BTreeMap::new();
+    for result in results {
+        let Some(digests) = result.digests else {
+            errors.insert(result.file_path, result.error.unwrap_or_default());
+            continue;
+        };
+        match std::fs::metadata(&result.file_path) {
+            Ok(metadata) => {
+                records.insert(
+                    result.file_path,
+                    FileRecord::new(
+                        OutputFormat::Hex.format_bytes(&digests[0], false),
+                        &metadata,
+                    ),
+                );
+            }
+            Err(e) => {
+                errors.insert(result.file_path, format!("Failed to read metadata: {}", e));
+            }
+        }
+    }
+
+    Ok((records, errors))
+}
+
+/// Fail a baseline run that could not read every file, after the rest of
+/// the tree has been scanned and reported
+fn unreadable_error(errors: &BTreeMap<String, String>) -> Result<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "{} {} could not be read",
+        errors.len(),
+        if errors.len() == 1 { "file" } else { "files" }
+    ))
+}
+
+/// Show statistics for, prune or clear a digest cache
+pub fn handle_cache(mode: CacheMode, dir: &str, verbosity: Verbosity) -> Result<()> {
+    let cache = DigestCache::open_existing(std::path::Path::new(dir))?;
+
+    match mode {
+        CacheMode::Stats => {
+            let stats = cache.stats()?;
+            println!("Cache: {}", dir);
+            println!("Entries: {}", stats.entries);
+            println!("Size: {} bytes", stats.bytes);
+            for (algorithm, count) in &stats.algorithms {
+                println!("  {}: {}", algorithm, count);
+            }
+        }
+        CacheMode::Prune => {
+            let removed = cache.prune()?;
+            if !matches!(verbosity, Verbosity::Quiet) {
+                println!("Removed {} stale cache entries", removed);
+            }
+        }
+        CacheMode::Clear => {
+            let removed = cache.clear()?;
+            if !matches!(verbosity, Verbosity::Quiet) {
+                println!("Removed {} cache entries", removed);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Arguments of `dupes`, as given on the command line
+#[derive(Debug, Clone, Default)]
+pub struct DupesArgs<'a> {
+    pub paths: &'a [String],
+    pub algo: &'a str,
+    pub allow_insecure: bool,
+    /// Smallest file size considered, in bytes
+    pub min_size: u64,
+    pub json: bool,
+    /// Print a `hardlink` or `delete` script instead of listing groups
+    pub script: Option<&'a str>,
+    /// Which files to consider
+    pub walk: WalkOptions,
+}
+
+/// Find groups of files with identical contents under the given paths.
+///
+/// Candidates are narrowed in three passes so that most files are never read
+/// in full: by size, by a hash of their first and last block, and finally by
+/// the full digest. Both hashing passes run in parallel like batch mode.
+/// Unreadable files are skipped with a warning.
+pub fn handle_dupes(args: DupesArgs<'_>, verbosity: Verbosity) -> Result<()> {
+    let DupesArgs {
+        paths,
+        algo: algo_str,
+        allow_insecure,
+        min_size,
+        json,
+        script,
+        walk,
+    } = args;
+    let algorithm: Algorithm = algo_str.parse()?;
+    check_insecure_allowed(algorithm, allow_insecure, verbosity)?;
+    let script = script.map(str::parse::<DupesScript>).transpose()?;
+
+    let files = collect_files(paths, &walk)?;
+    let mut skipped = 0;
+
+    // Pass 1: size. Further hard links to an already seen file are dropped.
+    let mut sizes = BTreeMap::new();
+    let mut identities = std::collections::HashSet::new();
+    for file in files {
+        match std::fs::metadata(&file) {
+            Ok(metadata) => {
+                if metadata.len() < min_size {
+                    continue;
+                }
+                if let Some(identity) = dupes::file_identity(&metadata) {
+                    if !identities.insert(identity) {
+                        continue;
+                    }
+                }
+                sizes.insert(file, metadata.len());
+            }
+            Err(e) => {
+                eprintln!("hashy: WARNING: skipping {}: {}", file, e);
+                skipped += 1;
+            }
+        }
+    }
+    let by_size = dupes::candidate_groups(sizes.iter().map(|(file, size)| (*size, file.clone())));
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!("Using algorithm: {}", algorithm);
+        eprintln!(
+            "Found {} files, {} share their size with another file",
+            sizes.len(),
+            by_size.iter().map(|(_, g)| g.len()).sum::<usize>()
+        );
+    }
+
+    // Pass 2: first and last block
+    let candidates: Vec<String> = by_size.into_iter().flat_map(|(_, g)| g).collect();
+    let results = batch_hash_files(&candidates, verbosity, |file_path| {
+        let size = sizes[file_path];
+        Ok((vec![dupes::partial_hash(algorithm, file_path, size)?], size))
+    });
+    let by_partial = dupes::candidate_groups(
+        collect_digests(results, &mut skipped).map(|(file, digest)| ((sizes[&file], digest), file)),
+    );
+
+    // Pass 3: full digest, except where the partial hash read the whole file
+    let mut digests = Vec::new();
+    let mut full_candidates = Vec::new();
+    for ((size, digest), group) in by_partial {
+        if dupes::partial_covers_file(size) {
+            digests.extend(group.into_iter().map(|file| (file, digest.clone())));
+        } else {
+            full_candidates.extend(group);
+        }
+    }
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!("Hashing {} files in full", full_candidates.len());
+    }
+
+    let results = batch_hash_files(&full_candidates, verbosity, |file_path| {
+        hash_file_multi(&[algorithm], file_path, None)
+            .map(|digests| with_file_size(file_path, digests))
+    });
+    digests.extend(collect_digests(results, &mut skipped));
+
+    let mut groups: Vec<DuplicateGroup> = dupes::candidate_groups(
+        digests
+            .into_iter()
+            .map(|(file, digest)| ((sizes[&file], digest), file)),
+    )
+    .into_iter()
+    .map(|((size, digest), mut files)| {
+        files.sort();
+        DuplicateGroup {
+            digest: OutputFormat::Hex.format_bytes(&digest, false),
+            size,
+            files,
+        }
+    })
+    .collect();
+    // Largest savings first
+    groups.sort_by(|a, b| {
+        b.reclaimable()
+            .cmp(&a.reclaimable())
+            .then_with(|| a.files.cmp(&b.files))
+    });
+
+    let summary = DupesSummary {
+        groups: groups.len(),
+        duplicates: groups.iter().map(|g| g.files.len() - 1).sum(),
+        reclaimable_bytes: groups.iter().map(DuplicateGroup::reclaimable).sum(),
+    };
+
+    if let Some(script) = script {
+        print!("{}", dupes::render_script(script, &groups));
+    } else if json {
+        let json_output = DupesJsonOutput {
+            algo: algorithm.name().to_string(),
+            groups,
+            summary,
+        };
+        println!("{}", serde_json::to_string(&json_output)?);
+    } else {
+        for (i, group) in groups.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            println!("{} ({} bytes each)", group.digest, group.size);
+            for file in &group.files {
+                println!("  {}", file);
+            }
+        }
+
+        if !matches!(verbosity, Verbosity::Quiet) {
+            if groups.is_empty() {
+                println!("No duplicate files found");
+            } else {
+                println!();
+                println!(
+                    "Summary: {} groups, {} duplicate files, {} bytes reclaimable",
+                    summary.groups, summary.duplicates, summary.reclaimable_bytes
+                );
+            }
+        }
+    }
+
+    if skipped > 0 {
+        eprintln!(
+            "hashy: WARNING: {} {} could not be read",
+            skipped,
+            if skipped == 1 { "file" } else { "files" }
+        );
+    }
+
+    Ok(())
+}
+
+/// Take the single digest of every successful batch result, warning about
+/// and counting the failures
+fn collect_digests(
+    results: Vec<BatchHashResult>,
+    skipped: &mut usize,
+) -> impl Iterator<Item = (String, Vec<u8>)> {
+    let mut digests = Vec::with_capacity(results.len());
+    for result in results {
+        match result.digests {
+            Some(mut d) => digests.push((result.file_path, d.remove(0))),
+            None => {
+                eprintln!(
+                    "hashy: WARNING: skipping {}: {}",
+                    result.file_path,
+                    result.error.unwrap_or_default()
+                );
+                *skipped += 1;
+            }
+        }
+    }
+    digests.into_iter()
+}
+
+/// Split files into content-defined chunks in parallel and summarize how
+/// much of the data is unique across all of them
+pub fn handle_chunks(
+    paths: &[String],
+    algo_str: &str,
+    allow_insecure: bool,
+    sizes: ChunkSizes,
+    json: bool,
+    walk: WalkOptions,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let algorithm: Algorithm = algo_str.parse()?;
+    check_insecure_allowed(algorithm, allow_insecure, verbosity)?;
+    sizes.validate()?;
+
+    let files = collect_files(paths, &walk)?;
+    if files.is_empty() {
+        return Err(anyhow!("No files found to chunk"));
+    }
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!("Using algorithm: {}", algorithm);
+        eprintln!(
+            "Chunking {} files (min {}, avg {}, max {} bytes)",
+            files.len(),
+            sizes.min,
+            sizes.avg,
+            sizes.max
+        );
+    }
+
+    // Using par_iter() which preserves order when collected
+    let results: Vec<Result<Vec<Chunk>>> = files
+        .par_iter()
+        .map(|file_path| {
+            if matches!(verbosity, Verbosity::Verbose) {
+                eprintln!("Chunking file: {}", file_path);
+            }
+            chunks::chunk_file(file_path, algorithm, sizes)
+        })
+        .collect();
+
+    let mut chunked = Vec::with_capacity(files.len());
+    for (path, result) in files.into_iter().zip(results) {
+        let chunks = result?;
+        chunked.push(ChunkedFileJson {
+            path,
+            size: chunks.iter().map(|c| c.length as u64).sum(),
+            chunks,
+        });
+    }
+
+    let summary = DedupSummary::from_chunks(chunked.len(), chunked.iter().flat_map(|f| &f.chunks));
+
+    if json {
+        let json_output = ChunksJsonOutput {
+            algo: algorithm.name().to_string(),
+            min_size: sizes.min,
+            avg_size: sizes.avg,
+            max_size: sizes.max,
+            files: chunked,
+            summary,
+        };
+        println!("{}", serde_json::to_string(&json_output)?);
+    } else if !matches!(verbosity, Verbosity::Quiet) {
+        for file in &chunked {
+            println!(
+                "{}: {} chunks, {} bytes",
+                file.path,
+                file.chunks.len(),
+                file.size
+            );
+        }
+        println!();
+        println!(
+            "Summary: {} chunks ({} unique), {} bytes ({} unique), dedup ratio {:.2}",
+            summary.chunks,
+            summary.unique_chunks,
+            summary.total_bytes,
+            summary.unique_bytes,
+            summary.dedup_ratio
+        );
+    }
+
+    Ok(())
+}
+
+/// Arguments of `merkle`, as given on the command line.
+/// Fields a subcommand does not take are empty.
+#[derive(Debug, Clone)]
+pub struct MerkleArgs<'a> {
+    pub mode: MerkleMode,
+    /// Paths to build from, or the single path to prove or verify
+    pub paths: &'a [String],
+    /// Tree file to prove from (prove)
+    pub tree: Option<&'a str>,
+    /// Proof file to check (verify)
+    pub proof: Option<&'a str>,
+    /// Expected root digest (verify)
+    pub root: Option<&'a str>,
+    pub output: Option<&'a str>,
+    /// Leaf algorithm (build)
+    pub algo: &'a str,
+    pub allow_insecure: bool,
+    /// Which files to include (build)
+    pub walk: WalkOptions,
+}
+
+/// Build a Merkle tree, produce an inclusion proof or verify one
+pub fn handle_merkle(args: MerkleArgs<'_>, verbosity: Verbosity) -> Result<()> {
+    let MerkleArgs {
+        mode,
+        paths,
+        tree,
+        proof,
+        root,
+        output,
+        algo: algo_str,
+        allow_insecure,
+        walk,
+    } = args;
+
+    match mode {
+        MerkleMode::Build => {
+            let algorithm: Algorithm = algo_str.parse()?;
+            check_insecure_allowed(algorithm, allow_insecure, verbosity)?;
+            let tree_path = std::path::Path::new(output.unwrap_or_default());
+
+            let mut files = collect_files(paths, &walk)?;
+            files.retain(|f| !manifest::is_manifest_path(f, tree_path));
+            if files.is_empty() {
+                return Err(anyhow!("No files found to hash"));
+            }
+
+            if matches!(verbosity, Verbosity::Verbose) {
+                eprintln!("Using algorithm: {}", algorithm);
+                eprintln!("Hashing {} files", files.len());
+            }
+
+            let results = batch_hash_files(&files, verbosity, |file_path| {
+                hash_file_multi(&[algorithm], file_path, None)
+                    .map(|digests| with_file_size(file_path, digests))
+            });
+
+            let mut leaves = Vec::with_capacity(results.len());
+            for result in results {
+                let Some(digests) = result.digests else {
+                    return Err(anyhow!(
+                        "Failed to hash file '{}': {}",
+                        result.file_path,
+                        result.error.unwrap_or_default()
+                    ));
+                };
+                leaves.push(MerkleLeaf {
+                    path: manifest::relative_entry_path(&result.file_path, tree_path)?,
+                    digest: OutputFormat::Hex.format_bytes(&digests[0], false),
+                });
+            }
+
+            let merkle_tree = MerkleTree::new(algorithm, leaves)?;
+            merkle_tree.save(tree_path)?;
+
+            if matches!(verbosity, Verbosity::Verbose) {
+                eprintln!(
+                    "Wrote Merkle tree of {} files to {}",
+                    merkle_tree.leaves.len(),
+                    tree_path.display()
+                );
+            }
+            println!("{}", merkle_tree.root);
+        }
+        MerkleMode::Prove => {
+            let tree_path = std::path::Path::new(tree.unwrap_or_default());
+            let merkle_tree = MerkleTree::load(tree_path)?;
+            let leaf_path = manifest::relative_entry_path(&paths[0], tree_path)?;
+            let inclusion_proof = merkle_tree.prove(&leaf_path)?;
+
+            match output {
+                Some(output) => {
+                    inclusion_proof.save(std::path::Path::new(output))?;
+                    if !matches!(verbosity, Verbosity::Quiet) {
+                        println!(
+                            "Wrote proof for {} (leaf {} of {}) to {}",
+                            inclusion_proof.path,
+                            inclusion_proof.index + 1,
+                            inclusion_proof.leaf_count,
+                            output
+                        );
+                    }
+                }
+                None => println!("{}", serde_json::to_string(&inclusion_proof)?),
+            }
+        }
+        MerkleMode::Verify => {
+            let inclusion_proof =
+                InclusionProof::load(std::path::Path::new(proof.unwrap_or_default()))?;
+            let algorithm = inclusion_proof.algorithm()?;
+            check_insecure_allowed(algorithm, allow_insecure, verbosity)?;
+
+            let root = root.unwrap_or_default().trim();
+            let root_bytes =
+                hex::decode(root).with_context(|| format!("Invalid Merkle root: {}", root))?;
+
+            let file_path = &paths[0];
+            let digest = hash_file_multi(&[algorithm], file_path, None)?.remove(0);
+
+            let failure = if !hex::encode(&digest).eq_ignore_ascii_case(&inclusion_proof.digest) {
+                Some(format!("contents differ from {}", inclusion_proof.path))
+            } else if !inclusion_proof.verify(&digest, &root_bytes)? {
+                Some(format!("not included in root {}", root))
+            } else {
+                None
+            };
+
+            match failure {
+                Some(reason) => {
+                    println!("{}: FAILED ({})", file_path, reason);
+                    return Err(anyhow!("Merkle proof verification failed: {}", file_path));
+                }
+                None => {
+                    if !matches!(verbosity, Verbosity::Quiet) {
+                        println!(
+                            "{}: OK ({}, leaf {} of {})",
+                            file_path,
+                            inclusion_proof.path,
+                            inclusion_proof.index + 1,
+                            inclusion_proof.leaf_count
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Arguments of `hmac`, as given on the command line. Not `Debug` or
+/// `Clone`, so the key is neither printed nor copied.
+#[derive(Default)]
+pub struct HmacArgs<'a> {
+    pub algo: &'a str,
+    pub allow_insecure: bool,
+    pub key_source: Option<KeySource>,
+    pub insecure_key_arg: bool,
+    pub text: Option<&'a str>,
+    pub file: Option<&'a str>,
+    pub files: &'a [String],
+    /// Expected tag to check against
+    pub verify: Option<&'a str>,
+    pub continue_on_error: bool,
+    pub format: Option<&'a str>,
+    pub uppercase: bool,
+    pub json: bool,
+}
+
+/// Compute (or check with --verify) a keyed hash of text, a file, STDIN or a
+/// batch of files
+pub fn handle_hmac(args: HmacArgs<'_>, verbosity: Verbosity) -> Result<()> {
+    let HmacArgs {
+        algo: algo_str,
+        allow_insecure,
+        key_source,
+        insecure_key_arg,
+        text,
+        file,
+        files,
+        verify,
+        continue_on_error,
+        format,
+        uppercase,
+        json,
+    } = args;
+
+    // Parse algorithm
+    let algorithm: Algorithm = algo_str.parse()?;
+
+    // Check security gating
+    check_insecure_allowed(algorithm, allow_insecure, verbosity)?;
+
+    // Keys on argv leak via the process list and shell history
+    let key_source =
+        key_source.ok_or_else(|| anyhow!("No key provided. Use --key-file or --key-env."))?;
+    if matches!(key_source, KeySource::Hex(_)) && !insecure_key_arg {
+        return Err(anyhow!(
+            "Refusing to read a key from the command line, where it is visible to other users and saved in shell history. Use --key-file or --key-env, or pass --insecure-key-arg to allow --key-hex."
+        ));
+    }
+
+    // Zeroized when dropped at the end of this function
+    let key = load_key(&key_source)?;
+
+    // Fail fast on keys the algorithm can't use (e.g. wrong BLAKE3 key size)
+    KeyedHasher::new(algorithm, &key)?;
+
+    let algo_name = mac_name(algorithm);
+
+    // Determine output format
+    let output_format = if json {
+        None // JSON output doesn't use format enum
+    } else if let Some(format_str) = format {
+        Some(parse_output_format(format_str)?)
+    } else {
+        None // Default multi-line format
+    };
+
+    let expected_tag = verify
+        .map(|tag| hex::decode(tag.trim()).context("Invalid tag: expected a hex string"))
+        .transpose()?;
+
+    // Handle batch mode (multiple files)
+    if !files.is_empty() {
+        if matches!(verbosity, Verbosity::Verbose) {
+            eprintln!("Using algorithm: {}", algo_name);
+            eprintln!("Hashing {} files", files.len());
+        }
+
+        let results = batch_hash_files(files, verbosity, |file_path| {
+            mac_file(algorithm, &key, file_path).map(|tag| with_file_size(file_path, vec![tag]))
+        });
+
+        return report_batch_results(
+            &[algo_name],
+            &results,
+            continue_on_error,
+            output_format,
+            uppercase,
+            json,
+            false,
+            false,
+            &[],
+            false,
+            None,
+            verbosity,
+        );
+    }
+
+    // Resolve input source for single input
+    let input_source = resolve_input_source(text, file)?;
+
+    let source_name = match &input_source {
+        InputSource::Text(_) => "text".to_string(),
+        InputSource::File(_) => "file".to_string(),
+        InputSource::Stdin => "stdin".to_string(),
+    };
+
+    let display_label = match &input_source {
+        InputSource::Text(t) => Some(format!("Text: {}", t)),
+        InputSource::File(f) => Some(format!("File: {}", f)),
+        InputSource::Stdin => None, // Don't print label for STDIN
+    };
+
+    if matches!(verbosity, Verbosity::Verbose) {
+        eprintln!("Using algorithm: {}", algo_name);
+        match &input_source {
+            InputSource::Text(t) => eprintln!("Hashing text: {}", t),
+            InputSource::File(f) => eprintln!("Hashing file: {}", f),
+            InputSource::Stdin => eprintln!("Hashing from STDIN"),
+        }
+    }
+
+    let (tag, input_size) = match input_source {
+        InputSource::Text(t) => (mac_data(algorithm, &key, t.as_bytes())?, t.len()),
+        InputSource::File(f) => {
+            let tag = mac_file(algorithm, &key, &f)
+                .with_context(|| format!("Failed to hash file: {}", f))?;
+            let size = std::fs::metadata(&f).map(|m| m.len() as usize).unwrap_or(0);
+            (tag, size)
+        }
+        InputSource::Stdin => mac_stdin(algorithm, &key).context("Failed to hash STDIN")?,
+    };
+
+    // Verification mode: compare in constant time and report OK/FAILED
+    if let Some(expected) = expected_tag {
+        let matched = tags_equal(&expected, &tag);
+
+        if !matches!(verbosity, Verbosity::Quiet) {
+            if json {
+                let json_output = HashJsonOutput {
+                    algo: algo_name.clone(),
+                    source: source_name,
+                    digest: OutputFormat::Hex.format_bytes(&tag, false),
+                    digests: None,
+                    bytes: input_size,
+                    length: None,
+                    range: None,
+                    file_path: None,
+                    success: Some(matched),
+                    error: None,
+                };
+                println!("{}", serde_json::to_string(&json_output)?);
+            } else if matched {
+                println!("OK");
+            } else {
+                println!("FAILED");
+            }
+        }
+
+        if !matched {
+            return Err(anyhow!("MAC verification failed"));
+        }
+        return Ok(());
+    }
+
+    if !matches!(verbosity, Verbosity::Quiet) {
+        print_single_digest(
+            &algo_name,
+            source_name,
+            display_label,
+            &tag,
+            input_size,
+            None,
+            None,
+            output_format,
+            uppercase,
+            json,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Arguments of `bench`, as given on the command line
+#[derive(Debug, Clone, Default)]
+pub struct BenchArgs<'a> {
+    /// Algorithms to measure (empty: all)
+    pub algo: &'a [String],
+    /// Buffer sizes in bytes (empty: defaults)
+    pub sizes: &'a [usize],
+    /// Size of the temp file in bytes
+    pub file_size: u64,
+    /// I/O modes for the file benchmark (empty: defaults)
+    pub io: &'a [String],
+    /// Directory for the temp file
+    pub dir: Option<&'a str>,
+    /// Time spent on each measurement, in milliseconds
+    pub duration_ms: u64,
+    pub json: bool,
+}
+
+/// Measure the throughput of each algorithm on in-memory buffers and on a
+/// temp file, and print it as tables or JSON
+pub fn handle_bench(args: BenchArgs<'_>, verbosity: Verbosity) -> Result<()> {
+    let BenchArgs {
+        algo: algo_strs,
+        sizes,
+        file_size,
+        io: io_strs,
+        dir,
+        duration_ms,
+        json,
+    } = args;
+
+    let algorithms = if algo_strs.is_empty() {
+        Algorithm::ALL.to_vec()
+    } else {
+        parse_algorithms(algo_strs)?
+    };
+
+    let sizes = if sizes.is_empty() {
+        bench::DEFAULT_BUFFER_SIZES.to_vec()
+    } else {
+        sizes.to_vec()
+    };
+    if sizes.contains(&0) {
+        return Err(anyhow!("Buffer sizes must be at least 1 byte"));
+    }
+
+    // Modes given explicitly must work; of the defaults, io_uring is
+    // skipped where the kernel does not allow it
+    let explicit_io = !io_strs.is_empty();
+    let io_modes: Vec<(String, IoMode)> = if explicit_io {
+        io_strs
+            .iter()
+            .map(|s| Ok((s.trim().to_lowercase(), s.parse::<IoMode>()?)))
+            .collect::<Result<_>>()?
+    } else {
+        let mut modes = vec![
+            ("read".to_string(), IoMode::Read),
+            ("mmap".to_string(), IoMode::Mmap),
+        ];
+        if cfg!(target_os = "linux") {
+            modes.push(("uring".to_string(), IoMode::Uring));
+        }
+        modes
+    };
+
+    let duration = std::time::Duration::from_millis(duration_ms);
+    let host = HostInfo::detect();
+
+    let mut memory = Vec::with_capacity(algorithms.len() * sizes.len());
+    for algorithm in &algorithms {
+        for &size in &sizes {
+            if matches!(verbosity, Verbosity::Verbose) {
+                eprintln!(
+                    "Measuring {} on {} buffers",
+                    algorithm,
+                    bench::size_label(size as u64)
+                );
+            }
+            memory.push(bench::bench_memory(*algorithm, size, duration)?);
+        }
+    }
+
+    let mut file = Vec::new();
+    let mut measured_io = Vec::new();
+    if file_size > 0 {
+        let dir = dir.map_or_else(std::env::temp_dir, PathBuf::from);
+        if matches!(verbosity, Verbosity::Verbose) {
+            eprintln!(
+                "Writing {} benchmark file in {}",
+                bench::size_label(file_size),
+                dir.display()
+            );
+        }
+        let bench_file = BenchFile::create(&dir, file_size)?;
+
+        for (name, mode) in io_modes {
+            if !explicit_io {
+                if let Err(e) = bench::check_io_mode(bench_file.path(), mode) {
+                    if !matches!(verbosity, Verbosity::Quiet) {
+                        eprintln!("hashy: WARNING: skipping --io {}: {:#}", name, e);
+                    }
+                    continue;
+                }
+            }
+            measured_io.push((name, mode));
+        }
+
+        for algorithm in &algorithms {
+            for (name, mode) in &measured_io {
+                if matches!(verbosity, Verbosity::Verbose) {
+                    eprintln!("Measuring {} on the file with --io {}", algorithm, name);
+                }
+                file.push(bench::bench_file(
+                    *algorithm,
+                    bench_file.path(),
+                    name,
+                    *mode,
+                    duration,
+                )?);
+            }
+        }
+    }
+
+    if json {
+        let json_output = BenchJsonOutput {
+            host,
+            duration_ms,
+            memory,
+            file_size,
+            file,
+        };
+        println!("{}", serde_json::to_string(&json_output)?);
+        return Ok(());
+    }
+    if matches!(verbosity, Verbosity::Quiet) {
+        return Ok(());
+    }
+
+    println!(
+        "Host: {} {}, {} {}, CPU features: {}",
+        host.os,
+        host.arch,
+        host.threads,
+        if host.threads == 1 {
+            "thread"
+        } else {
+            "threads"
+        },
+        if host.cpu_features.is_empty() {
+            "none detected".to_string()
+        } else {
+            host.cpu_features.join(" ")
+        }
+    );
+
+    // Both result lists hold one run of columns per algorithm, in order
+    let columns: Vec<String> = sizes.iter().map(|&s| bench::size_label(s as u64)).collect();
+    let rates: Vec<f64> = memory.iter().map(|r| r.measurement.mb_per_sec).collect();
+    println!();
+    println!("In-memory throughput (MB/s)");
+    print_bench_table(&algorithms, &columns, &rates);
+
+    if !measured_io.is_empty() {
+        let columns: Vec<String> = measured_io.into_iter().map(|(name, _)| name).collect();
+        let rates: Vec<f64> = file.iter().map(|r| r.measurement.mb_per_sec).collect();
+        println!();
+        println!(
+            "File throughput (MB/s, {} file in the page cache)",
+            bench::size_label(file_size)
+        );
+        print_bench_table(&algorithms, &columns, &rates);
+    }
+
+    Ok(())
+}
+
+/// Print one row per algorithm and one column per label. `rates` holds the
+/// rows one after another.
+fn print_bench_table(algorithms: &[Algorithm], columns: &[String], rates: &[f64]) {
+    let name_width = algorithms
+        .iter()
+        .map(|a| a.name().len())
+        .max()
+        .unwrap_or(0)
+        .max("algorithm".len());
+    let widths: Vec<usize> = columns.iter().map(|c| c.len().max(9)).collect();
+
+    let mut header = format!("{:<width$}", "algorithm", width = name_width);
+    for (column, width) in columns.iter().zip(&widths) {
+        header.push_str(&format!("  {:>width$}", column, width = width));
+    }
+    println!("{}", header);
+
+    for (algorithm, row) in algorithms.iter().zip(rates.chunks(columns.len())) {
+        let mut line = format!("{:<width$}", algorithm.name(), width = name_width);
+        for (rate, width) in row.iter().zip(&widths) {
+            line.push_str(&format!("  {:>width$.1}", rate, width = width));
+        }
+        println!("{}", line);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::algorithm::Algorithm;
+use crate::hash::{hash_reader_multi, hashers_for};
+use anyhow::{anyhow, Context, Result};
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{stdin, BufReader, Cursor, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use xz2::read::XzDecoder;
+
+/// Longest magic number among the supported formats (xz)
+const MAGIC_LEN: usize = 6;
+
+/// Compression formats `--decompress` can undo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+}
+
+impl Compression {
+    const ALL: [Compression; 4] = [
+        Compression::Gzip,
+        Compression::Zstd,
+        Compression::Xz,
+        Compression::Bzip2,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+            Compression::Bzip2 => "bzip2",
+        }
+    }
+
+    /// Usual file extension, without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+            Compression::Xz => "xz",
+            Compression::Bzip2 => "bz2",
+        }
+    }
+
+    /// Detect the format from the first bytes of a stream
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if header.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else {
+            None
+        }
+    }
+}
+
+/// What `--decompress` was asked to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decompress {
+    /// Detect the format by its magic bytes; uncompressed input is hashed
+    /// as it is
+    Auto,
+    /// Expect this format and fail on anything else
+    Format(Compression),
+}
+
+impl FromStr for Decompress {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Decompress::Auto),
+            "gzip" | "gz" => Ok(Decompress::Format(Compression::Gzip)),
+            "zstd" | "zst" => Ok(Decompress::Format(Compression::Zstd)),
+            "xz" => Ok(Decompress::Format(Compression::Xz)),
+            "bzip2" | "bz2" => Ok(Decompress::Format(Compression::Bzip2)),
+            _ => Err(anyhow!(
+                "Unsupported compression: {}. Supported: auto, gzip, zstd, xz, bzip2",
+                s
+            )),
+        }
+    }
+}
+
+impl Decompress {
+    /// Where a compressed copy of `path` may be stored: `path` itself, then
+    /// `path` with each extension this mode accepts (`disk.img.gz`, ...)
+    pub fn candidate_paths(&self, path: &Path) -> Vec<PathBuf> {
+        let formats: &[Compression] = match self {
+            Decompress::Auto => &Compression::ALL,
+            Decompress::Format(format) => std::slice::from_ref(format),
+        };
+
+        let mut candidates = vec![path.to_path_buf()];
+        for format in formats {
+            let mut candidate = path.as_os_str().to_owned();
+            candidate.push(".");
+            candidate.push(format.extension());
+            candidates.push(PathBuf::from(candidate));
+        }
+        candidates
+    }
+}
+
+/// Wrap `reader` so it yields decompressed data.
+///
+/// The first bytes are read to identify the format. In `Auto` mode input
+/// without a known magic number is passed through unchanged.
+pub fn decompressing_reader<'a, R: Read + 'a>(
+    mut reader: R,
+    mode: Decompress,
+) -> Result<Box<dyn Read + 'a>> {
+    let mut header = Vec::with_capacity(MAGIC_LEN);
+    let mut chunk = [0u8; MAGIC_LEN];
+    while header.len() < MAGIC_LEN {
+        match reader.read(&mut chunk[..MAGIC_LEN - header.len()]) {
+            Ok(0) => break,
+            Ok(n) => header.extend_from_slice(&chunk[..n]),
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e).context("Failed to read input"),
+        }
+    }
+
+    let detected = Compression::detect(&header);
+    let format = match (mode, detected) {
+        (Decompress::Auto, detected) => detected,
+        (Decompress::Format(expected), Some(detected)) if expected == detected => Some(expected),
+        (Decompress::Format(expected), _) => {
+            return Err(anyhow!("Input is not {}-compressed", expected.name()));
+        }
+    };
+
+    let input = Cursor::new(header).chain(reader);
+    Ok(match format {
+        None => Box::new(input),
+        Some(Compression::Gzip) => Box::new(MultiGzDecoder::new(input)),
+        Some(Compression::Zstd) => Box::new(
+            zstd::stream::read::Decoder::new(input).context("Failed to start zstd decoder")?,
+        ),
+        Some(Compression::Xz) => Box::new(XzDecoder::new_multi_decoder(input)),
+        Some(Compression::Bzip2) => Box::new(MultiBzDecoder::new(input)),
+    })
+}
+
+/// Hash the decompressed contents of a file with several algorithms.
+/// Returns (one digest per algorithm, decompressed size)
+pub fn hash_file_decompressed(
+    algorithms: &[Algorithm],
+    file_path: &str,
+    length: Option<usize>,
+    mode: Decompress,
+) -> Result<(Vec<Vec<u8>>, u64)> {
+    let hashers = hashers_for(algorithms, length)?;
+
+    let file =
+        File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
+    let reader = decompressing_reader(BufReader::new(file), mode)
+        .with_context(|| format!("Failed to decompress file: {}", file_path))?;
+
+    hash_reader_multi(hashers, reader)
+        .with_context(|| format!("Failed to decompress file: {}", file_path))
+}
+
+/// Hash the decompressed contents of STDIN with several algorithms.
+/// Returns (one digest per algorithm, decompressed size)
+pub fn hash_stdin_decompressed(
+    algorithms: &[Algorithm],
+    length: Option<usize>,
+    mode: Decompress,
+) -> Result<(Vec<Vec<u8>>, u64)> {
+    let hashers = hashers_for(algorithms, length)?;
+
+    let stdin_handle = stdin();
+    let reader =
+        decompressing_reader(stdin_handle.lock(), mode).context("Failed to decompress STDIN")?;
+
+    hash_reader_multi(hashers, reader).context("Failed to decompress STDIN")
+}
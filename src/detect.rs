@@ -0,0 +1,151 @@
+use crate::algorithm::Algorithm;
+use crate::manifest;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// How the algorithm for untagged manifest lines was chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectionSource {
+    /// Named by the manifest file (e.g. `SHA512SUMS`, `release.md5`)
+    FileName,
+    /// The only algorithm producing digests of the length found
+    DigestLength,
+    /// SHA-256 fallback when detection is not conclusive
+    Default,
+}
+
+/// Algorithm inferred for a manifest's untagged lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detection {
+    pub algorithm: Algorithm,
+    pub source: DetectionSource,
+}
+
+/// Parse a file-name component as an algorithm, accepting the `b3` shorthand
+/// used by `b3sum`
+fn parse_name_hint(s: &str) -> Option<Algorithm> {
+    match s {
+        "b3" => Some(Algorithm::Blake3),
+        _ => s.parse().ok(),
+    }
+}
+
+/// Algorithm named by a manifest file name: `SHA256SUMS`, `B3SUMS`,
+/// `release.sha512`, `files.md5`, or `sha256.sums` as written by
+/// `hash --manifest '{algo}.sums'`
+pub fn algorithm_from_file_name(path: &Path) -> Option<Algorithm> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+
+    let mut hints = vec![name.as_str()];
+    if let Some((stem, ext)) = name.rsplit_once('.') {
+        hints.push(ext);
+        hints.push(stem);
+    }
+
+    hints.into_iter().find_map(|hint| {
+        let without_sums = hint
+            .strip_suffix("sums")
+            .or_else(|| hint.strip_suffix("sum"))
+            .map(|s| s.trim_end_matches(['.', '-', '_']));
+        without_sums
+            .and_then(parse_name_hint)
+            .or_else(|| parse_name_hint(hint))
+    })
+}
+
+/// Check whether a field looks like a hex digest
+fn is_hex_digest(s: &str) -> bool {
+    !s.is_empty() && s.len().is_multiple_of(2) && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Lengths (in hex characters) of the digests on untagged checksum lines
+fn untagged_digest_lengths(contents: &str) -> BTreeSet<usize> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| manifest::parse_tagged_line(line).is_none())
+        // Lines with escaped paths start with a backslash
+        .map(|line| line.strip_prefix('\\').unwrap_or(line))
+        .filter_map(|line| match manifest::split_line(line) {
+            (left, Some(_)) if is_hex_digest(left) => Some(left.len()),
+            (_, Some(right)) if is_hex_digest(right) => Some(right.len()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Infer the algorithm for the untagged lines of a hex manifest.
+///
+/// The file name wins if its digest size matches the lines; otherwise the
+/// digest length must identify a single algorithm. When several algorithms
+/// fit (SHA-256, BLAKE3 and SHA3-256 all give 64 hex characters), an
+/// explicit `--algo auto` is an error, while the implicit default falls back
+/// to SHA-256 if it is among them, matching `sha256sum`.
+pub fn detect_algorithm(
+    manifest_path: &Path,
+    contents: &str,
+    length: Option<usize>,
+    explicit: bool,
+) -> Result<Detection> {
+    let lengths = untagged_digest_lengths(contents);
+    let fits = |algorithm: &Algorithm| {
+        algorithm
+            .resolve_output_size(length)
+            .is_ok_and(|size| lengths.iter().all(|&len| len == size * 2))
+    };
+
+    if let Some(algorithm) = algorithm_from_file_name(manifest_path).filter(fits) {
+        return Ok(Detection {
+            algorithm,
+            source: DetectionSource::FileName,
+        });
+    }
+
+    let hex_len = match lengths.iter().collect::<Vec<_>>().as_slice() {
+        // Nothing untagged to verify (or no hex digests at all)
+        [] => {
+            return Ok(Detection {
+                algorithm: Algorithm::Sha256,
+                source: DetectionSource::Default,
+            })
+        }
+        [len] => **len,
+        _ => {
+            return Err(anyhow!(
+                "Cannot detect the algorithm: the manifest mixes digests of {} hex characters. Use --algo or tagged lines.",
+                lengths
+                    .iter()
+                    .map(|len| len.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        }
+    };
+
+    let candidates: Vec<Algorithm> = Algorithm::ALL.into_iter().filter(fits).collect();
+    match candidates.as_slice() {
+        [] => Err(anyhow!(
+            "Cannot detect the algorithm: no supported algorithm produces {}-character digests",
+            hex_len
+        )),
+        [algorithm] => Ok(Detection {
+            algorithm: *algorithm,
+            source: DetectionSource::DigestLength,
+        }),
+        _ if !explicit && candidates.contains(&Algorithm::Sha256) => Ok(Detection {
+            algorithm: Algorithm::Sha256,
+            source: DetectionSource::Default,
+        }),
+        _ => Err(anyhow!(
+            "Ambiguous algorithm: {}-character digests could be {}. Use --algo to choose one.",
+            hex_len,
+            candidates
+                .iter()
+                .map(|a| a.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
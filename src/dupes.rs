@@ -0,0 +1,147 @@
+use crate::algorithm::Algorithm;
+use crate::hash::hash_data;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{File, Metadata};
+use std::io::{Read, Seek, SeekFrom};
+use std::str::FromStr;
+
+/// Bytes read from each end of a file for the partial hash
+pub const PARTIAL_BLOCK_SIZE: u64 = 4096;
+
+/// Shell script `hashy dupes --script` writes instead of the group listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DupesScript {
+    /// Replace every duplicate with a hard link to the first file of its group
+    Hardlink,
+    /// Delete every duplicate, keeping the first file of its group
+    Delete,
+}
+
+impl FromStr for DupesScript {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "hardlink" => Ok(DupesScript::Hardlink),
+            "delete" => Ok(DupesScript::Delete),
+            _ => Err(anyhow!(
+                "Unsupported script: {}. Supported: hardlink, delete",
+                s
+            )),
+        }
+    }
+}
+
+/// A set of files with identical contents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicateGroup {
+    /// Hex-encoded full digest shared by every file
+    pub digest: String,
+    /// Size of each file in bytes
+    pub size: u64,
+    /// Paths in sorted order; the first one is kept by the scripts
+    pub files: Vec<String>,
+}
+
+impl DuplicateGroup {
+    /// Bytes freed by keeping a single copy
+    pub fn reclaimable(&self) -> u64 {
+        self.size * (self.files.len() as u64 - 1)
+    }
+}
+
+/// Whether the partial hash of a file of this size already covers the
+/// whole file, and is therefore its full digest
+pub fn partial_covers_file(size: u64) -> bool {
+    size <= 2 * PARTIAL_BLOCK_SIZE
+}
+
+/// Hash the first and last `PARTIAL_BLOCK_SIZE` bytes of a file.
+///
+/// Files up to two blocks long are read whole, so for them the result equals
+/// the digest `hash_file` would compute.
+pub fn partial_hash(algorithm: Algorithm, file_path: &str, size: u64) -> Result<Vec<u8>> {
+    let mut file =
+        File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
+
+    let mut data = Vec::with_capacity(size.min(2 * PARTIAL_BLOCK_SIZE) as usize);
+    if partial_covers_file(size) {
+        file.read_to_end(&mut data)?;
+    } else {
+        let mut block = vec![0u8; PARTIAL_BLOCK_SIZE as usize];
+        file.read_exact(&mut block)?;
+        data.extend_from_slice(&block);
+        file.seek(SeekFrom::End(-(PARTIAL_BLOCK_SIZE as i64)))?;
+        file.read_exact(&mut block)?;
+        data.extend_from_slice(&block);
+    }
+
+    Ok(hash_data(algorithm, &data))
+}
+
+/// Identity of the underlying file (`(device, inode)`), so several hard
+/// links to one file are not reported as duplicates. `None` off Unix.
+pub fn file_identity(metadata: &Metadata) -> Option<(u64, u64)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some((metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// Group paths by key, keeping only groups with at least two members.
+/// Paths keep their relative order within a group.
+pub fn candidate_groups<K: Ord>(
+    items: impl IntoIterator<Item = (K, String)>,
+) -> Vec<(K, Vec<String>)> {
+    let mut groups: BTreeMap<K, Vec<String>> = BTreeMap::new();
+    for (key, path) in items {
+        groups.entry(key).or_default().push(path);
+    }
+    groups.into_iter().filter(|(_, g)| g.len() > 1).collect()
+}
+
+/// Quote a path for a POSIX shell
+fn shell_quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', r"'\''"))
+}
+
+/// Render a POSIX shell script that hardlinks or deletes every duplicate,
+/// keeping the first file of each group
+pub fn render_script(script: DupesScript, groups: &[DuplicateGroup]) -> String {
+    let action = match script {
+        DupesScript::Hardlink => {
+            "replace duplicates with hard links to the first file of each group"
+        }
+        DupesScript::Delete => "delete duplicates, keeping the first file of each group",
+    };
+
+    let mut out = String::new();
+    out.push_str("#!/bin/sh\n");
+    out.push_str(&format!("# Generated by hashy dupes: {}\n", action));
+    out.push_str("set -e\n");
+
+    for group in groups {
+        let (keep, duplicates) = group.files.split_first().expect("groups have two files");
+        out.push_str(&format!("\n# {} ({} bytes)\n", group.digest, group.size));
+        for duplicate in duplicates {
+            let line = match script {
+                DupesScript::Hardlink => {
+                    format!("ln -f -- {} {}", shell_quote(keep), shell_quote(duplicate))
+                }
+                DupesScript::Delete => format!("rm -f -- {}", shell_quote(duplicate)),
+            };
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+
+    out
+}
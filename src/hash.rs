@@ -1,228 +1,400 @@
 use crate::algorithm::Algorithm;
-use anyhow::{Context, Result};
-use digest::Digest;
+use crate::io::{hash_file_io, IoMode};
+use anyhow::{anyhow, Context, Result};
+use digest::{Digest, ExtendableOutput};
 use md5::Md5;
 use sha1::Sha1;
 use sha2::{Sha256, Sha512};
+use sha3::{Keccak256, Keccak512, Sha3_224, Sha3_256, Sha3_384, Sha3_512, Shake128, Shake256};
 use std::fs::File;
-use std::io::{stdin, BufReader, Read};
+use std::io::{stdin, BufReader, ErrorKind, Read, Seek, SeekFrom};
+
+/// Size of the buffer used when streaming input into a hasher
+const CHUNK_SIZE: usize = 64 * 1024; // 64 KiB
+
+/// Streaming hasher for any supported algorithm.
+///
+/// The trait is object-safe, so callers can pick an algorithm at runtime and
+/// work with a `Box<dyn Hasher>` (see [`Algorithm::hasher`]).
+pub trait Hasher: Send {
+    /// Feed more input into the hasher
+    fn update(&mut self, data: &[u8]);
+
+    /// Feed a large block of input, spreading the work over the current
+    /// rayon thread pool when the algorithm allows it (BLAKE3 only). The
+    /// digest is the same as with `update`.
+    fn update_parallel(&mut self, data: &[u8]) {
+        self.update(data);
+    }
+
+    /// Consume the hasher and return the raw digest bytes
+    fn finalize(self: Box<Self>) -> Vec<u8>;
+
+    /// Size in bytes of the digest returned by `finalize`
+    fn output_size(&self) -> usize;
+
+    /// Algorithm this hasher computes
+    fn algorithm(&self) -> Algorithm;
+}
+
+/// Fixed-output hashers from the RustCrypto `digest` family
+struct DigestHasher<D> {
+    inner: D,
+    algorithm: Algorithm,
+}
+
+impl<D: Digest + Send> Hasher for DigestHasher<D> {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(&mut self.inner, data);
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.inner.finalize().to_vec()
+    }
+
+    fn output_size(&self) -> usize {
+        <D as Digest>::output_size()
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+}
+
+/// SHAKE128/SHAKE256 reading `output_size` bytes of extendable output
+struct XofHasher<X> {
+    inner: X,
+    algorithm: Algorithm,
+    output_size: usize,
+}
+
+impl<X: ExtendableOutput + digest::Update + Send> Hasher for XofHasher<X> {
+    fn update(&mut self, data: &[u8]) {
+        digest::Update::update(&mut self.inner, data);
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        let mut output = vec![0u8; self.output_size];
+        self.inner.finalize_xof_into(&mut output);
+        output
+    }
+
+    fn output_size(&self) -> usize {
+        self.output_size
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+}
+
+/// BLAKE3 reading `output_size` bytes of extendable output.
+/// The first 32 bytes are identical to the default BLAKE3 digest.
+struct Blake3Hasher {
+    inner: blake3::Hasher,
+    output_size: usize,
+}
+
+impl Hasher for Blake3Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    fn update_parallel(&mut self, data: &[u8]) {
+        self.inner.update_rayon(data);
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        let mut output = vec![0u8; self.output_size];
+        self.inner.finalize_xof().fill(&mut output);
+        output
+    }
+
+    fn output_size(&self) -> usize {
+        self.output_size
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::Blake3
+    }
+}
+
+/// Create a hasher producing `output_size` bytes.
+/// Callers validate the size via `Algorithm::resolve_output_size` first.
+pub(crate) fn new_hasher(algorithm: Algorithm, output_size: usize) -> Box<dyn Hasher> {
+    fn digest<D: Digest + Send + 'static>(algorithm: Algorithm) -> Box<dyn Hasher> {
+        Box::new(DigestHasher {
+            inner: D::new(),
+            algorithm,
+        })
+    }
 
-/// Hash data using the specified algorithm, returns raw bytes
-pub fn hash_data(algorithm: Algorithm, data: &[u8]) -> Vec<u8> {
     match algorithm {
-        Algorithm::Sha1 => {
-            let mut hasher = Sha1::new();
-            hasher.update(data);
-            hasher.finalize().to_vec()
-        }
-        Algorithm::Sha256 => {
-            let mut hasher = Sha256::new();
-            hasher.update(data);
-            hasher.finalize().to_vec()
-        }
-        Algorithm::Sha512 => {
-            let mut hasher = Sha512::new();
-            hasher.update(data);
-            hasher.finalize().to_vec()
-        }
-        Algorithm::Blake3 => {
-            let hash = blake3::hash(data);
-            hash.as_bytes().to_vec()
-        }
-        Algorithm::Md5 => {
-            let mut hasher = Md5::new();
-            hasher.update(data);
-            hasher.finalize().to_vec()
-        }
+        Algorithm::Sha1 => digest::<Sha1>(algorithm),
+        Algorithm::Sha256 => digest::<Sha256>(algorithm),
+        Algorithm::Sha512 => digest::<Sha512>(algorithm),
+        Algorithm::Md5 => digest::<Md5>(algorithm),
+        Algorithm::Sha3_224 => digest::<Sha3_224>(algorithm),
+        Algorithm::Sha3_256 => digest::<Sha3_256>(algorithm),
+        Algorithm::Sha3_384 => digest::<Sha3_384>(algorithm),
+        Algorithm::Sha3_512 => digest::<Sha3_512>(algorithm),
+        Algorithm::Keccak256 => digest::<Keccak256>(algorithm),
+        Algorithm::Keccak512 => digest::<Keccak512>(algorithm),
+        Algorithm::Blake3 => Box::new(Blake3Hasher {
+            inner: blake3::Hasher::new(),
+            output_size,
+        }),
+        Algorithm::Shake128 => Box::new(XofHasher {
+            inner: Shake128::default(),
+            algorithm,
+            output_size,
+        }),
+        Algorithm::Shake256 => Box::new(XofHasher {
+            inner: Shake256::default(),
+            algorithm,
+            output_size,
+        }),
+    }
+}
+
+/// Read `reader` to the end in chunks (64 KiB), passing each chunk to `consume`.
+/// Returns the number of bytes read.
+fn read_chunks<R: Read>(mut reader: R, mut consume: impl FnMut(&[u8])) -> Result<u64> {
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut total_bytes = 0u64;
+
+    loop {
+        let bytes_read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e).context("Failed to read input"),
+        };
+
+        total_bytes += bytes_read as u64;
+        consume(&buffer[..bytes_read]);
     }
+
+    Ok(total_bytes)
+}
+
+/// Feed everything from `reader` into `hasher` in chunks (64 KiB) and finalize.
+/// Works with any stream: files, STDIN, sockets, decompressors, in-memory cursors.
+/// Returns (hash_bytes, bytes_read)
+pub fn hash_reader<R: Read>(mut hasher: Box<dyn Hasher>, reader: R) -> Result<(Vec<u8>, u64)> {
+    let total_bytes = read_chunks(reader, |chunk| hasher.update(chunk))?;
+    Ok((hasher.finalize(), total_bytes))
+}
+
+/// Feed `reader` into several hashers in a single pass: each chunk is handed
+/// to every hasher before the next one is read.
+/// Returns (hash_bytes per hasher in the given order, bytes_read)
+pub fn hash_reader_multi<R: Read>(
+    mut hashers: Vec<Box<dyn Hasher>>,
+    reader: R,
+) -> Result<(Vec<Vec<u8>>, u64)> {
+    let total_bytes = read_chunks(reader, |chunk| {
+        for hasher in hashers.iter_mut() {
+            hasher.update(chunk);
+        }
+    })?;
+    let digests = hashers.into_iter().map(|h| h.finalize()).collect();
+    Ok((digests, total_bytes))
+}
+
+/// Create one hasher per algorithm, all with the same optional `--length`
+pub(crate) fn hashers_for(
+    algorithms: &[Algorithm],
+    length: Option<usize>,
+) -> Result<Vec<Box<dyn Hasher>>> {
+    algorithms
+        .iter()
+        .map(|algorithm| algorithm.hasher_with_length(length))
+        .collect()
+}
+
+/// Hash data using the specified algorithm, returns raw bytes
+pub fn hash_data(algorithm: Algorithm, data: &[u8]) -> Vec<u8> {
+    let mut hasher = algorithm.hasher();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Hash data with an explicit output length in bytes (BLAKE3 and SHAKE only).
+/// `None` uses the algorithm's default digest size.
+pub fn hash_data_with_length(
+    algorithm: Algorithm,
+    data: &[u8],
+    length: Option<usize>,
+) -> Result<Vec<u8>> {
+    let mut hasher = algorithm.hasher_with_length(length)?;
+    hasher.update(data);
+    Ok(hasher.finalize())
 }
 
 /// Hash a file using the specified algorithm by reading it in chunks (64 KiB).
 /// This avoids loading the entire file into memory.
 /// Returns raw bytes of the hash.
 pub fn hash_file(algorithm: Algorithm, file_path: &str) -> Result<Vec<u8>> {
-    const CHUNK_SIZE: usize = 64 * 1024; // 64 KiB
+    hash_file_with_length(algorithm, file_path, None)
+}
 
-    let file =
-        File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
+/// Hash a file with an explicit output length in bytes (BLAKE3 and SHAKE only).
+/// `None` uses the algorithm's default digest size.
+pub fn hash_file_with_length(
+    algorithm: Algorithm,
+    file_path: &str,
+    length: Option<usize>,
+) -> Result<Vec<u8>> {
+    let (mut digests, _) = hash_file_io(&[algorithm], file_path, length, IoMode::Read)?;
+    Ok(digests.remove(0))
+}
 
-    let mut reader = BufReader::new(file);
-    let mut buffer = vec![0u8; CHUNK_SIZE];
+/// Hash a file like [`hash_file_with_length`], but memory-map it when it is
+/// large ([`IoMode::Auto`]). BLAKE3 then hashes files of at least
+/// `PARALLEL_THRESHOLD` bytes on all threads of the current rayon pool.
+/// A file truncated by another process while it is mapped raises SIGBUS, so
+/// only use this for files that do not change during hashing.
+pub fn hash_file_mapped(
+    algorithm: Algorithm,
+    file_path: &str,
+    length: Option<usize>,
+) -> Result<Vec<u8>> {
+    let (mut digests, _) = hash_file_io(&[algorithm], file_path, length, IoMode::Auto)?;
+    Ok(digests.remove(0))
+}
 
-    match algorithm {
-        Algorithm::Sha1 => {
-            let mut hasher = Sha1::new();
-            loop {
-                let bytes_read = reader
-                    .read(&mut buffer)
-                    .with_context(|| format!("Failed to read from file: {}", file_path))?;
-
-                if bytes_read == 0 {
-                    break;
-                }
-
-                hasher.update(&buffer[..bytes_read]);
-            }
-            Ok(hasher.finalize().to_vec())
-        }
-        Algorithm::Sha256 => {
-            let mut hasher = Sha256::new();
-            loop {
-                let bytes_read = reader
-                    .read(&mut buffer)
-                    .with_context(|| format!("Failed to read from file: {}", file_path))?;
-
-                if bytes_read == 0 {
-                    break;
-                }
-
-                hasher.update(&buffer[..bytes_read]);
-            }
-            Ok(hasher.finalize().to_vec())
-        }
-        Algorithm::Sha512 => {
-            let mut hasher = Sha512::new();
-            loop {
-                let bytes_read = reader
-                    .read(&mut buffer)
-                    .with_context(|| format!("Failed to read from file: {}", file_path))?;
-
-                if bytes_read == 0 {
-                    break;
-                }
-
-                hasher.update(&buffer[..bytes_read]);
-            }
-            Ok(hasher.finalize().to_vec())
-        }
-        Algorithm::Blake3 => {
-            let mut hasher = blake3::Hasher::new();
-            loop {
-                let bytes_read = reader
-                    .read(&mut buffer)
-                    .with_context(|| format!("Failed to read from file: {}", file_path))?;
-
-                if bytes_read == 0 {
-                    break;
-                }
-
-                hasher.update(&buffer[..bytes_read]);
-            }
-            Ok(hasher.finalize().as_bytes().to_vec())
-        }
-        Algorithm::Md5 => {
-            let mut hasher = Md5::new();
-            loop {
-                let bytes_read = reader
-                    .read(&mut buffer)
-                    .with_context(|| format!("Failed to read from file: {}", file_path))?;
-
-                if bytes_read == 0 {
-                    break;
-                }
-
-                hasher.update(&buffer[..bytes_read]);
-            }
-            Ok(hasher.finalize().to_vec())
-        }
-    }
+/// Hash `count` bytes of a file starting at byte `offset` (`None` hashes up
+/// to the end of the file). The range must lie within the file.
+/// Returns raw bytes of the hash.
+pub fn hash_file_range(
+    algorithm: Algorithm,
+    file_path: &str,
+    offset: u64,
+    count: Option<u64>,
+) -> Result<Vec<u8>> {
+    let (mut digests, _) = hash_file_range_multi(&[algorithm], file_path, offset, count, None)?;
+    Ok(digests.remove(0))
 }
 
 /// Hash STDIN using the specified algorithm by reading it in chunks (64 KiB).
 /// This avoids loading the entire input into memory.
 /// Returns (hash_bytes, input_size)
 pub fn hash_stdin(algorithm: Algorithm) -> Result<(Vec<u8>, usize)> {
-    const CHUNK_SIZE: usize = 64 * 1024; // 64 KiB
+    hash_stdin_with_length(algorithm, None)
+}
+
+/// Hash STDIN with an explicit output length in bytes (BLAKE3 and SHAKE only).
+/// `None` uses the algorithm's default digest size.
+pub fn hash_stdin_with_length(
+    algorithm: Algorithm,
+    length: Option<usize>,
+) -> Result<(Vec<u8>, usize)> {
+    let hasher = algorithm.hasher_with_length(length)?;
 
     let stdin_handle = stdin();
-    let mut reader = BufReader::new(stdin_handle.lock());
-    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let (hash, total_bytes) =
+        hash_reader(hasher, stdin_handle.lock()).context("Failed to read from STDIN")?;
+    Ok((hash, total_bytes as usize))
+}
 
-    match algorithm {
-        Algorithm::Sha1 => {
-            let mut hasher = Sha1::new();
-            let mut total_bytes = 0;
-            loop {
-                let bytes_read = reader
-                    .read(&mut buffer)
-                    .context("Failed to read from STDIN")?;
-
-                if bytes_read == 0 {
-                    break;
-                }
-
-                total_bytes += bytes_read;
-                hasher.update(&buffer[..bytes_read]);
-            }
-            Ok((hasher.finalize().to_vec(), total_bytes))
-        }
-        Algorithm::Sha256 => {
-            let mut hasher = Sha256::new();
-            let mut total_bytes = 0;
-            loop {
-                let bytes_read = reader
-                    .read(&mut buffer)
-                    .context("Failed to read from STDIN")?;
-
-                if bytes_read == 0 {
-                    break;
-                }
-
-                total_bytes += bytes_read;
-                hasher.update(&buffer[..bytes_read]);
-            }
-            Ok((hasher.finalize().to_vec(), total_bytes))
-        }
-        Algorithm::Sha512 => {
-            let mut hasher = Sha512::new();
-            let mut total_bytes = 0;
-            loop {
-                let bytes_read = reader
-                    .read(&mut buffer)
-                    .context("Failed to read from STDIN")?;
-
-                if bytes_read == 0 {
-                    break;
-                }
-
-                total_bytes += bytes_read;
-                hasher.update(&buffer[..bytes_read]);
-            }
-            Ok((hasher.finalize().to_vec(), total_bytes))
-        }
-        Algorithm::Blake3 => {
-            let mut hasher = blake3::Hasher::new();
-            let mut total_bytes = 0;
-            loop {
-                let bytes_read = reader
-                    .read(&mut buffer)
-                    .context("Failed to read from STDIN")?;
-
-                if bytes_read == 0 {
-                    break;
-                }
-
-                total_bytes += bytes_read;
-                hasher.update(&buffer[..bytes_read]);
-            }
-            Ok((hasher.finalize().as_bytes().to_vec(), total_bytes))
-        }
-        Algorithm::Md5 => {
-            let mut hasher = Md5::new();
-            let mut total_bytes = 0;
-            loop {
-                let bytes_read = reader
-                    .read(&mut buffer)
-                    .context("Failed to read from STDIN")?;
-
-                if bytes_read == 0 {
-                    break;
-                }
-
-                total_bytes += bytes_read;
-                hasher.update(&buffer[..bytes_read]);
-            }
-            Ok((hasher.finalize().to_vec(), total_bytes))
+/// Hash data with several algorithms at once.
+/// Returns one digest per algorithm, in the given order.
+pub fn hash_data_multi(
+    algorithms: &[Algorithm],
+    data: &[u8],
+    length: Option<usize>,
+) -> Result<Vec<Vec<u8>>> {
+    let mut hashers = hashers_for(algorithms, length)?;
+    for hasher in hashers.iter_mut() {
+        hasher.update(data);
+    }
+    Ok(hashers.into_iter().map(|h| h.finalize()).collect())
+}
+
+/// Hash a file with several algorithms, reading it only once.
+/// The file is read in chunks; use [`hash_file_io`] to memory-map it.
+/// Returns one digest per algorithm, in the given order.
+pub fn hash_file_multi(
+    algorithms: &[Algorithm],
+    file_path: &str,
+    length: Option<usize>,
+) -> Result<Vec<Vec<u8>>> {
+    let (digests, _) = hash_file_io(algorithms, file_path, length, IoMode::Read)?;
+    Ok(digests)
+}
+
+/// Hash a byte range of a file with several algorithms, reading it only once.
+/// The range starts at `offset` and covers `count` bytes, or runs to the end
+/// of the file when `count` is `None`; it must lie within the file.
+/// Returns (one digest per algorithm, bytes hashed)
+pub fn hash_file_range_multi(
+    algorithms: &[Algorithm],
+    file_path: &str,
+    offset: u64,
+    count: Option<u64>,
+    length: Option<usize>,
+) -> Result<(Vec<Vec<u8>>, u64)> {
+    let hashers = hashers_for(algorithms, length)?;
+
+    let mut file =
+        File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
+    let size = file
+        .metadata()
+        .with_context(|| format!("Failed to read metadata: {}", file_path))?
+        .len();
+
+    if offset > size {
+        return Err(anyhow!(
+            "Offset {} is past the end of {} ({} bytes)",
+            offset,
+            file_path,
+            size
+        ));
+    }
+    let count = match count {
+        Some(count) if offset.checked_add(count).is_none_or(|end| end > size) => {
+            return Err(anyhow!(
+                "Range of {} bytes at offset {} is past the end of {} ({} bytes)",
+                count,
+                offset,
+                file_path,
+                size
+            ));
         }
+        Some(count) => count,
+        None => size - offset,
+    };
+
+    file.seek(SeekFrom::Start(offset))
+        .with_context(|| format!("Failed to seek in file: {}", file_path))?;
+    let (digests, bytes_read) = hash_reader_multi(hashers, BufReader::new(file).take(count))
+        .with_context(|| format!("Failed to read from file: {}", file_path))?;
+
+    // The file may have been truncated since its size was checked
+    if bytes_read != count {
+        return Err(anyhow!(
+            "{} ended after {} of {} bytes at offset {}",
+            file_path,
+            bytes_read,
+            count,
+            offset
+        ));
     }
+
+    Ok((digests, bytes_read))
+}
+
+/// Hash STDIN with several algorithms in a single pass.
+/// Returns (one digest per algorithm, input_size)
+pub fn hash_stdin_multi(
+    algorithms: &[Algorithm],
+    length: Option<usize>,
+) -> Result<(Vec<Vec<u8>>, usize)> {
+    let hashers = hashers_for(algorithms, length)?;
+
+    let stdin_handle = stdin();
+    let (digests, total_bytes) =
+        hash_reader_multi(hashers, stdin_handle.lock()).context("Failed to read from STDIN")?;
+    Ok((digests, total_bytes as usize))
 }
@@ -0,0 +1,320 @@
+use crate::algorithm::Algorithm;
+use crate::hash::{hash_reader_multi, hashers_for, Hasher};
+use anyhow::{anyhow, Context, Result};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::BufReader;
+use std::str::FromStr;
+
+/// Files at least this large are memory-mapped by `IoMode::Auto`; below it
+/// setting up the mapping costs more than the reads it saves
+pub const MMAP_THRESHOLD: u64 = 1024 * 1024; // 1 MiB
+
+/// Bytes of a memory map handed to every hasher before moving on, so that
+/// with several algorithms the data is still in the CPU cache
+const MMAP_CHUNK: usize = 1024 * 1024; // 1 MiB
+
+/// Memory-mapped files at least this large are hashed with several threads
+/// by algorithms that support it (BLAKE3)
+pub const PARALLEL_THRESHOLD: u64 = 16 * 1024 * 1024; // 16 MiB
+
+/// Bytes of a memory map handed to every hasher at a time when hashing in
+/// parallel, enough to keep all threads busy between joins
+const PARALLEL_CHUNK: usize = 64 * 1024 * 1024; // 64 MiB
+
+/// How file contents are read for hashing.
+///
+/// The default is `Read`: a memory-mapped file that another process truncates
+/// raises SIGBUS, so mapping is left for callers to opt into, as the CLI does
+/// with `Auto`. `Uring` is never picked automatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IoMode {
+    /// `Mmap` for regular files of at least `MMAP_THRESHOLD` bytes, `Read`
+    /// for everything else
+    Auto,
+    /// Buffered `read` calls in 64 KiB chunks
+    #[default]
+    Read,
+    /// Map the whole file into memory
+    Mmap,
+    /// io_uring with several reads in flight ahead of the hasher (Linux only)
+    Uring,
+}
+
+impl FromStr for IoMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(IoMode::Auto),
+            "read" => Ok(IoMode::Read),
+            "mmap" => Ok(IoMode::Mmap),
+            "uring" | "io_uring" | "io-uring" => Ok(IoMode::Uring),
+            _ => Err(anyhow!(
+                "Unsupported I/O mode: {}. Supported: auto, mmap, uring, read",
+                s
+            )),
+        }
+    }
+}
+
+/// Hash a file with several algorithms, reading it only once with the given
+/// I/O mode. `Mmap` and `Uring` need a regular file.
+/// Returns (one digest per algorithm, bytes hashed)
+pub fn hash_file_io(
+    algorithms: &[Algorithm],
+    file_path: &str,
+    length: Option<usize>,
+    mode: IoMode,
+) -> Result<(Vec<Vec<u8>>, u64)> {
+    hash_file_with_hashers(hashers_for(algorithms, length)?, file_path, mode)
+}
+
+/// Feed a file into the given hashers with the given I/O mode, like
+/// [`hash_file_io`]. Memory-mapped files of at least `PARALLEL_THRESHOLD`
+/// bytes are passed to `Hasher::update_parallel`.
+/// Returns (hash_bytes per hasher in the given order, bytes hashed)
+pub fn hash_file_with_hashers(
+    hashers: Vec<Box<dyn Hasher>>,
+    file_path: &str,
+    mode: IoMode,
+) -> Result<(Vec<Vec<u8>>, u64)> {
+    let file =
+        File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
+
+    let result = match mode {
+        IoMode::Read => hash_reader_multi(hashers, BufReader::new(file)),
+        IoMode::Mmap => regular_file_size(&file, "mmap")
+            .and_then(|_| map_file(&file))
+            .map(|map| hash_mapped(hashers, &map)),
+        IoMode::Uring => {
+            regular_file_size(&file, "uring").and_then(|size| hash_uring(hashers, &file, size))
+        }
+        IoMode::Auto => {
+            let large = file
+                .metadata()
+                .is_ok_and(|m| m.is_file() && m.len() >= MMAP_THRESHOLD);
+            // Fall back to reads when the file cannot be mapped, e.g. on
+            // filesystems without mmap support
+            match large.then(|| map_file(&file).ok()).flatten() {
+                Some(map) => Ok(hash_mapped(hashers, &map)),
+                None => hash_reader_multi(hashers, BufReader::new(file)),
+            }
+        }
+    };
+    result.with_context(|| format!("Failed to read from file: {}", file_path))
+}
+
+/// Size of `file`, or an error naming `mode` if it is not a regular file
+fn regular_file_size(file: &File, mode: &str) -> Result<u64> {
+    let metadata = file.metadata()?;
+    if !metadata.is_file() {
+        return Err(anyhow!("--io {} needs a regular file", mode));
+    }
+    Ok(metadata.len())
+}
+
+fn map_file(file: &File) -> Result<Mmap> {
+    // SAFETY: the mapping is only read. If another process truncates the
+    // file while it is mapped, touching the lost pages raises SIGBUS, as
+    // with any mmap-based reader; `--io read` avoids this.
+    let map = unsafe { Mmap::map(file) }.context("Failed to memory-map file")?;
+    #[cfg(unix)]
+    let _ = map.advise(memmap2::Advice::Sequential);
+    Ok(map)
+}
+
+/// Hash a memory-mapped file.
+///
+/// BLAKE3 splits large files across the rayon pool of the calling thread.
+/// In batch mode and `verify` that is the pool already hashing the other
+/// files, so a huge file borrows idle workers instead of starting more
+/// threads than there are cores, and `verify --jobs 1` stays on one thread.
+fn hash_mapped(mut hashers: Vec<Box<dyn Hasher>>, data: &[u8]) -> (Vec<Vec<u8>>, u64) {
+    let parallel = data.len() as u64 >= PARALLEL_THRESHOLD;
+    let chunk_size = if parallel { PARALLEL_CHUNK } else { MMAP_CHUNK };
+
+    for chunk in data.chunks(chunk_size) {
+        for hasher in hashers.iter_mut() {
+            if parallel {
+                hasher.update_parallel(chunk);
+            } else {
+                hasher.update(chunk);
+            }
+        }
+    }
+    let digests = hashers.into_iter().map(|h| h.finalize()).collect();
+    (digests, data.len() as u64)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn hash_uring(_: Vec<Box<dyn Hasher>>, _: &File, _: u64) -> Result<(Vec<Vec<u8>>, u64)> {
+    Err(anyhow!("--io uring is only supported on Linux"))
+}
+
+#[cfg(target_os = "linux")]
+fn hash_uring(
+    mut hashers: Vec<Box<dyn Hasher>>,
+    file: &File,
+    size: u64,
+) -> Result<(Vec<Vec<u8>>, u64)> {
+    let mut reader = uring::UringReader::new(file, size)?;
+    while let Some(block) = reader.next_block()? {
+        for hasher in hashers.iter_mut() {
+            hasher.update(block);
+        }
+    }
+    let digests = hashers.into_iter().map(|h| h.finalize()).collect();
+    Ok((digests, size))
+}
+
+#[cfg(target_os = "linux")]
+mod uring {
+    use anyhow::{anyhow, Context, Result};
+    use io_uring::{opcode, types, IoUring};
+    use std::fs::File;
+    use std::io::{Error, ErrorKind};
+    use std::os::unix::io::AsRawFd;
+
+    /// Reads kept in flight ahead of the block being hashed
+    const DEPTH: usize = 8;
+    /// Size of each read
+    const BLOCK_SIZE: usize = 256 * 1024; // 256 KiB
+
+    /// One block of the file and how much of it has arrived
+    #[derive(Clone, Copy, Default)]
+    struct Block {
+        offset: u64,
+        len: usize,
+        filled: usize,
+    }
+
+    /// Reads a file in order, block by block, while the next `DEPTH - 1`
+    /// blocks are already being read by the kernel
+    pub(super) struct UringReader {
+        ring: IoUring,
+        fd: types::Fd,
+        size: u64,
+        buffers: Vec<Vec<u8>>,
+        blocks: [Block; DEPTH],
+        /// Index of the next block to submit
+        submitted: u64,
+        /// Index of the next block to hand out
+        consumed: u64,
+        in_flight: usize,
+    }
+
+    impl UringReader {
+        pub(super) fn new(file: &File, size: u64) -> Result<Self> {
+            let ring = IoUring::new(DEPTH as u32).context("io_uring is not available")?;
+            Ok(UringReader {
+                ring,
+                fd: types::Fd(file.as_raw_fd()),
+                size,
+                buffers: vec![vec![0u8; BLOCK_SIZE]; DEPTH],
+                blocks: [Block::default(); DEPTH],
+                submitted: 0,
+                consumed: 0,
+                in_flight: 0,
+            })
+        }
+
+        /// The next block of the file, or `None` at the end
+        pub(super) fn next_block(&mut self) -> Result<Option<&[u8]>> {
+            let total = self.size.div_ceil(BLOCK_SIZE as u64);
+            if self.consumed == total {
+                return Ok(None);
+            }
+
+            // The slot handed out last time is free again: refill the queue
+            while self.submitted < total && self.submitted < self.consumed + DEPTH as u64 {
+                let slot = (self.submitted % DEPTH as u64) as usize;
+                let offset = self.submitted * BLOCK_SIZE as u64;
+                self.blocks[slot] = Block {
+                    offset,
+                    len: (self.size - offset).min(BLOCK_SIZE as u64) as usize,
+                    filled: 0,
+                };
+                self.push_read(slot)?;
+                self.submitted += 1;
+            }
+
+            let slot = (self.consumed % DEPTH as u64) as usize;
+            while self.blocks[slot].filled < self.blocks[slot].len {
+                // A signal can interrupt the wait; the reads are still queued
+                match self.ring.submit_and_wait(1) {
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    result => result?,
+                };
+                let completions: Vec<(u64, i32)> = self
+                    .ring
+                    .completion()
+                    .map(|entry| (entry.user_data(), entry.result()))
+                    .collect();
+                self.in_flight -= completions.len();
+                for (done, result) in completions {
+                    self.complete(done as usize, result)?;
+                }
+            }
+
+            self.consumed += 1;
+            Ok(Some(&self.buffers[slot][..self.blocks[slot].len]))
+        }
+
+        /// Account for a finished read, reissuing it if it came up short
+        fn complete(&mut self, slot: usize, result: i32) -> Result<()> {
+            if result < 0 {
+                let error = Error::from_raw_os_error(-result);
+                if error.kind() == ErrorKind::Interrupted {
+                    return self.push_read(slot);
+                }
+                return Err(error).context("Failed to read input");
+            }
+            if result == 0 {
+                return Err(anyhow!("File was truncated while it was read"));
+            }
+
+            let block = &mut self.blocks[slot];
+            block.filled += result as usize;
+            if block.filled < block.len {
+                self.push_read(slot)?;
+            }
+            Ok(())
+        }
+
+        /// Queue a read of the rest of the block in `slot`
+        fn push_read(&mut self, slot: usize) -> Result<()> {
+            let block = self.blocks[slot];
+            let buffer = &mut self.buffers[slot][block.filled..block.len];
+            let read = opcode::Read::new(self.fd, buffer.as_mut_ptr(), buffer.len() as u32)
+                .offset(block.offset + block.filled as u64)
+                .build()
+                .user_data(slot as u64);
+
+            // SAFETY: the buffer is neither moved nor freed until the read
+            // completes: slots are only refilled once handed out, and `drop`
+            // waits for reads still in flight.
+            unsafe { self.ring.submission().push(&read) }
+                .map_err(|_| anyhow!("io_uring submission queue is full"))?;
+            self.in_flight += 1;
+            Ok(())
+        }
+    }
+
+    impl Drop for UringReader {
+        fn drop(&mut self) {
+            // After an error reads may still be writing into the buffers
+            while self.in_flight > 0 {
+                match self.ring.submit_and_wait(1) {
+                    Ok(_) => self.in_flight -= self.ring.completion().count(),
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(_) => {
+                        // Never free memory the kernel may still write to
+                        std::mem::forget(std::mem::take(&mut self.buffers));
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,22 @@
 pub mod algorithm;
+pub mod archive;
+pub mod baseline;
+pub mod bench;
+pub mod cache;
+pub mod chunks;
 pub mod cli;
 pub mod command;
+pub mod decompress;
+pub mod detect;
+pub mod dupes;
 pub mod hash;
+pub mod io;
+pub mod mac;
+pub mod manifest;
+pub mod merkle;
 pub mod output;
+pub mod pieces;
+pub mod tree;
 pub mod verbosity;
+pub mod walk;
+pub mod xattrs;
@@ -0,0 +1,191 @@
+use crate::algorithm::Algorithm;
+use crate::hash::{hash_reader, Hasher};
+use anyhow::{anyhow, Context, Result};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use std::fs::File;
+use std::io::{stdin, BufReader};
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Where to read the MAC key from
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    /// Raw key bytes read from a file
+    File(String),
+    /// Key taken from the named environment variable
+    Env(String),
+    /// Hex-encoded key passed on the command line (requires explicit opt-in)
+    Hex(Zeroizing<String>),
+}
+
+/// Load key bytes from the given source.
+/// The returned buffer is wiped from memory when dropped.
+pub fn load_key(source: &KeySource) -> Result<Zeroizing<Vec<u8>>> {
+    let key = match source {
+        KeySource::File(path) => Zeroizing::new(
+            std::fs::read(path).with_context(|| format!("Failed to read key file: {}", path))?,
+        ),
+        KeySource::Env(var) => {
+            let value = Zeroizing::new(
+                std::env::var(var)
+                    .with_context(|| format!("Failed to read key from environment: {}", var))?,
+            );
+            Zeroizing::new(value.as_bytes().to_vec())
+        }
+        KeySource::Hex(hex_str) => Zeroizing::new(
+            hex::decode(hex_str.trim())
+                .context("Invalid hex key: expected an even number of hex digits")?,
+        ),
+    };
+
+    if key.is_empty() {
+        return Err(anyhow!("Key is empty"));
+    }
+
+    Ok(key)
+}
+
+/// Get display name for a keyed-hash algorithm (e.g. `hmac-sha256`)
+pub fn mac_name(algorithm: Algorithm) -> String {
+    match algorithm {
+        Algorithm::Blake3 => "blake3-keyed".to_string(),
+        other => format!("hmac-{}", other.name()),
+    }
+}
+
+/// Streaming keyed hasher: HMAC for SHA-1/SHA-256/SHA-512, or BLAKE3 keyed mode.
+///
+/// The state is derived from the key. BLAKE3 state is wiped from memory when
+/// dropped. The HMAC states are not: `hmac` 0.12 has no `zeroize` support and
+/// their layout is private, so they cannot be wiped soundly. The key itself is
+/// only kept in `Zeroizing` buffers.
+pub enum KeyedHasher {
+    HmacSha1(Hmac<Sha1>),
+    HmacSha256(Hmac<Sha256>),
+    HmacSha512(Hmac<Sha512>),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl KeyedHasher {
+    /// Create a keyed hasher. BLAKE3 keyed mode requires a key of exactly 32 bytes.
+    pub fn new(algorithm: Algorithm, key: &[u8]) -> Result<Self> {
+        match algorithm {
+            Algorithm::Sha1 => Ok(KeyedHasher::HmacSha1(
+                Hmac::new_from_slice(key).map_err(|e| anyhow!("Invalid HMAC key: {}", e))?,
+            )),
+            Algorithm::Sha256 => Ok(KeyedHasher::HmacSha256(
+                Hmac::new_from_slice(key).map_err(|e| anyhow!("Invalid HMAC key: {}", e))?,
+            )),
+            Algorithm::Sha512 => Ok(KeyedHasher::HmacSha512(
+                Hmac::new_from_slice(key).map_err(|e| anyhow!("Invalid HMAC key: {}", e))?,
+            )),
+            Algorithm::Blake3 => {
+                let key: Zeroizing<[u8; blake3::KEY_LEN]> =
+                    Zeroizing::new(key.try_into().map_err(|_| {
+                        anyhow!(
+                            "BLAKE3 keyed mode requires a {}-byte key (got {} bytes)",
+                            blake3::KEY_LEN,
+                            key.len()
+                        )
+                    })?);
+                Ok(KeyedHasher::Blake3(Box::new(blake3::Hasher::new_keyed(
+                    &key,
+                ))))
+            }
+            other => Err(anyhow!(
+                "Keyed hashing is not supported for '{}'. Supported algorithms: sha256, sha512, sha1, blake3",
+                other.name()
+            )),
+        }
+    }
+
+    /// Feed data into the MAC
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            KeyedHasher::HmacSha1(mac) => mac.update(data),
+            KeyedHasher::HmacSha256(mac) => mac.update(data),
+            KeyedHasher::HmacSha512(mac) => mac.update(data),
+            KeyedHasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    /// Finish and return the authentication tag
+    pub fn finalize(mut self) -> Vec<u8> {
+        // Finalize in place: the `Drop` impl keeps the state from being moved out
+        match &mut self {
+            KeyedHasher::HmacSha1(mac) => mac.finalize_reset().into_bytes().to_vec(),
+            KeyedHasher::HmacSha256(mac) => mac.finalize_reset().into_bytes().to_vec(),
+            KeyedHasher::HmacSha512(mac) => mac.finalize_reset().into_bytes().to_vec(),
+            KeyedHasher::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+        }
+    }
+}
+
+impl Drop for KeyedHasher {
+    fn drop(&mut self) {
+        if let KeyedHasher::Blake3(hasher) = self {
+            hasher.zeroize();
+        }
+    }
+}
+
+impl Hasher for KeyedHasher {
+    fn update(&mut self, data: &[u8]) {
+        KeyedHasher::update(self, data);
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        KeyedHasher::finalize(*self)
+    }
+
+    fn output_size(&self) -> usize {
+        self.algorithm().output_size()
+    }
+
+    /// Underlying hash function (the MAC name is given by `mac_name`)
+    fn algorithm(&self) -> Algorithm {
+        match self {
+            KeyedHasher::HmacSha1(_) => Algorithm::Sha1,
+            KeyedHasher::HmacSha256(_) => Algorithm::Sha256,
+            KeyedHasher::HmacSha512(_) => Algorithm::Sha512,
+            KeyedHasher::Blake3(_) => Algorithm::Blake3,
+        }
+    }
+}
+
+/// Compute a MAC over in-memory data
+pub fn mac_data(algorithm: Algorithm, key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut hasher = KeyedHasher::new(algorithm, key)?;
+    hasher.update(data);
+    Ok(hasher.finalize())
+}
+
+/// Compute a MAC over a file by reading it in chunks (64 KiB)
+pub fn mac_file(algorithm: Algorithm, key: &[u8], file_path: &str) -> Result<Vec<u8>> {
+    let hasher = Box::new(KeyedHasher::new(algorithm, key)?);
+    let file =
+        File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
+
+    let (tag, _) = hash_reader(hasher, BufReader::new(file))
+        .with_context(|| format!("Failed to read from file: {}", file_path))?;
+    Ok(tag)
+}
+
+/// Compute a MAC over STDIN by reading it in chunks (64 KiB).
+/// Returns (tag_bytes, input_size)
+pub fn mac_stdin(algorithm: Algorithm, key: &[u8]) -> Result<(Vec<u8>, usize)> {
+    let hasher = Box::new(KeyedHasher::new(algorithm, key)?);
+    let stdin_handle = stdin();
+    let (tag, total_bytes) =
+        hash_reader(hasher, stdin_handle.lock()).context("Failed to read from STDIN")?;
+    Ok((tag, total_bytes as usize))
+}
+
+/// Compare two tags in constant time
+pub fn tags_equal(expected: &[u8], actual: &[u8]) -> bool {
+    expected.ct_eq(actual).into()
+}
@@ -1,7 +1,11 @@
 use anyhow::Result;
 use clap::Parser;
 use hashy::cli::Cli;
-use hashy::command::{handle_hash, handle_verify};
+use hashy::command::{
+    handle_audit, handle_baseline, handle_bench, handle_cache, handle_chunks, handle_dupes,
+    handle_hash, handle_hmac, handle_merkle, handle_verify, handle_verify_pieces,
+    handle_verify_xattr, StatusFailure,
+};
 use hashy::verbosity::Verbosity;
 
 fn main() -> Result<()> {
@@ -16,41 +20,53 @@ fn main() -> Result<()> {
         Verbosity::Normal
     };
 
-    if let Some((
-        algo,
-        allow_insecure,
-        text,
-        file,
-        files,
-        continue_on_error,
-        format,
-        uppercase,
-        json,
-    )) = cli.command.get_hash_params()
+    match run(cli, verbosity) {
+        // `verify --status` reports failure through the exit code alone
+        Err(e) if e.is::<StatusFailure>() => std::process::exit(1),
+        result => result,
+    }
+}
+
+fn run(mut cli: Cli, verbosity: Verbosity) -> Result<()> {
+    if let Some(args) = cli.command.get_hash_params() {
+        handle_hash(args, verbosity)?;
+    } else if let Some((paths, algo, allow_insecure, continue_on_error, status)) =
+        cli.command.get_verify_xattr_params()
     {
-        handle_hash(
+        handle_verify_xattr(
+            paths,
             algo,
             allow_insecure,
-            text,
-            file,
-            files,
             continue_on_error,
-            format,
-            uppercase,
-            json,
+            status,
             verbosity,
         )?;
-    } else if let Some((algo, allow_insecure, checksums_file, continue_on_error, format)) =
-        cli.command.get_verify_params()
+    } else if let Some((list, file, allow_insecure, status)) =
+        cli.command.get_verify_pieces_params()
     {
-        handle_verify(
-            algo,
-            allow_insecure,
-            checksums_file,
-            continue_on_error,
-            format,
-            verbosity,
-        )?;
+        handle_verify_pieces(list, file, allow_insecure, status, verbosity)?;
+    } else if let Some(args) = cli.command.get_verify_params() {
+        handle_verify(args, verbosity)?;
+    } else if let Some(args) = cli.command.get_audit_params() {
+        handle_audit(args, verbosity)?;
+    } else if let Some((mode, db, dirs, algo, allow_insecure, walk, json)) =
+        cli.command.get_baseline_params()
+    {
+        handle_baseline(mode, db, dirs, algo, allow_insecure, walk, json, verbosity)?;
+    } else if let Some((mode, cache)) = cli.command.get_cache_params() {
+        handle_cache(mode, cache, verbosity)?;
+    } else if let Some(args) = cli.command.get_dupes_params() {
+        handle_dupes(args, verbosity)?;
+    } else if let Some((paths, algo, allow_insecure, sizes, json, walk)) =
+        cli.command.get_chunks_params()
+    {
+        handle_chunks(paths, algo, allow_insecure, sizes, json, walk, verbosity)?;
+    } else if let Some(args) = cli.command.get_merkle_params() {
+        handle_merkle(args, verbosity)?;
+    } else if let Some(args) = cli.command.get_hmac_params() {
+        handle_hmac(args, verbosity)?;
+    } else if let Some(args) = cli.command.get_bench_params() {
+        handle_bench(args, verbosity)?;
     }
 
     Ok(())
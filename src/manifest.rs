@@ -0,0 +1,248 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Format a single sha256sum-compatible manifest line: `<DIGEST>  <PATH>`
+pub fn format_line(digest_hex: &str, path: &str) -> String {
+    match escape_path(path) {
+        Some(escaped) => format!("\\{}  {}", digest_hex, escaped),
+        None => format!("{}  {}", digest_hex, path),
+    }
+}
+
+/// Format a BSD-style tagged line: `<TAG> (<PATH>) = <DIGEST>`
+pub fn format_tagged_line(tag: &str, path: &str, digest: &str) -> String {
+    match escape_path(path) {
+        Some(escaped) => format!("\\{} ({}) = {}", tag, escaped, digest),
+        None => format!("{} ({}) = {}", tag, path, digest),
+    }
+}
+
+/// Escape a path the way sha256sum does when it contains a backslash, a
+/// newline or a carriage return: those become `\\`, `\n` and `\r`, and the
+/// line is then prefixed with a backslash. Returns `None` if no escaping is
+/// needed.
+pub fn escape_path(path: &str) -> Option<String> {
+    if !path.contains(['\\', '\n', '\r']) {
+        return None;
+    }
+    let mut escaped = String::with_capacity(path.len() + 2);
+    for c in path.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    Some(escaped)
+}
+
+/// Undo `escape_path`. Returns `None` for an unknown escape sequence or a
+/// trailing backslash, which make the line improperly formatted.
+pub fn unescape_path(path: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => unescaped.push('\\'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(unescaped)
+}
+
+/// One checksum line of a manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// Algorithm tag of a BSD-style line; `None` for `<DIGEST>  <PATH>` lines
+    pub tag: Option<String>,
+    pub digest: String,
+    pub path: String,
+}
+
+impl ManifestEntry {
+    /// Format the entry as a manifest line, tagged if it has a tag
+    pub fn to_line(&self) -> String {
+        match &self.tag {
+            Some(tag) => format_tagged_line(tag, &self.path, &self.digest),
+            None => format_line(&self.digest, &self.path),
+        }
+    }
+}
+
+/// Parse a BSD-style tagged line: `TAG (PATH) = DIGEST`, or `TAG(PATH)= DIGEST`
+/// as written by `openssl dgst`, with an escaped path if the line starts with
+/// a backslash. Returns `None` for any other line.
+pub fn parse_tagged_line(line: &str) -> Option<ManifestEntry> {
+    if let Some(line) = line.strip_prefix('\\') {
+        let entry = parse_tagged_line(line)?;
+        return Some(ManifestEntry {
+            path: unescape_path(&entry.path)?,
+            ..entry
+        });
+    }
+
+    let (head, digest) = match line.rsplit_once(" = ") {
+        Some((head, digest)) => (head.strip_suffix(')')?, digest.trim()),
+        None => {
+            let (head, digest) = line.rsplit_once(")= ")?;
+            (head, digest.trim())
+        }
+    };
+
+    let open = head.find('(')?;
+    let tag = head[..open].trim_end();
+    let path = &head[open + 1..];
+
+    if tag.is_empty() || tag.contains(char::is_whitespace) || path.is_empty() || digest.is_empty() {
+        return None;
+    }
+
+    Some(ManifestEntry {
+        tag: Some(tag.to_string()),
+        digest: digest.to_string(),
+        path: path.to_string(),
+    })
+}
+
+/// Split an untagged line into its two fields, separated by two spaces
+/// (preferred) or a single space. Which field is the digest is left to the
+/// caller, since both `<DIGEST>  <PATH>` and `<PATH>  <DIGEST>` are accepted.
+pub fn split_line(line: &str) -> (&str, Option<&str>) {
+    if let Some(pos) = line.find("  ") {
+        let (l, r) = line.split_at(pos);
+        (l.trim(), Some(r.trim_start_matches(' ').trim()))
+    } else if let Some(pos) = line.find(' ') {
+        let (l, r) = line.split_at(pos);
+        (l.trim(), Some(r.trim_start()))
+    } else {
+        (line, None)
+    }
+}
+
+/// Manifest path for one algorithm when several are written at once.
+///
+/// `{algo}` in `manifest` is replaced by the algorithm name; otherwise
+/// `.<algo>` is appended (e.g. `SUMS` becomes `SUMS.sha512`).
+pub fn per_algorithm_path(manifest: &str, algo_name: &str) -> PathBuf {
+    if manifest.contains("{algo}") {
+        PathBuf::from(manifest.replace("{algo}", algo_name))
+    } else {
+        PathBuf::from(format!("{}.{}", manifest, algo_name))
+    }
+}
+
+/// Make a path absolute without resolving symlinks
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+        Ok(cwd.join(path))
+    }
+}
+
+/// Make a path absolute and lexically normalize it: `.` components are
+/// dropped and `..` removes the preceding component. Symlinks are not resolved.
+pub fn normalize_path(path: &Path) -> Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in absolute_path(path)?.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Compute the path to record in a manifest for `file`.
+///
+/// `handle_verify` resolves entries relative to the manifest's directory, so
+/// files are recorded relative to it, with `..` components for files outside
+/// it (e.g. `../rel/a.txt`). Only a file on another drive or share, which has
+/// no relative path, is recorded as an absolute path.
+pub fn relative_entry_path(file: &str, manifest_path: &Path) -> Result<String> {
+    let manifest_dir = manifest_path.parent().unwrap_or(Path::new("."));
+    let manifest_dir = normalize_path(manifest_dir)?;
+    let file_abs = normalize_path(Path::new(file))?;
+
+    let entry = relative_path(&file_abs, &manifest_dir).unwrap_or(file_abs);
+    Ok(entry.to_string_lossy().into_owned())
+}
+
+/// Path of `path` relative to the directory `base`, both normalized and
+/// absolute. Returns `None` if they do not share a root.
+fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    let mut path_components = path.components().peekable();
+    let mut base_components = base.components().peekable();
+
+    // Roots and prefixes must match for a relative path to exist
+    match (path_components.peek(), base_components.peek()) {
+        (Some(a), Some(b)) if a == b => {}
+        _ => return None,
+    }
+
+    // Skip the common ancestors, then climb out of the rest of `base`
+    while let (Some(a), Some(b)) = (path_components.peek(), base_components.peek()) {
+        if a != b {
+            break;
+        }
+        path_components.next();
+        base_components.next();
+    }
+
+    let mut relative: PathBuf = base_components.map(|_| Component::ParentDir).collect();
+    relative.extend(path_components);
+    Some(relative)
+}
+
+/// Check whether `file` refers to the manifest being written
+pub fn is_manifest_path(file: &str, manifest_path: &Path) -> bool {
+    match (absolute_path(Path::new(file)), absolute_path(manifest_path)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Write a manifest of entries, sha256sum-compatible for untagged entries.
+/// Paths are rewritten relative to the manifest location.
+pub fn write_manifest(manifest_path: &Path, entries: &[ManifestEntry]) -> Result<()> {
+    let entries = entries
+        .iter()
+        .map(|entry| {
+            Ok(ManifestEntry {
+                path: relative_entry_path(&entry.path, manifest_path)?,
+                ..entry.clone()
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    write_manifest_entries(manifest_path, &entries)
+}
+
+/// Write a manifest of entries with their paths as given, e.g. archive
+/// members, which are already relative to the archive root
+pub fn write_manifest_entries(manifest_path: &Path, entries: &[ManifestEntry]) -> Result<()> {
+    let file = File::create(manifest_path)
+        .with_context(|| format!("Failed to create manifest: {}", manifest_path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    for entry in entries {
+        writeln!(writer, "{}", entry.to_line())
+            .with_context(|| format!("Failed to write manifest: {}", manifest_path.display()))?;
+    }
+
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write manifest: {}", manifest_path.display()))
+}
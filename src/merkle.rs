@@ -0,0 +1,313 @@
+use crate::algorithm::Algorithm;
+use crate::hash::hash_data;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Tree and proof format version written by this build
+const FORMAT_VERSION: u32 = 1;
+
+/// Domain separation prefixes (RFC 6962), so a leaf can never be passed off
+/// as an interior node or vice versa
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Which `merkle` subcommand to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MerkleMode {
+    /// Hash files and write the tree
+    Build,
+    /// Produce an inclusion proof for one file
+    Prove,
+    /// Check a file against an inclusion proof and a root
+    Verify,
+}
+
+/// Hash of the leaf for `path` with file digest `digest`:
+/// `H(0x00 || len(path) as u64 BE || path || digest)`
+pub fn leaf_hash(algorithm: Algorithm, path: &str, digest: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(1 + 8 + path.len() + digest.len());
+    data.push(LEAF_PREFIX);
+    data.extend_from_slice(&(path.len() as u64).to_be_bytes());
+    data.extend_from_slice(path.as_bytes());
+    data.extend_from_slice(digest);
+    hash_data(algorithm, &data)
+}
+
+/// Hash of an interior node: `H(0x01 || left || right)`
+fn node_hash(algorithm: Algorithm, left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(1 + left.len() + right.len());
+    data.push(NODE_PREFIX);
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+    hash_data(algorithm, &data)
+}
+
+/// Largest power of two smaller than `n` (for `n > 1`)
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+/// Root over leaf hashes, split like RFC 6962: the left subtree holds the
+/// largest power of two leaves, so no leaf is ever duplicated
+pub fn root_hash(algorithm: Algorithm, leaves: &[Vec<u8>]) -> Vec<u8> {
+    match leaves.len() {
+        0 => hash_data(algorithm, &[]),
+        1 => leaves[0].clone(),
+        n => {
+            let k = split_point(n);
+            node_hash(
+                algorithm,
+                &root_hash(algorithm, &leaves[..k]),
+                &root_hash(algorithm, &leaves[k..]),
+            )
+        }
+    }
+}
+
+/// Sibling hashes from leaf `index` up to the root (RFC 6962 audit path)
+pub fn inclusion_path(algorithm: Algorithm, leaves: &[Vec<u8>], index: usize) -> Vec<Vec<u8>> {
+    let n = leaves.len();
+    if n <= 1 {
+        return Vec::new();
+    }
+
+    let k = split_point(n);
+    if index < k {
+        let mut path = inclusion_path(algorithm, &leaves[..k], index);
+        path.push(root_hash(algorithm, &leaves[k..]));
+        path
+    } else {
+        let mut path = inclusion_path(algorithm, &leaves[k..], index - k);
+        path.push(root_hash(algorithm, &leaves[..k]));
+        path
+    }
+}
+
+/// Check that `leaf` is leaf `index` of a tree of `size` leaves with the
+/// given root, following RFC 9162 section 2.1.3.2
+pub fn verify_inclusion(
+    algorithm: Algorithm,
+    leaf: &[u8],
+    index: u64,
+    size: u64,
+    path: &[Vec<u8>],
+    root: &[u8],
+) -> bool {
+    if index >= size {
+        return false;
+    }
+
+    let mut fn_ = index;
+    let mut sn = size - 1;
+    let mut r = leaf.to_vec();
+
+    for p in path {
+        if sn == 0 {
+            return false;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            r = node_hash(algorithm, p, &r);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            r = node_hash(algorithm, &r, p);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    sn == 0 && r == root
+}
+
+/// One file of a tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleLeaf {
+    /// Path relative to the tree file's directory
+    pub path: String,
+    /// Hex-encoded file digest
+    pub digest: String,
+}
+
+/// A Merkle tree file written by `hashy merkle build`: every leaf, sorted by
+/// path, so proofs can be produced later
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MerkleTree {
+    pub version: u32,
+    pub algorithm: String,
+    /// Hex-encoded root
+    pub root: String,
+    pub leaves: Vec<MerkleLeaf>,
+}
+
+impl MerkleTree {
+    /// Build a tree from leaves, which are sorted by path. Identical leaves
+    /// (a file reached through overlapping arguments) are merged.
+    pub fn new(algorithm: Algorithm, mut leaves: Vec<MerkleLeaf>) -> Result<Self> {
+        leaves.sort_by(|a, b| a.path.cmp(&b.path));
+        leaves.dedup();
+        if let Some(pair) = leaves.windows(2).find(|pair| pair[0].path == pair[1].path) {
+            return Err(anyhow!("Duplicate Merkle leaf: {}", pair[0].path));
+        }
+
+        let mut tree = MerkleTree {
+            version: FORMAT_VERSION,
+            algorithm: algorithm.name().to_string(),
+            root: String::new(),
+            leaves,
+        };
+        tree.root = hex::encode(root_hash(algorithm, &tree.leaf_hashes()?));
+        Ok(tree)
+    }
+
+    /// Read a tree written by `save`, checking that its root matches its leaves
+    pub fn load(path: &Path) -> Result<Self> {
+        let tree: MerkleTree = read_json(path, "Merkle tree")?;
+        check_version(tree.version, path, "Merkle tree")?;
+
+        let computed = hex::encode(root_hash(tree.algorithm()?, &tree.leaf_hashes()?));
+        if !computed.eq_ignore_ascii_case(&tree.root) {
+            return Err(anyhow!(
+                "Merkle tree {} is corrupt: its leaves hash to {}, not {}",
+                path.display(),
+                computed,
+                tree.root
+            ));
+        }
+
+        Ok(tree)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_json(path, self, "Merkle tree")
+    }
+
+    /// Algorithm of the file digests and the tree
+    pub fn algorithm(&self) -> Result<Algorithm> {
+        self.algorithm.parse()
+    }
+
+    /// Leaf hashes in leaf order
+    fn leaf_hashes(&self) -> Result<Vec<Vec<u8>>> {
+        let algorithm = self.algorithm()?;
+        self.leaves
+            .iter()
+            .map(|leaf| {
+                let digest = hex::decode(&leaf.digest)
+                    .with_context(|| format!("Invalid digest for {}", leaf.path))?;
+                Ok(leaf_hash(algorithm, &leaf.path, &digest))
+            })
+            .collect()
+    }
+
+    /// Inclusion proof for the leaf with the given path
+    pub fn prove(&self, path: &str) -> Result<InclusionProof> {
+        let index = self
+            .leaves
+            .iter()
+            .position(|leaf| leaf.path == path)
+            .ok_or_else(|| anyhow!("{} is not a leaf of the Merkle tree", path))?;
+
+        let proof = inclusion_path(self.algorithm()?, &self.leaf_hashes()?, index);
+        Ok(InclusionProof {
+            version: FORMAT_VERSION,
+            algorithm: self.algorithm.clone(),
+            root: self.root.clone(),
+            leaf_count: self.leaves.len() as u64,
+            index: index as u64,
+            path: self.leaves[index].path.clone(),
+            digest: self.leaves[index].digest.clone(),
+            proof: proof.iter().map(hex::encode).collect(),
+        })
+    }
+}
+
+/// Proof that one file is a leaf of a tree with a given root
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub version: u32,
+    pub algorithm: String,
+    /// Root the proof was produced for (informational; verification uses
+    /// the root given by the caller)
+    pub root: String,
+    pub leaf_count: u64,
+    pub index: u64,
+    /// Leaf path, relative to the tree file's directory
+    pub path: String,
+    /// Hex-encoded file digest
+    pub digest: String,
+    /// Hex-encoded sibling hashes from the leaf up to the root
+    pub proof: Vec<String>,
+}
+
+impl InclusionProof {
+    pub fn load(path: &Path) -> Result<Self> {
+        let proof: InclusionProof = read_json(path, "Merkle proof")?;
+        check_version(proof.version, path, "Merkle proof")?;
+        Ok(proof)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_json(path, self, "Merkle proof")
+    }
+
+    pub fn algorithm(&self) -> Result<Algorithm> {
+        self.algorithm.parse()
+    }
+
+    /// Check that a file with digest `file_digest` is this proof's leaf in
+    /// the tree with root `root`
+    pub fn verify(&self, file_digest: &[u8], root: &[u8]) -> Result<bool> {
+        let algorithm = self.algorithm()?;
+        let path = self
+            .proof
+            .iter()
+            .map(|h| hex::decode(h).context("Invalid hash in Merkle proof"))
+            .collect::<Result<Vec<_>>>()?;
+
+        let leaf = leaf_hash(algorithm, &self.path, file_digest);
+        Ok(verify_inclusion(
+            algorithm,
+            &leaf,
+            self.index,
+            self.leaf_count,
+            &path,
+            root,
+        ))
+    }
+}
+
+/// Read a JSON tree or proof file
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path, what: &str) -> Result<T> {
+    let contents =
+        fs::read(path).with_context(|| format!("Failed to read {}: {}", what, path.display()))?;
+    serde_json::from_slice(&contents)
+        .with_context(|| format!("Invalid {}: {}", what, path.display()))
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T, what: &str) -> Result<()> {
+    let mut contents = serde_json::to_vec(value)?;
+    contents.push(b'\n');
+    fs::write(path, contents)
+        .with_context(|| format!("Failed to write {}: {}", what, path.display()))
+}
+
+fn check_version(version: u32, path: &Path, what: &str) -> Result<()> {
+    if version != FORMAT_VERSION {
+        return Err(anyhow!(
+            "Unsupported {} version {} in {} (expected {})",
+            what,
+            version,
+            path.display(),
+            FORMAT_VERSION
+        ));
+    }
+    Ok(())
+}
@@ -1,5 +1,10 @@
+use crate::baseline::AttributeChange;
+use crate::bench::{FileResult, HostInfo, MemoryResult};
+use crate::chunks::{Chunk, DedupSummary};
+use crate::dupes::DuplicateGroup;
 use base64::{engine::general_purpose, Engine as _};
 use serde::Serialize;
+use std::collections::BTreeMap;
 
 /// Output format options for hash encoding
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -43,7 +48,17 @@ pub struct HashJsonOutput {
     pub algo: String,
     pub source: String,
     pub digest: String,
+    /// Digest per algorithm when several algorithms were requested
+    /// (`digest` then holds the first algorithm's digest)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digests: Option<BTreeMap<String, String>>,
     pub bytes: usize,
+    /// Requested digest length in bytes (extendable-output functions only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<usize>,
+    /// Part of the file that was hashed (`--offset`/`--range-length` only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<ByteRange>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -52,6 +67,13 @@ pub struct HashJsonOutput {
     pub error: Option<String>,
 }
 
+/// Byte range of a file: `length` bytes starting at `offset`
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ByteRange {
+    pub offset: u64,
+    pub length: u64,
+}
+
 /// JSON output structure for batch hash results
 #[derive(Debug, Serialize)]
 pub struct BatchHashJsonOutput {
@@ -60,3 +82,123 @@ pub struct BatchHashJsonOutput {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub summary: Option<String>,
 }
+
+/// JSON output for one path of an audit
+#[derive(Debug, Serialize)]
+pub struct AuditJsonResult {
+    pub path: String,
+    /// One of `unchanged`, `modified`, `missing`, `new` or `unreadable`
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Number of paths per audit status
+#[derive(Debug, Default, Serialize)]
+pub struct AuditSummary {
+    pub unchanged: usize,
+    pub modified: usize,
+    pub missing: usize,
+    pub new: usize,
+    pub unreadable: usize,
+}
+
+impl AuditSummary {
+    /// Number of paths that differ from the manifest
+    pub fn drift(&self) -> usize {
+        self.modified + self.missing + self.new + self.unreadable
+    }
+}
+
+/// JSON output structure for audit results
+#[derive(Debug, Serialize)]
+pub struct AuditJsonOutput {
+    pub algo: String,
+    pub manifest: String,
+    pub directory: String,
+    pub results: Vec<AuditJsonResult>,
+    pub summary: AuditSummary,
+}
+
+/// JSON output for one file that differs from a baseline
+#[derive(Debug, Serialize)]
+pub struct BaselineJsonResult {
+    pub path: String,
+    /// One of `changed`, `added`, `removed` or `unreadable`
+    pub status: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<AttributeChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Number of files per baseline check status
+#[derive(Debug, Default, Serialize)]
+pub struct BaselineSummary {
+    pub unchanged: usize,
+    pub changed: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub unreadable: usize,
+}
+
+/// JSON output structure for `baseline check`
+#[derive(Debug, Serialize)]
+pub struct BaselineJsonOutput {
+    pub algo: String,
+    pub database: String,
+    pub results: Vec<BaselineJsonResult>,
+    pub summary: BaselineSummary,
+}
+
+/// Totals for `hashy dupes`
+#[derive(Debug, Default, Serialize)]
+pub struct DupesSummary {
+    pub groups: usize,
+    /// Files beyond the first of each group
+    pub duplicates: usize,
+    /// Bytes freed by keeping one file per group
+    pub reclaimable_bytes: u64,
+}
+
+/// JSON output structure for `hashy dupes`
+#[derive(Debug, Serialize)]
+pub struct DupesJsonOutput {
+    pub algo: String,
+    pub groups: Vec<DuplicateGroup>,
+    pub summary: DupesSummary,
+}
+
+/// Chunks of one file in `hashy chunks --json`
+#[derive(Debug, Serialize)]
+pub struct ChunkedFileJson {
+    pub path: String,
+    pub size: u64,
+    pub chunks: Vec<Chunk>,
+}
+
+/// JSON output structure for `hashy chunks`
+#[derive(Debug, Serialize)]
+pub struct ChunksJsonOutput {
+    pub algo: String,
+    pub min_size: u32,
+    pub avg_size: u32,
+    pub max_size: u32,
+    pub files: Vec<ChunkedFileJson>,
+    pub summary: DedupSummary,
+}
+
+/// JSON output structure for `hashy bench`
+#[derive(Debug, Serialize)]
+pub struct BenchJsonOutput {
+    pub host: HostInfo,
+    pub duration_ms: u64,
+    pub memory: Vec<MemoryResult>,
+    /// Size of the temp file, 0 when the file benchmarks were skipped
+    pub file_size: u64,
+    pub file: Vec<FileResult>,
+}
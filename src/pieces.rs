@@ -0,0 +1,189 @@
+use crate::algorithm::Algorithm;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::Path;
+
+/// Size of the buffer used when reading a file for piecewise hashing
+const BUFFER_SIZE: usize = 64 * 1024; // 64 KiB
+
+/// Digest of one fixed-size block of a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Piece {
+    pub offset: u64,
+    pub length: u64,
+    /// Hex-encoded digest of the piece's bytes
+    pub digest: String,
+}
+
+impl Piece {
+    /// Offset of the piece's last byte
+    pub fn end(&self) -> u64 {
+        (self.offset + self.length).saturating_sub(1)
+    }
+}
+
+/// Output of `hashy hash --file FILE --piece-size N --json`, read back by
+/// `hashy verify --pieces`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PieceList {
+    pub algo: String,
+    pub source: String,
+    /// Hex-encoded digest of the whole file
+    pub digest: String,
+    pub bytes: u64,
+    /// Requested digest length in bytes (extendable-output functions only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<usize>,
+    pub file_path: String,
+    pub piece_size: u64,
+    pub pieces: Vec<Piece>,
+}
+
+impl PieceList {
+    /// Read a piece list written with `hash --piece-size --json`
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read(path)
+            .with_context(|| format!("Failed to read piece list: {}", path.display()))?;
+        let list: PieceList = serde_json::from_slice(&contents)
+            .with_context(|| format!("Invalid piece list: {}", path.display()))?;
+        if list.piece_size == 0 {
+            return Err(anyhow!(
+                "Invalid piece list: {} (piece size is 0)",
+                path.display()
+            ));
+        }
+        Ok(list)
+    }
+
+    pub fn algorithm(&self) -> Result<Algorithm> {
+        self.algo.parse()
+    }
+}
+
+/// Hash a file in one pass, producing the digest of the whole file and one
+/// digest per `piece_size` block (the last piece may be shorter).
+/// Returns (whole-file digest, pieces, bytes read)
+pub fn hash_file_pieces(
+    algorithm: Algorithm,
+    length: Option<usize>,
+    file_path: &str,
+    piece_size: u64,
+) -> Result<(Vec<u8>, Vec<Piece>, u64)> {
+    if piece_size == 0 {
+        return Err(anyhow!("Piece size must be at least 1 byte"));
+    }
+
+    let file =
+        File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
+    let mut reader = BufReader::new(file);
+
+    let mut whole = algorithm.hasher_with_length(length)?;
+    let mut piece = algorithm.hasher_with_length(length)?;
+    let mut piece_offset = 0u64;
+    let mut piece_filled = 0u64;
+    let mut pieces = Vec::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut total_bytes = 0u64;
+
+    loop {
+        let bytes_read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read file: {}", file_path)),
+        };
+        total_bytes += bytes_read as u64;
+
+        let mut data = &buffer[..bytes_read];
+        whole.update(data);
+        while !data.is_empty() {
+            let take = ((piece_size - piece_filled) as usize).min(data.len());
+            piece.update(&data[..take]);
+            piece_filled += take as u64;
+            data = &data[take..];
+
+            if piece_filled == piece_size {
+                let finished = std::mem::replace(&mut piece, algorithm.hasher_with_length(length)?);
+                pieces.push(Piece {
+                    offset: piece_offset,
+                    length: piece_filled,
+                    digest: hex::encode(finished.finalize()),
+                });
+                piece_offset += piece_filled;
+                piece_filled = 0;
+            }
+        }
+    }
+
+    if piece_filled > 0 {
+        pieces.push(Piece {
+            offset: piece_offset,
+            length: piece_filled,
+            digest: hex::encode(piece.finalize()),
+        });
+    }
+
+    Ok((whole.finalize(), pieces, total_bytes))
+}
+
+/// Why a run of pieces failed verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PieceStatus {
+    /// The bytes differ from the recorded piece
+    Mismatch,
+    /// The file ends before the recorded piece
+    Missing,
+    /// The file continues past the last recorded piece
+    Unexpected,
+}
+
+/// A run of consecutive pieces with the same failure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BadRange {
+    pub status: PieceStatus,
+    /// Index of the first and last piece of the run
+    pub first_piece: usize,
+    pub last_piece: usize,
+    /// First and last byte offset covered by the run
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Compare freshly computed pieces against recorded ones and return every
+/// failing byte range, merging adjacent pieces that failed the same way
+pub fn compare_pieces(expected: &[Piece], actual: &[Piece]) -> Vec<BadRange> {
+    let mut ranges: Vec<BadRange> = Vec::new();
+
+    for index in 0..expected.len().max(actual.len()) {
+        let (status, start, end) = match (expected.get(index), actual.get(index)) {
+            (Some(e), Some(a)) => {
+                if e.length == a.length && e.digest.eq_ignore_ascii_case(&a.digest) {
+                    continue;
+                }
+                // A last piece that grew or shrank covers the longer of the two
+                (PieceStatus::Mismatch, e.offset, e.end().max(a.end()))
+            }
+            (Some(e), None) => (PieceStatus::Missing, e.offset, e.end()),
+            (None, Some(a)) => (PieceStatus::Unexpected, a.offset, a.end()),
+            (None, None) => unreachable!("index is below the longer list's length"),
+        };
+
+        match ranges.last_mut() {
+            Some(last) if last.status == status && last.last_piece + 1 == index => {
+                last.last_piece = index;
+                last.end = end;
+            }
+            _ => ranges.push(BadRange {
+                status,
+                first_piece: index,
+                last_piece: index,
+                start,
+                end,
+            }),
+        }
+    }
+
+    ranges
+}
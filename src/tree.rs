@@ -0,0 +1,173 @@
+use crate::algorithm::Algorithm;
+use crate::hash::hash_data_with_length;
+use crate::walk::{build_glob_set, matches_glob};
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Written before the first entry, so a future format can never produce the
+/// same digest as this one
+const TREE_FORMAT: &[u8] = b"hashy-tree-v1\0";
+
+/// What a directory digest covers besides names and contents
+#[derive(Debug, Clone, Default)]
+pub struct TreeOptions {
+    /// Record whether each file is executable
+    pub permissions: bool,
+    /// Record where each symbolic link points
+    pub symlink_targets: bool,
+    /// Glob patterns for files and directories to leave out
+    pub exclude: Vec<String>,
+}
+
+/// Kind of one entry in a tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeEntryKind {
+    Directory,
+    File { executable: bool },
+    Symlink { target: String },
+}
+
+/// One entry of a tree, with its path relative to the root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEntry {
+    /// Components joined with `/` on every platform
+    pub path: String,
+    pub kind: TreeEntryKind,
+}
+
+/// List every directory, file and symbolic link under `root`, hidden ones
+/// included, sorted by the byte order of their paths. Symbolic links are
+/// never followed. Other file types (sockets, FIFOs, devices) are an error.
+pub fn collect_tree(root: &Path, options: &TreeOptions) -> Result<Vec<TreeEntry>> {
+    if !root.is_dir() {
+        return Err(anyhow!("Not a directory: {}", root.display()));
+    }
+    let exclude = build_glob_set(&options.exclude)?;
+
+    let walker = WalkDir::new(root)
+        .min_depth(1)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            !matches_glob(&exclude, relative)
+        });
+
+    let mut entries = Vec::new();
+    for entry in walker {
+        let entry =
+            entry.with_context(|| format!("Failed to walk directory: {}", root.display()))?;
+        let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+
+        let mut components = Vec::new();
+        for component in relative.components() {
+            let name = component
+                .as_os_str()
+                .to_str()
+                .ok_or_else(|| anyhow!("Path is not valid UTF-8: {}", entry.path().display()))?;
+            components.push(name);
+        }
+        let path = components.join("/");
+
+        let file_type = entry.file_type();
+        let kind = if file_type.is_dir() {
+            TreeEntryKind::Directory
+        } else if file_type.is_file() {
+            let metadata = entry
+                .metadata()
+                .with_context(|| format!("Failed to read metadata: {}", entry.path().display()))?;
+            TreeEntryKind::File {
+                executable: is_executable(&metadata),
+            }
+        } else if file_type.is_symlink() {
+            let target = std::fs::read_link(entry.path())
+                .with_context(|| format!("Failed to read link: {}", entry.path().display()))?;
+            TreeEntryKind::Symlink {
+                target: target
+                    .to_str()
+                    .ok_or_else(|| anyhow!("Link target is not valid UTF-8: {}", path))?
+                    .to_string(),
+            }
+        } else {
+            return Err(anyhow!(
+                "Unsupported file type in tree: {}",
+                entry.path().display()
+            ));
+        };
+
+        entries.push(TreeEntry { path, kind });
+    }
+
+    entries.sort_by(|a, b| a.path.as_bytes().cmp(b.path.as_bytes()));
+    Ok(entries)
+}
+
+/// Whether any execute bit is set. Always `false` off Unix.
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// Digest of a whole tree.
+///
+/// The digest is the hash of `hashy-tree-v1\0` followed by one record per
+/// entry in path order:
+///
+/// - directory: `d` path `\0`
+/// - file: `f` (or `x` if executable) path `\0` digest of the contents
+/// - symbolic link: `l` path `\0` target `\0`
+///
+/// Paths cannot contain NUL bytes, so the encoding is unambiguous. File
+/// contents are hashed with `algorithm` at its default size. `file_digests`
+/// holds one digest per file entry, in entry order. When permissions or
+/// symlink targets are left out, every file is recorded as `f` and every
+/// target as empty.
+pub fn tree_digest(
+    algorithm: Algorithm,
+    length: Option<usize>,
+    entries: &[TreeEntry],
+    file_digests: &[Vec<u8>],
+    options: &TreeOptions,
+) -> Result<Vec<u8>> {
+    let mut data = TREE_FORMAT.to_vec();
+    let mut file_digests = file_digests.iter();
+
+    for entry in entries {
+        let tag = match &entry.kind {
+            TreeEntryKind::Directory => b'd',
+            TreeEntryKind::File { executable: true } if options.permissions => b'x',
+            TreeEntryKind::File { .. } => b'f',
+            TreeEntryKind::Symlink { .. } => b'l',
+        };
+        data.push(tag);
+        data.extend_from_slice(entry.path.as_bytes());
+        data.push(0);
+
+        match &entry.kind {
+            TreeEntryKind::Directory => {}
+            TreeEntryKind::File { .. } => {
+                let digest = file_digests
+                    .next()
+                    .ok_or_else(|| anyhow!("Missing digest for {}", entry.path))?;
+                data.extend_from_slice(digest);
+            }
+            TreeEntryKind::Symlink { target } => {
+                if options.symlink_targets {
+                    data.extend_from_slice(target.as_bytes());
+                }
+                data.push(0);
+            }
+        }
+    }
+
+    hash_data_with_length(algorithm, &data, length)
+}
@@ -0,0 +1,149 @@
+use anyhow::{anyhow, Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+use walkdir::{DirEntry, WalkDir};
+
+/// Options controlling how directories are expanded in recursive mode
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// Glob patterns a file must match to be hashed (empty means all files)
+    pub include: Vec<String>,
+    /// Glob patterns for files and directories to skip
+    pub exclude: Vec<String>,
+    /// Maximum directory depth to descend into (1 = direct children only)
+    pub max_depth: Option<usize>,
+    /// Include hidden files and directories (names starting with '.')
+    pub hidden: bool,
+    /// Follow symbolic links instead of skipping them
+    pub follow_symlinks: bool,
+}
+
+/// Build a glob set from a list of patterns
+pub(crate) fn build_glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob =
+            Glob::new(pattern).with_context(|| format!("Invalid glob pattern: {}", pattern))?;
+        builder.add(glob);
+    }
+    builder.build().context("Failed to build glob set")
+}
+
+/// Check whether a path (relative to the walk root) matches a glob set.
+/// Patterns are matched against both the relative path and the file name,
+/// so `*.log` and `target` match at any depth.
+pub(crate) fn matches_glob(set: &GlobSet, relative: &Path) -> bool {
+    set.is_match(relative) || relative.file_name().is_some_and(|name| set.is_match(name))
+}
+
+/// Check if a directory entry is hidden (name starts with '.')
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry
+        .file_name()
+        .to_str()
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+/// One result of walking the paths given to `walk_paths`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalkEntry {
+    /// A file to hash
+    File(String),
+    /// A path that could not be walked, such as an unreadable directory or
+    /// a broken symlink being followed, with the reason
+    Error { path: String, message: String },
+}
+
+/// Expand the given paths into a list of files to hash.
+///
+/// Like [`walk_paths`], but the first path that cannot be walked fails the
+/// whole expansion.
+pub fn collect_files(roots: &[String], options: &WalkOptions) -> Result<Vec<String>> {
+    walk_paths(roots, options)?
+        .into_iter()
+        .map(|entry| match entry {
+            WalkEntry::File(path) => Ok(path),
+            WalkEntry::Error { path, message } => {
+                Err(anyhow!("Failed to walk directory: {}: {}", path, message))
+            }
+        })
+        .collect()
+}
+
+/// Expand the given paths into the files to hash and the paths that could
+/// not be walked, in walk order.
+///
+/// Regular files are passed through unchanged. Directories are walked
+/// recursively in sorted order, applying the include/exclude globs, depth
+/// limit, hidden-file and symlink policies from `options`. Returned paths are
+/// the directory argument joined with the path inside it (e.g. `dist/a/b.txt`).
+/// A walk error only skips the entry it concerns; only invalid globs fail.
+pub fn walk_paths(roots: &[String], options: &WalkOptions) -> Result<Vec<WalkEntry>> {
+    let include = build_glob_set(&options.include)?;
+    let exclude = build_glob_set(&options.exclude)?;
+
+    let mut entries = Vec::new();
+
+    for root in roots {
+        let root_path = Path::new(root);
+        if !root_path.is_dir() {
+            // Explicit files (including missing ones) are hashed as given so
+            // errors are reported the same way as in plain batch mode
+            entries.push(WalkEntry::File(root.clone()));
+            continue;
+        }
+
+        let mut walker = WalkDir::new(root_path)
+            .min_depth(1)
+            .follow_links(options.follow_symlinks)
+            .sort_by_file_name();
+        if let Some(depth) = options.max_depth {
+            walker = walker.max_depth(depth);
+        }
+
+        let iter = walker.into_iter().filter_entry(|entry| {
+            if !options.hidden && is_hidden(entry) {
+                return false;
+            }
+            let relative = entry.path().strip_prefix(root_path).unwrap_or(entry.path());
+            !matches_glob(&exclude, relative)
+        });
+
+        for entry in iter {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    let path = e
+                        .path()
+                        .map_or_else(|| root.clone(), |p| p.display().to_string());
+                    let message = match e.io_error() {
+                        Some(io) => io.to_string(),
+                        None => e.to_string(),
+                    };
+                    entries.push(WalkEntry::Error { path, message });
+                    continue;
+                }
+            };
+
+            // Skip directories, and symlinks when they are not being followed
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let relative = entry.path().strip_prefix(root_path).unwrap_or(entry.path());
+            if !options.include.is_empty() && !matches_glob(&include, relative) {
+                continue;
+            }
+
+            match entry.path().to_str() {
+                Some(path) => entries.push(WalkEntry::File(path.to_string())),
+                None => entries.push(WalkEntry::Error {
+                    path: entry.path().display().to_string(),
+                    message: "Path is not valid UTF-8".to_string(),
+                }),
+            }
+        }
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,101 @@
+use crate::algorithm::Algorithm;
+#[cfg(unix)]
+use anyhow::Context;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Prefix of the extended attributes hashy writes; the algorithm name follows
+pub const ATTR_PREFIX: &str = "user.hashy.";
+
+/// Digest stored in a `user.hashy.<algo>` extended attribute, as JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredDigest {
+    pub algo: String,
+    /// Hex-encoded digest
+    pub digest: String,
+    /// File modification time (nanoseconds since the Unix epoch) when the
+    /// digest was computed
+    pub mtime_ns: Option<i64>,
+}
+
+/// Name of the attribute holding the digest for `algorithm`
+pub fn attr_name(algorithm: Algorithm) -> String {
+    format!("{}{}", ATTR_PREFIX, algorithm.name())
+}
+
+/// Modification time of a file in nanoseconds since the Unix epoch
+pub fn file_mtime_ns(path: &Path) -> Option<i64> {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .and_then(|d| i64::try_from(d.as_nanos()).ok())
+}
+
+/// Store a digest in the file's `user.hashy.<algo>` attribute
+#[cfg(unix)]
+pub fn write_digest(
+    path: &str,
+    algorithm: Algorithm,
+    digest: &[u8],
+    mtime_ns: Option<i64>,
+) -> Result<()> {
+    let stored = StoredDigest {
+        algo: algorithm.name().to_string(),
+        digest: hex::encode(digest),
+        mtime_ns,
+    };
+    let value = serde_json::to_vec(&stored)?;
+
+    xattr::set(path, attr_name(algorithm), &value)
+        .with_context(|| format!("Failed to write extended attribute on {}", path))
+}
+
+/// Read every digest hashy stored on a file, in attribute name order
+#[cfg(unix)]
+pub fn read_digests(path: &str) -> Result<Vec<(Algorithm, StoredDigest)>> {
+    let mut names: Vec<String> = xattr::list(path)
+        .with_context(|| format!("Failed to read extended attributes of {}", path))?
+        .filter_map(|name| name.to_str().map(str::to_string))
+        .filter(|name| name.starts_with(ATTR_PREFIX))
+        .collect();
+    names.sort();
+
+    let mut digests = Vec::new();
+    for name in names {
+        let Some(value) = xattr::get(path, &name)
+            .with_context(|| format!("Failed to read extended attribute {} of {}", name, path))?
+        else {
+            continue;
+        };
+
+        let algorithm: Algorithm = name[ATTR_PREFIX.len()..]
+            .parse()
+            .with_context(|| format!("Invalid {} attribute on {}", name, path))?;
+        let stored: StoredDigest = serde_json::from_slice(&value)
+            .with_context(|| format!("Invalid {} attribute on {}", name, path))?;
+        digests.push((algorithm, stored));
+    }
+
+    Ok(digests)
+}
+
+#[cfg(not(unix))]
+pub fn write_digest(
+    _path: &str,
+    _algorithm: Algorithm,
+    _digest: &[u8],
+    _mtime_ns: Option<i64>,
+) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "Extended attributes are not supported on this platform"
+    ))
+}
+
+#[cfg(not(unix))]
+pub fn read_digests(_path: &str) -> Result<Vec<(Algorithm, StoredDigest)>> {
+    Err(anyhow::anyhow!(
+        "Extended attributes are not supported on this platform"
+    ))
+}
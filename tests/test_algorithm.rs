@@ -56,3 +56,106 @@ fn test_unsupported_algorithm() {
         .failure()
         .stderr(predicate::str::contains("Unsupported algorithm"));
 }
+
+/// Run `hashy hash --algo <algo> --format hex --text <text>` and return the digest
+fn hash_text_hex(algo: &str, text: &str) -> String {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg(algo)
+        .arg("--format")
+        .arg("hex")
+        .arg("--text")
+        .arg(text);
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(output.status.success(), "hashing with {} failed", algo);
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[test]
+fn test_sha3_nist_vectors() {
+    // FIPS 202 known-answer tests for "abc" and the empty message
+    let vectors = [
+        (
+            "sha3-224",
+            "abc",
+            "e642824c3f8cf24ad09234ee7d3c766fc9a3a5168d0c94ad73b46fdf",
+        ),
+        (
+            "sha3-224",
+            "",
+            "6b4e03423667dbb73b6e15454f0eb1abd4597f9a1b078e3f5b5a6bc7",
+        ),
+        (
+            "sha3-256",
+            "abc",
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+        ),
+        (
+            "sha3-256",
+            "",
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+        ),
+        (
+            "sha3-384",
+            "abc",
+            "ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b298d88cea927ac7f539f1edf228376d25",
+        ),
+        (
+            "sha3-512",
+            "abc",
+            "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+        ),
+    ];
+
+    for (algo, text, expected) in vectors {
+        assert_eq!(hash_text_hex(algo, text), expected, "{}({:?})", algo, text);
+    }
+}
+
+#[test]
+fn test_keccak_vectors() {
+    // Original Keccak padding (as used by Ethereum), not FIPS 202 SHA-3
+    assert_eq!(
+        hash_text_hex("keccak256", ""),
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+    );
+    assert_eq!(
+        hash_text_hex("keccak256", "abc"),
+        "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+    );
+    assert_eq!(
+        hash_text_hex("keccak512", ""),
+        "0eab42de4c3ceb9235fc91acffe746b29c29a8c366b7c60e4e67c466f36a4304c00fa9caf9d87976ba469bcbe06713b435f091ef2769fb160cdab33d3670680e"
+    );
+}
+
+#[test]
+fn test_shake_default_lengths() {
+    // SHAKE128 defaults to 256 bits, SHAKE256 to 512 bits
+    assert_eq!(
+        hash_text_hex("shake128", ""),
+        "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+    );
+    assert_eq!(
+        hash_text_hex("shake128", "abc"),
+        "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8"
+    );
+    assert_eq!(
+        hash_text_hex("shake256", ""),
+        "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be"
+    );
+}
+
+#[test]
+fn test_sha3_algorithm_aliases() {
+    // Underscore and uppercase spellings are accepted
+    assert_eq!(
+        hash_text_hex("SHA3_256", "abc"),
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+    );
+    assert_eq!(
+        hash_text_hex("Keccak-256", "abc"),
+        "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+    );
+}
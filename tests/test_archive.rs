@@ -0,0 +1,173 @@
+mod common;
+
+use common::get_cmd;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use tempfile::TempDir;
+use xz2::write::XzEncoder;
+
+const MEMBERS: [(&str, &[u8]); 3] = [
+    ("./README.md", b"read me\n"),
+    ("./src/main.rs", b"fn main() {}\n"),
+    ("./src/lib.rs", b""),
+];
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn tar_bytes() -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut dir = tar::Header::new_gnu();
+    dir.set_entry_type(tar::EntryType::Directory);
+    dir.set_size(0);
+    dir.set_mode(0o755);
+    builder
+        .append_data(&mut dir, "./src/", std::io::empty())
+        .unwrap();
+
+    for (path, contents) in MEMBERS {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, path, contents).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+fn write_zip(path: &Path) {
+    let mut writer = zip::ZipWriter::new(File::create(path).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    writer.add_directory("src/", options).unwrap();
+    for (name, contents) in MEMBERS {
+        writer
+            .start_file(name.trim_start_matches("./"), options)
+            .unwrap();
+        writer.write_all(contents).unwrap();
+    }
+    writer.finish().unwrap();
+}
+
+fn hash_archive(dir: &Path, archive: &str, args: &[&str]) -> String {
+    let output = get_cmd()
+        .current_dir(dir)
+        .args(["hash", "--archive", archive])
+        .args(args)
+        .output()
+        .expect("Failed to execute command");
+    assert!(
+        output.status.success(),
+        "hash --archive failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn expected_lines() -> String {
+    MEMBERS
+        .iter()
+        .map(|(path, contents)| {
+            format!(
+                "{}  {}\n",
+                sha256_hex(contents),
+                path.trim_start_matches("./")
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_archive_tar_formats() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let tar = tar_bytes();
+    fs::write(temp_dir.path().join("bundle.tar"), &tar).unwrap();
+
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(&tar).unwrap();
+    fs::write(temp_dir.path().join("bundle.tar.gz"), gz.finish().unwrap()).unwrap();
+
+    let mut xz = XzEncoder::new(Vec::new(), 6);
+    xz.write_all(&tar).unwrap();
+    fs::write(temp_dir.path().join("bundle.tar.xz"), xz.finish().unwrap()).unwrap();
+
+    for archive in ["bundle.tar", "bundle.tar.gz", "bundle.tar.xz"] {
+        assert_eq!(
+            hash_archive(temp_dir.path(), archive, &[]),
+            expected_lines(),
+            "{}",
+            archive
+        );
+    }
+}
+
+#[test]
+fn test_archive_zip_json() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    write_zip(&temp_dir.path().join("bundle.zip"));
+
+    assert_eq!(
+        hash_archive(temp_dir.path(), "bundle.zip", &[]),
+        expected_lines()
+    );
+
+    let stdout = hash_archive(temp_dir.path(), "bundle.zip", &["--json"]);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Output is not valid JSON");
+    let results = json["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[1]["file_path"], "src/main.rs");
+    assert_eq!(results[1]["digest"], sha256_hex(b"fn main() {}\n"));
+    assert_eq!(results[1]["bytes"], 13);
+}
+
+#[test]
+fn test_archive_manifest_verifies_extracted_tree() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    write_zip(&temp_dir.path().join("bundle.zip"));
+
+    // The manifest lives in a different directory than the archive, where
+    // the tree is extracted
+    let extracted = temp_dir.path().join("extracted");
+    fs::create_dir_all(extracted.join("src")).unwrap();
+    for (path, contents) in MEMBERS {
+        fs::write(extracted.join(path), contents).unwrap();
+    }
+
+    hash_archive(
+        temp_dir.path(),
+        "bundle.zip",
+        &["--manifest", "extracted/SHA256SUMS"],
+    );
+    assert_eq!(
+        fs::read_to_string(extracted.join("SHA256SUMS")).unwrap(),
+        expected_lines()
+    );
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["verify", "extracted/SHA256SUMS"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(
+        output.status.success(),
+        "verify failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn test_archive_unsupported_format() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("notes.txt"), b"not an archive").unwrap();
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["hash", "--archive", "notes.txt"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unsupported archive format"));
+}
@@ -0,0 +1,211 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::get_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+/// Create a release folder (a.txt, b.txt, sub/c.txt) with a SHA256SUMS manifest
+fn create_release() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("sub")).expect("Failed to create dirs");
+    fs::write(root.join("a.txt"), "hello").expect("Failed to write test file");
+    fs::write(root.join("b.txt"), "world").expect("Failed to write test file");
+    fs::write(root.join("sub/c.txt"), "nested").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(root)
+        .arg("hash")
+        .arg("-r")
+        .arg("--manifest")
+        .arg("SHA256SUMS")
+        .arg(".");
+    cmd.assert().success();
+
+    temp_dir
+}
+
+#[test]
+fn test_audit_unchanged_tree_passes() {
+    let temp_dir = create_release();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("audit")
+        .arg("SHA256SUMS")
+        .arg(".");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("a.txt: OK"))
+        .stdout(predicate::str::contains("sub/c.txt: OK"))
+        .stdout(predicate::str::contains(
+            "Summary: 3 unchanged, 0 modified, 0 missing, 0 new",
+        ))
+        .stdout(predicate::str::contains("SHA256SUMS").not());
+}
+
+#[test]
+fn test_audit_reports_drift() {
+    let temp_dir = create_release();
+    let root = temp_dir.path();
+    fs::write(root.join("a.txt"), "tampered").expect("Failed to write test file");
+    fs::remove_file(root.join("b.txt")).expect("Failed to remove file");
+    fs::write(root.join("sub/dropped.bin"), "payload").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(root)
+        .arg("audit")
+        .arg("SHA256SUMS")
+        .arg(".");
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("a.txt: MODIFIED"));
+    assert!(stdout.contains("b.txt: MISSING"));
+    assert!(stdout.contains("sub/c.txt: OK"));
+    assert!(stdout.contains("sub/dropped.bin: NEW"));
+    assert!(stdout.contains("Summary: 1 unchanged, 1 modified, 1 missing, 1 new"));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Audit failed: 3 of 4 paths differ from the manifest"));
+}
+
+#[test]
+fn test_audit_json_output() {
+    let temp_dir = create_release();
+    let root = temp_dir.path();
+    fs::write(root.join("new.txt"), "hello").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(root)
+        .arg("audit")
+        .arg("--json")
+        .arg("SHA256SUMS")
+        .arg(".");
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(!output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Failed to parse JSON output");
+    assert_eq!(json["algo"], "sha256");
+    assert_eq!(json["summary"]["unchanged"], 3);
+    assert_eq!(json["summary"]["new"], 1);
+
+    let results = json["results"]
+        .as_array()
+        .expect("results should be an array");
+    let new_entry = results
+        .iter()
+        .find(|r| r["path"] == "new.txt")
+        .expect("new.txt should be reported");
+    assert_eq!(new_entry["status"], "new");
+    assert_eq!(
+        new_entry["actual"],
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+}
+
+#[test]
+fn test_audit_manifest_outside_directory() {
+    let outer = TempDir::new().expect("Failed to create temp directory");
+    let release = outer.path().join("dist");
+    fs::create_dir_all(release.join("sub")).expect("Failed to create dirs");
+    fs::write(release.join("a.txt"), "hello").expect("Failed to write test file");
+    fs::write(release.join("sub/b.txt"), "world").expect("Failed to write test file");
+
+    // Stored manifest: written into the release, then moved elsewhere
+    let mut cmd = get_cmd();
+    cmd.current_dir(outer.path())
+        .arg("hash")
+        .arg("-r")
+        .arg("--manifest")
+        .arg("dist/SHA256SUMS")
+        .arg("dist");
+    cmd.assert().success();
+    fs::create_dir(outer.path().join("other")).expect("Failed to create dir");
+    fs::rename(release.join("SHA256SUMS"), outer.path().join("other/SUMS"))
+        .expect("Failed to move manifest");
+
+    // Entries are resolved against the audited directory
+    let mut cmd = get_cmd();
+    cmd.current_dir(outer.path())
+        .arg("audit")
+        .arg("other/SUMS")
+        .arg("dist");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "a.txt: OK\nsub/b.txt: OK\nSummary: 2 unchanged, 0 modified, 0 missing, 0 new\n",
+    ));
+
+    fs::write(release.join("extra.txt"), "extra").expect("Failed to write test file");
+    let mut cmd = get_cmd();
+    cmd.arg("audit")
+        .arg(outer.path().join("other/SUMS").as_os_str())
+        .arg(release.as_os_str());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("a.txt: OK"))
+        .stdout(predicate::str::contains("extra.txt: NEW"))
+        .stdout(predicate::str::contains("MISSING").not());
+
+    // A copy of the manifest inside the directory is drift too
+    fs::remove_file(release.join("extra.txt")).expect("Failed to remove test file");
+    fs::copy(outer.path().join("other/SUMS"), release.join("SUMS.bak"))
+        .expect("Failed to copy manifest");
+    let mut cmd = get_cmd();
+    cmd.current_dir(outer.path())
+        .arg("audit")
+        .arg("other/SUMS")
+        .arg("dist");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("SUMS.bak: NEW"));
+}
+
+#[test]
+fn test_audit_exclude_and_hidden() {
+    let temp_dir = create_release();
+    let root = temp_dir.path();
+    fs::write(root.join("build.log"), "log").expect("Failed to write test file");
+    fs::write(root.join(".cache"), "cache").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(root)
+        .arg("audit")
+        .arg("--exclude")
+        .arg("*.log")
+        .arg("SHA256SUMS")
+        .arg(".");
+    cmd.assert().success();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(root)
+        .arg("audit")
+        .arg("--exclude")
+        .arg("*.log")
+        .arg("--hidden")
+        .arg("SHA256SUMS")
+        .arg(".");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains(".cache: NEW"));
+}
+
+#[test]
+fn test_audit_requires_directory() {
+    let temp_dir = create_release();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("audit")
+        .arg("SHA256SUMS")
+        .arg("a.txt");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Not a directory"));
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::get_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+const HELLO_SHA512: &str = "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca72323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043";
+const HELLO_BLAKE3: &str = "ea8f163db38682925e4491c5e58d4bb3506ef8c14eb78a86e908c5624a67200f";
+const HELLO_SHA1: &str = "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d";
+
+/// Write hello.txt and a one-line manifest named `manifest_name`
+fn create_manifest(manifest_name: &str, digest: &str) -> (TempDir, std::path::PathBuf) {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("hello.txt"), "hello").expect("Failed to write test file");
+    let manifest_path = temp_dir.path().join(manifest_name);
+    fs::write(&manifest_path, format!("{}  hello.txt\n", digest))
+        .expect("Failed to write manifest");
+    (temp_dir, manifest_path)
+}
+
+#[test]
+fn test_detect_from_sums_file_name() {
+    let (_dir, manifest_path) = create_manifest("SHA512SUMS", HELLO_SHA512);
+
+    let mut cmd = get_cmd();
+    cmd.arg("--verbose")
+        .arg("verify")
+        .arg(manifest_path.as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hello.txt: OK"))
+        .stderr(predicate::str::contains(
+            "Detected algorithm: sha512 (manifest file name)",
+        ));
+}
+
+#[test]
+fn test_detect_from_extension_and_b3_name() {
+    let (_dir, manifest_path) = create_manifest("release.sha512", HELLO_SHA512);
+    let mut cmd = get_cmd();
+    cmd.arg("verify").arg(manifest_path.as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hello.txt: OK"));
+
+    let (_dir, manifest_path) = create_manifest("B3SUMS", HELLO_BLAKE3);
+    let mut cmd = get_cmd();
+    cmd.arg("verify").arg(manifest_path.as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hello.txt: OK"));
+}
+
+#[test]
+fn test_detect_from_unique_digest_length() {
+    let (_dir, manifest_path) = create_manifest("checksums.txt", HELLO_SHA1);
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify")
+        .arg("--allow-insecure")
+        .arg(manifest_path.as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hello.txt: OK"));
+
+    // Detected insecure algorithms are still gated
+    let mut cmd = get_cmd();
+    cmd.arg("verify").arg(manifest_path.as_os_str());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--allow-insecure"));
+}
+
+#[test]
+fn test_explicit_auto_reports_ambiguity() {
+    let (_dir, manifest_path) = create_manifest("checksums.txt", HELLO_SHA256);
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify")
+        .arg("--algo")
+        .arg("auto")
+        .arg(manifest_path.as_os_str());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Ambiguous algorithm"))
+        .stderr(predicate::str::contains("sha256, blake3"));
+}
+
+#[test]
+fn test_default_prefers_sha256_when_ambiguous() {
+    let (_dir, manifest_path) = create_manifest("checksums.txt", HELLO_SHA256);
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify").arg(manifest_path.as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hello.txt: OK"));
+}
+
+#[test]
+fn test_default_errors_when_sha256_cannot_fit() {
+    let (_dir, manifest_path) = create_manifest("checksums.txt", HELLO_SHA512);
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify").arg(manifest_path.as_os_str());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("128-character digests"))
+        .stderr(predicate::str::contains("sha512"));
+}
+
+#[test]
+fn test_detect_rejects_mixed_digest_lengths() {
+    let (dir, manifest_path) = create_manifest("checksums.txt", HELLO_SHA256);
+    fs::write(
+        &manifest_path,
+        format!("{}  hello.txt\n{}  hello.txt\n", HELLO_SHA256, HELLO_SHA512),
+    )
+    .expect("Failed to write manifest");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(dir.path())
+        .arg("verify")
+        .arg("checksums.txt");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("mixes digests of 64, 128"));
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::get_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+/// Create a monitored directory `etc` with two files and record a baseline
+/// in `base.db` next to it
+fn init_baseline() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    fs::create_dir(root.join("etc")).expect("Failed to create dirs");
+    fs::write(root.join("etc/hosts"), "127.0.0.1 localhost").expect("Failed to write test file");
+    fs::write(root.join("etc/motd"), "welcome").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(root)
+        .arg("baseline")
+        .arg("init")
+        .arg("--db")
+        .arg("base.db")
+        .arg("etc");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Recorded 2 files in baseline"));
+
+    temp_dir
+}
+
+#[test]
+fn test_baseline_check_unchanged() {
+    let temp_dir = init_baseline();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("baseline")
+        .arg("check")
+        .arg("--db")
+        .arg("base.db");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "Summary: 2 unchanged, 0 changed, 0 added, 0 removed\n",
+    ));
+}
+
+#[test]
+fn test_baseline_check_reports_changes() {
+    let temp_dir = init_baseline();
+    let etc = temp_dir.path().join("etc");
+    fs::write(etc.join("hosts"), "10.0.0.1 evil.example").expect("Failed to write test file");
+    fs::remove_file(etc.join("motd")).expect("Failed to remove file");
+    fs::write(etc.join("backdoor"), "payload").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("baseline")
+        .arg("check")
+        .arg("--db")
+        .arg("base.db");
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("hosts: CHANGED (digest, size"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("  digest: "));
+    assert!(stdout.contains("motd: REMOVED"));
+    assert!(stdout.contains("backdoor: ADDED"));
+    assert!(stdout.contains("Summary: 0 unchanged, 1 changed, 1 added, 1 removed"));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Baseline check failed: 3 files differ from the baseline"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_baseline_check_reports_mode_change() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = init_baseline();
+    let motd = temp_dir.path().join("etc/motd");
+    fs::set_permissions(&motd, fs::Permissions::from_mode(0o666))
+        .expect("Failed to set permissions");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("baseline")
+        .arg("check")
+        .arg("--json")
+        .arg("--db")
+        .arg("base.db");
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(!output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Failed to parse JSON output");
+    assert_eq!(json["summary"]["changed"], 1);
+
+    let result = &json["results"][0];
+    assert!(result["path"].as_str().unwrap().ends_with("etc/motd"));
+    assert_eq!(result["status"], "changed");
+    assert_eq!(result["changes"][0]["attribute"], "mode");
+    assert_eq!(result["changes"][0]["new"], "100666");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_baseline_check_continues_past_unreadable_file() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = init_baseline();
+    let etc = temp_dir.path().join("etc");
+    let hosts = etc.join("hosts");
+    fs::set_permissions(&hosts, fs::Permissions::from_mode(0o000))
+        .expect("Failed to set permissions");
+    if fs::read(&hosts).is_ok() {
+        // Running as root: permissions do not stop reads
+        return;
+    }
+    fs::write(etc.join("backdoor"), "payload").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("baseline")
+        .arg("check")
+        .arg("--db")
+        .arg("base.db");
+    let output = cmd.output().expect("Failed to execute command");
+    fs::set_permissions(&hosts, fs::Permissions::from_mode(0o644))
+        .expect("Failed to set permissions");
+    assert!(!output.status.success());
+
+    // The unreadable file is neither removed nor the end of the scan
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("hosts: FAILED ("), "{}", stdout);
+    assert!(stdout.contains("backdoor: ADDED"));
+    assert!(!stdout.contains("REMOVED"));
+    assert!(stdout.contains("Summary: 1 unchanged, 0 changed, 1 added, 0 removed, 1 unreadable"));
+}
+
+#[test]
+fn test_baseline_quiet_hides_missing_root_warning() {
+    let temp_dir = init_baseline();
+    fs::remove_dir_all(temp_dir.path().join("etc")).expect("Failed to remove dir");
+
+    let run = |quiet: bool| {
+        let mut cmd = get_cmd();
+        cmd.current_dir(temp_dir.path());
+        if quiet {
+            cmd.arg("--quiet");
+        }
+        cmd.args(["baseline", "check", "--db", "base.db"])
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run(false);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is no longer a directory"));
+
+    let output = run(true);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("hosts: REMOVED"));
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("WARNING"));
+}
+
+#[test]
+fn test_baseline_update_accepts_changes() {
+    let temp_dir = init_baseline();
+    fs::write(temp_dir.path().join("etc/motd"), "new message").expect("Failed to write file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("baseline")
+        .arg("update")
+        .arg("--db")
+        .arg("base.db");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("motd: CHANGED"))
+        .stdout(predicate::str::contains(
+            "Updated baseline: 2 files recorded",
+        ));
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("baseline")
+        .arg("check")
+        .arg("--db")
+        .arg("base.db");
+    cmd.assert().success();
+}
+
+#[test]
+fn test_baseline_check_from_other_directory() {
+    let temp_dir = init_baseline();
+
+    // Roots are recorded as absolute paths
+    let mut cmd = get_cmd();
+    cmd.arg("baseline")
+        .arg("check")
+        .arg("--db")
+        .arg(temp_dir.path().join("base.db").as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("2 unchanged"));
+}
+
+#[test]
+fn test_baseline_init_refuses_existing_database() {
+    let temp_dir = init_baseline();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("baseline")
+        .arg("init")
+        .arg("--db")
+        .arg("base.db")
+        .arg("etc");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+}
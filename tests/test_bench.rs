@@ -0,0 +1,151 @@
+mod common;
+
+use common::get_cmd;
+use hashy::bench::BenchFile;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_bench_json() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let output = get_cmd()
+        .args([
+            "bench",
+            "--algo",
+            "sha256,blake3",
+            "--sizes",
+            "64,4096",
+            "--file-size",
+            "100000",
+            "--io",
+            "read,mmap",
+            "--duration",
+            "1",
+            "--dir",
+            temp_dir.path().to_str().unwrap(),
+            "--json",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(
+        output.status.success(),
+        "bench failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output is not valid JSON");
+    assert!(json["host"]["threads"].as_u64().unwrap() >= 1);
+    assert_eq!(json["duration_ms"], 1);
+    assert_eq!(json["file_size"], 100000);
+
+    let memory = json["memory"].as_array().unwrap();
+    let cases: Vec<(&str, u64)> = memory
+        .iter()
+        .map(|r| {
+            (
+                r["algo"].as_str().unwrap(),
+                r["buffer_size"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        cases,
+        [
+            ("sha256", 64),
+            ("sha256", 4096),
+            ("blake3", 64),
+            ("blake3", 4096)
+        ]
+    );
+    for result in memory {
+        assert!(result["mb_per_sec"].as_f64().unwrap() > 0.0);
+        assert_eq!(
+            result["bytes"].as_u64().unwrap(),
+            result["iterations"].as_u64().unwrap() * result["buffer_size"].as_u64().unwrap()
+        );
+    }
+
+    let file = json["file"].as_array().unwrap();
+    let cases: Vec<(&str, &str)> = file
+        .iter()
+        .map(|r| (r["algo"].as_str().unwrap(), r["io"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        cases,
+        [
+            ("sha256", "read"),
+            ("sha256", "mmap"),
+            ("blake3", "read"),
+            ("blake3", "mmap")
+        ]
+    );
+
+    // The temp file is cleaned up
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_bench_text_tables() {
+    let output = get_cmd()
+        .args([
+            "bench",
+            "--algo",
+            "md5",
+            "--sizes",
+            "1024",
+            "--file-size",
+            "0",
+            "--duration",
+            "1",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Host: "));
+    assert!(stdout.contains("In-memory throughput (MB/s)"));
+    assert!(stdout
+        .lines()
+        .any(|line| line.starts_with("algorithm") && line.ends_with("1 KiB")));
+    assert!(stdout.lines().any(|line| line.starts_with("md5 ")));
+    // --file-size 0 skips the file benchmarks
+    assert!(!stdout.contains("File throughput"));
+}
+
+#[test]
+fn test_bench_rejections() {
+    for args in [
+        vec!["--sizes", "0"],
+        vec!["--io", "dma"],
+        vec!["--algo", "crc32"],
+    ] {
+        let output = get_cmd()
+            .args(["bench", "--duration", "1", "--file-size", "1000"])
+            .args(&args)
+            .output()
+            .expect("Failed to execute command");
+        assert!(!output.status.success(), "{:?} should fail", args);
+    }
+}
+
+#[test]
+fn test_bench_file_names_are_unique() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("keep.txt"), b"keep").expect("Failed to write file");
+
+    let first = BenchFile::create(temp_dir.path(), 10).expect("Failed to create bench file");
+    let second = BenchFile::create(temp_dir.path(), 10).expect("Failed to create bench file");
+    assert_ne!(first.path(), second.path());
+    assert_eq!(fs::read(first.path()).unwrap().len(), 10);
+
+    drop(first);
+    drop(second);
+    let names: Vec<_> = fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(names, ["keep.txt"]);
+    assert_eq!(fs::read(temp_dir.path().join("keep.txt")).unwrap(), b"keep");
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::get_cmd;
+use hashy::algorithm::Algorithm;
+use hashy::hash::{hash_data, hash_file_range};
+use std::fs;
+use tempfile::TempDir;
+
+fn image(temp_dir: &TempDir) -> (String, Vec<u8>) {
+    // Larger than one 64 KiB chunk so a range can span several reads
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let path = temp_dir.path().join("disk.img");
+    fs::write(&path, &data).expect("Failed to write file");
+    (path.to_str().unwrap().to_string(), data)
+}
+
+#[test]
+fn test_hash_file_range_library() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let (path, data) = image(&temp_dir);
+
+    for (offset, count) in [(0, Some(512)), (446, Some(64)), (70_000, Some(100_000))] {
+        let digest = hash_file_range(Algorithm::Sha256, &path, offset, count).unwrap();
+        let end = offset as usize + count.unwrap() as usize;
+        assert_eq!(
+            digest,
+            hash_data(Algorithm::Sha256, &data[offset as usize..end])
+        );
+    }
+
+    // Without a length the range runs to the end of the file
+    let digest = hash_file_range(Algorithm::Blake3, &path, 199_000, None).unwrap();
+    assert_eq!(digest, hash_data(Algorithm::Blake3, &data[199_000..]));
+}
+
+#[test]
+fn test_hash_range_json_records_range() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let (path, data) = image(&temp_dir);
+
+    let output = get_cmd()
+        .args([
+            "hash",
+            "--file",
+            &path,
+            "--offset",
+            "446",
+            "--range-length",
+            "64",
+            "--json",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output is not valid JSON");
+    assert_eq!(
+        json["digest"],
+        hex::encode(hash_data(Algorithm::Sha256, &data[446..510]))
+    );
+    assert_eq!(json["bytes"], 64);
+    assert_eq!(json["range"]["offset"], 446);
+    assert_eq!(json["range"]["length"], 64);
+}
+
+#[test]
+fn test_hash_range_text_output() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let (path, data) = image(&temp_dir);
+
+    let output = get_cmd()
+        .args(["hash", "--file", &path, "--offset", "199990"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("(10 bytes at offset 199990)"));
+    assert!(stdout.contains(&hex::encode(hash_data(Algorithm::Sha256, &data[199_990..]))));
+}
+
+#[test]
+fn test_hash_range_out_of_bounds() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let (path, _) = image(&temp_dir);
+
+    for args in [
+        vec!["--offset", "200001"],
+        vec!["--offset", "199990", "--range-length", "11"],
+        vec!["--offset", "1", "--range-length", "18446744073709551615"],
+    ] {
+        let output = get_cmd()
+            .args(["hash", "--file", &path])
+            .args(&args)
+            .output()
+            .expect("Failed to execute command");
+        assert!(!output.status.success(), "{:?} should fail", args);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("past the end"));
+    }
+
+    // An empty range at the very end is fine
+    let output = get_cmd()
+        .args(["hash", "--file", &path, "--offset", "200000"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+}
@@ -0,0 +1,235 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::get_cmd;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+/// Write a file and backdate its mtime so the cache does not treat it as
+/// recently modified
+fn write_old_file(path: &Path, contents: &str) {
+    fs::write(path, contents).expect("Failed to write test file");
+    let file = fs::File::options()
+        .write(true)
+        .open(path)
+        .expect("Failed to open test file");
+    file.set_modified(SystemTime::now() - Duration::from_secs(3600))
+        .expect("Failed to set mtime");
+}
+
+fn cache_stats(temp_dir: &TempDir) -> String {
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("cache")
+        .arg("stats")
+        .arg("--cache")
+        .arg("cache");
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(output.status.success());
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_cache_hit_on_second_run() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    write_old_file(&temp_dir.path().join("a.txt"), "hello");
+    write_old_file(&temp_dir.path().join("b.txt"), "world");
+
+    for expected in ["Cache: 0 hits, 2 misses", "Cache: 2 hits, 0 misses"] {
+        let mut cmd = get_cmd();
+        cmd.current_dir(temp_dir.path())
+            .arg("--verbose")
+            .arg("hash")
+            .arg("--cache")
+            .arg("cache")
+            .arg("a.txt")
+            .arg("b.txt");
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains(HELLO_SHA256))
+            .stderr(predicate::str::contains(expected));
+    }
+
+    assert!(cache_stats(&temp_dir).contains("Entries: 2"));
+}
+
+#[test]
+fn test_cache_rehashes_changed_file() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let file = temp_dir.path().join("a.txt");
+    write_old_file(&file, "stale contents");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("--cache")
+        .arg("cache")
+        .arg("--file")
+        .arg("a.txt");
+    cmd.assert().success();
+
+    write_old_file(&file, "hello");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("--cache")
+        .arg("cache")
+        .arg("--file")
+        .arg("a.txt");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(HELLO_SHA256));
+}
+
+#[test]
+fn test_cache_skips_recently_modified_files() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("a.txt"), "hello").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("--cache")
+        .arg("cache")
+        .arg("a.txt");
+    cmd.assert().success();
+
+    assert!(cache_stats(&temp_dir).contains("Entries: 0"));
+}
+
+#[test]
+fn test_cache_env_var_and_verify() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    write_old_file(&temp_dir.path().join("a.txt"), "hello");
+    fs::write(
+        temp_dir.path().join("SHA256SUMS"),
+        format!("{}  a.txt\n", HELLO_SHA256),
+    )
+    .expect("Failed to write manifest");
+
+    for _ in 0..2 {
+        let mut cmd = get_cmd();
+        cmd.current_dir(temp_dir.path())
+            .env("HASHY_CACHE", "cache")
+            .arg("verify")
+            .arg("SHA256SUMS");
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("a.txt: OK"));
+    }
+
+    assert!(cache_stats(&temp_dir).contains("sha256: 1"));
+}
+
+#[test]
+fn test_cache_prune_and_clear() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    write_old_file(&temp_dir.path().join("a.txt"), "hello");
+    write_old_file(&temp_dir.path().join("b.txt"), "world");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("--cache")
+        .arg("cache")
+        .arg("a.txt")
+        .arg("b.txt");
+    cmd.assert().success();
+
+    fs::remove_file(temp_dir.path().join("a.txt")).expect("Failed to remove file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("cache")
+        .arg("prune")
+        .arg("--cache")
+        .arg("cache");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 1 stale cache entries"));
+    assert!(cache_stats(&temp_dir).contains("Entries: 1"));
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("cache")
+        .arg("clear")
+        .arg("--cache")
+        .arg("cache");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 1 cache entries"));
+    assert!(cache_stats(&temp_dir).contains("Entries: 0"));
+}
+
+#[test]
+fn test_cache_commands_do_not_create_a_cache() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::create_dir(temp_dir.path().join("empty")).expect("Failed to create dir");
+
+    for action in ["stats", "prune", "clear"] {
+        let mut cmd = get_cmd();
+        cmd.current_dir(temp_dir.path())
+            .args(["cache", action, "--cache", "missing"]);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("Cache directory does not exist"));
+        assert!(!temp_dir.path().join("missing").exists());
+
+        let mut cmd = get_cmd();
+        cmd.current_dir(temp_dir.path())
+            .args(["cache", action, "--cache", "empty"]);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("Not a digest cache directory"));
+        assert_eq!(
+            fs::read_dir(temp_dir.path().join("empty")).unwrap().count(),
+            0
+        );
+    }
+}
+
+#[test]
+fn test_cache_shared_by_parallel_processes() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let mut files = Vec::new();
+    for i in 0..50 {
+        let name = format!("file{:02}.txt", i);
+        write_old_file(&temp_dir.path().join(&name), &format!("content {}", i));
+        files.push(name);
+    }
+
+    let children: Vec<_> = (0..4)
+        .map(|_| {
+            let mut cmd = get_cmd();
+            cmd.current_dir(temp_dir.path())
+                .arg("hash")
+                .arg("--cache")
+                .arg("cache")
+                .args(&files)
+                .stdout(std::process::Stdio::piped())
+                .spawn()
+                .expect("Failed to spawn command")
+        })
+        .collect();
+
+    let outputs: Vec<_> = children
+        .into_iter()
+        .map(|child| {
+            child
+                .wait_with_output()
+                .expect("Failed to wait for command")
+        })
+        .collect();
+
+    for output in &outputs {
+        assert!(output.status.success());
+        assert_eq!(output.stdout, outputs[0].stdout);
+    }
+    assert!(cache_stats(&temp_dir).contains("Entries: 50"));
+}
@@ -0,0 +1,213 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::get_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+/// Create hello.txt plus a SHA256SUMS manifest with `extra` appended
+fn create_manifest(extra: &str) -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("hello.txt"), "hello").expect("Failed to write test file");
+    fs::write(
+        temp_dir.path().join("SHA256SUMS"),
+        format!("{}  hello.txt\n{}", HELLO_SHA256, extra),
+    )
+    .expect("Failed to write manifest");
+    temp_dir
+}
+
+#[test]
+fn test_improperly_formatted_lines_are_skipped_with_warning() {
+    let temp_dir = create_manifest("not a checksum line\ngarbage\n");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("SHA256SUMS");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hello.txt: OK"))
+        .stderr(predicate::str::contains(
+            "hashy: WARNING: 2 lines are improperly formatted",
+        ));
+}
+
+#[test]
+fn test_warn_reports_line_numbers() {
+    let temp_dir = create_manifest("garbage\n");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--warn")
+        .arg("SHA256SUMS");
+    cmd.assert().success().stderr(predicate::str::contains(
+        "hashy: SHA256SUMS: 2: improperly formatted SHA256 checksum line",
+    ));
+}
+
+#[test]
+fn test_strict_fails_on_improperly_formatted_lines() {
+    let temp_dir = create_manifest("garbage\n");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--strict")
+        .arg("SHA256SUMS");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("hello.txt: OK"))
+        .stdout(predicate::str::contains("All files verified successfully.").not());
+}
+
+#[test]
+fn test_ignore_missing() {
+    let temp_dir = create_manifest(&format!("{}  missing.txt\n", HELLO_SHA256));
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--ignore-missing")
+        .arg("SHA256SUMS");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("hello.txt: OK\n"));
+
+    // Without the flag, the missing file is a read failure
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--continue-on-error")
+        .arg("SHA256SUMS");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "hashy: WARNING: 1 listed file could not be read",
+    ));
+}
+
+#[test]
+fn test_ignore_missing_with_nothing_verified() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(
+        temp_dir.path().join("SHA256SUMS"),
+        format!("{}  missing.txt\n", HELLO_SHA256),
+    )
+    .expect("Failed to write manifest");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--ignore-missing")
+        .arg("SHA256SUMS");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("SHA256SUMS: no file was verified"));
+}
+
+#[test]
+fn test_status_prints_nothing() {
+    let temp_dir = create_manifest("");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--status")
+        .arg("SHA256SUMS");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::is_empty());
+
+    fs::write(temp_dir.path().join("hello.txt"), "changed").expect("Failed to write test file");
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--status")
+        .arg("SHA256SUMS");
+    cmd.assert()
+        .code(1)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn test_mismatch_warning_and_no_valid_lines() {
+    let temp_dir = create_manifest("");
+    fs::write(temp_dir.path().join("hello.txt"), "changed").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("SHA256SUMS");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "hashy: WARNING: 1 computed checksum did NOT match",
+    ));
+
+    fs::write(temp_dir.path().join("empty.sums"), "garbage\n").expect("Failed to write manifest");
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("empty.sums");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "empty.sums: no properly formatted checksum lines found",
+    ));
+}
+
+#[test]
+fn test_mismatch_output_matches_coreutils() {
+    let temp_dir = create_manifest("");
+    fs::write(temp_dir.path().join("hello.txt"), "changed").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("SHA256SUMS");
+    cmd.assert()
+        .code(1)
+        .stdout(predicate::str::diff("hello.txt: FAILED\n"))
+        .stderr(predicate::str::diff(
+            "hashy: WARNING: 1 computed checksum did NOT match\n",
+        ));
+}
+
+#[test]
+fn test_unreadable_output_matches_coreutils() {
+    let temp_dir = create_manifest(&format!("{}  missing.txt\n", HELLO_SHA256));
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--continue-on-error")
+        .arg("SHA256SUMS");
+    cmd.assert()
+        .code(1)
+        .stdout(predicate::str::diff(
+            "hello.txt: OK\nmissing.txt: FAILED open or read\n",
+        ))
+        .stderr(predicate::str::diff(
+            "hashy: missing.txt: No such file or directory\n\
+             hashy: WARNING: 1 listed file could not be read\n",
+        ));
+}
+
+#[test]
+fn test_uppercase_digests_match() {
+    let temp_dir = create_manifest("");
+    fs::write(
+        temp_dir.path().join("SHA256SUMS"),
+        format!("{}  hello.txt\n", HELLO_SHA256.to_uppercase()),
+    )
+    .expect("Failed to write manifest");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("SHA256SUMS");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hello.txt: OK"));
+}
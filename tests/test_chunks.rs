@@ -0,0 +1,172 @@
+mod common;
+
+use common::get_cmd;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Deterministic pseudo-random bytes (xorshift64)
+fn random_bytes(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+fn chunks_json(dir: &Path, args: &[&str]) -> serde_json::Value {
+    let output = get_cmd()
+        .current_dir(dir)
+        .arg("chunks")
+        .arg("--json")
+        .args(args)
+        .output()
+        .expect("Failed to execute command");
+    assert!(
+        output.status.success(),
+        "chunks failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("Output is not valid JSON")
+}
+
+#[test]
+fn test_chunks_cover_file() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let data = random_bytes(300_000, 1);
+    fs::write(temp_dir.path().join("data.bin"), &data).expect("Failed to write test file");
+
+    let json = chunks_json(
+        temp_dir.path(),
+        &[
+            "--algo",
+            "sha256",
+            "--min-size",
+            "2048",
+            "--avg-size",
+            "8192",
+            "--max-size",
+            "32768",
+            "data.bin",
+        ],
+    );
+    assert_eq!(json["algo"], "sha256");
+    assert_eq!(json["avg_size"], 8192);
+
+    let file = &json["files"][0];
+    assert_eq!(file["path"], "data.bin");
+    assert_eq!(file["size"], 300_000);
+
+    // Chunks are contiguous, within bounds, and hashed with the algorithm
+    let chunks = file["chunks"].as_array().unwrap();
+    assert!(chunks.len() > 10);
+    let mut offset = 0;
+    for (i, chunk) in chunks.iter().enumerate() {
+        let length = chunk["length"].as_u64().unwrap() as usize;
+        assert_eq!(chunk["offset"].as_u64().unwrap() as usize, offset);
+        assert!(length <= 32768);
+        if i + 1 < chunks.len() {
+            assert!(length >= 2048);
+        }
+        assert_eq!(
+            chunk["digest"],
+            hex::encode(Sha256::digest(&data[offset..offset + length]))
+        );
+        offset += length;
+    }
+    assert_eq!(offset, data.len());
+}
+
+#[test]
+fn test_chunks_dedup_summary() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let data = random_bytes(500_000, 2);
+    fs::write(temp_dir.path().join("a.bin"), &data).expect("Failed to write test file");
+    fs::write(temp_dir.path().join("copy.bin"), &data).expect("Failed to write test file");
+
+    // Inserting bytes only changes the chunks around the insertion
+    let mut shifted = data[..100_000].to_vec();
+    shifted.extend_from_slice(b"inserted bytes");
+    shifted.extend_from_slice(&data[100_000..]);
+    fs::write(temp_dir.path().join("shifted.bin"), &shifted).expect("Failed to write test file");
+
+    let json = chunks_json(temp_dir.path(), &["a.bin", "copy.bin"]);
+    let summary = &json["summary"];
+    assert_eq!(summary["files"], 2);
+    assert_eq!(summary["total_bytes"], 1_000_000);
+    assert_eq!(summary["unique_bytes"], 500_000);
+    assert_eq!(
+        summary["chunks"],
+        summary["unique_chunks"].as_u64().unwrap() * 2
+    );
+    assert_eq!(summary["dedup_ratio"], 2.0);
+
+    let json = chunks_json(temp_dir.path(), &["a.bin", "shifted.bin"]);
+    let summary = &json["summary"];
+    let chunks = summary["chunks"].as_u64().unwrap();
+    let unique = summary["unique_chunks"].as_u64().unwrap();
+    assert!(
+        unique <= chunks / 2 + 3,
+        "{} unique of {} chunks",
+        unique,
+        chunks
+    );
+    assert!(summary["dedup_ratio"].as_f64().unwrap() > 1.5);
+}
+
+#[test]
+fn test_chunks_text_output() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::create_dir(temp_dir.path().join("dir")).expect("Failed to create directory");
+    let data = random_bytes(200_000, 3);
+    fs::write(temp_dir.path().join("dir/a.bin"), &data).expect("Failed to write test file");
+    fs::write(temp_dir.path().join("dir/b.bin"), &data).expect("Failed to write test file");
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .arg("chunks")
+        .arg("dir")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("dir/a.bin: "));
+    assert!(stdout.contains("dir/b.bin: "));
+    assert!(stdout.contains("400000 bytes (200000 unique), dedup ratio 2.00"));
+}
+
+#[test]
+fn test_chunks_invalid_sizes() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("a.bin"), "data").expect("Failed to write test file");
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["chunks", "--min-size", "32", "a.bin"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("--min-size must be between 64 and 1048576 bytes, got 32"));
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args([
+            "chunks",
+            "--avg-size",
+            "8192",
+            "--max-size",
+            "4096",
+            "a.bin",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("min <= avg <= max"));
+}
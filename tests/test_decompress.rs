@@ -0,0 +1,233 @@
+mod common;
+
+use common::get_cmd;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+use tempfile::TempDir;
+
+const CONTENTS: &[u8] = b"disk image contents\n";
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Write CONTENTS compressed with every supported format
+fn write_compressed(dir: &Path) {
+    fs::write(dir.join("disk.img.gz"), gzip(CONTENTS)).unwrap();
+    fs::write(
+        dir.join("disk.img.zst"),
+        zstd::encode_all(CONTENTS, 3).unwrap(),
+    )
+    .unwrap();
+
+    let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+    xz.write_all(CONTENTS).unwrap();
+    fs::write(dir.join("disk.img.xz"), xz.finish().unwrap()).unwrap();
+
+    let mut bz2 = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+    bz2.write_all(CONTENTS).unwrap();
+    fs::write(dir.join("disk.img.bz2"), bz2.finish().unwrap()).unwrap();
+}
+
+#[test]
+fn test_decompress_files_and_batch() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    write_compressed(temp_dir.path());
+    fs::write(temp_dir.path().join("plain.img"), CONTENTS).unwrap();
+
+    for (file, format) in [
+        ("disk.img.gz", "gzip"),
+        ("disk.img.zst", "zstd"),
+        ("disk.img.xz", "xz"),
+        ("disk.img.bz2", "bzip2"),
+    ] {
+        for mode in ["auto", format] {
+            let output = get_cmd()
+                .current_dir(temp_dir.path())
+                .args(["hash", "--file", file, "--decompress", mode, "--json"])
+                .output()
+                .expect("Failed to execute command");
+            assert!(output.status.success(), "{} with {}", file, mode);
+            let json: serde_json::Value =
+                serde_json::from_slice(&output.stdout).expect("Output is not valid JSON");
+            assert_eq!(json["digest"], sha256_hex(CONTENTS), "{}", file);
+            assert_eq!(json["bytes"], CONTENTS.len());
+        }
+    }
+
+    // Batch mode mixes compressed and uncompressed files
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args([
+            "hash",
+            "disk.img.gz",
+            "disk.img.zst",
+            "plain.img",
+            "--decompress",
+            "auto",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches(&sha256_hex(CONTENTS)).count(), 3);
+}
+
+#[test]
+fn test_decompress_stdin() {
+    let mut child = get_cmd()
+        .args(["hash", "--decompress", "auto", "--json"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn command");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&gzip(CONTENTS))
+        .unwrap();
+    let output = child
+        .wait_with_output()
+        .expect("Failed to wait for command");
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output is not valid JSON");
+    assert_eq!(json["source"], "stdin");
+    assert_eq!(json["digest"], sha256_hex(CONTENTS));
+}
+
+#[test]
+fn test_verify_decompress_finds_compressed_copy() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("disk.img.gz"), gzip(CONTENTS)).unwrap();
+    fs::write(
+        temp_dir.path().join("SHA256SUMS"),
+        format!("{}  disk.img\n", sha256_hex(CONTENTS)),
+    )
+    .unwrap();
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["verify", "SHA256SUMS"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["verify", "SHA256SUMS", "--decompress", "auto"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(
+        output.status.success(),
+        "verify failed: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("disk.img: OK"));
+}
+
+#[test]
+fn test_decompress_format_mismatch() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("plain.img"), CONTENTS).unwrap();
+
+    // Uncompressed input is hashed as it is in auto mode
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["hash", "--file", "plain.img", "--decompress", "auto"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&sha256_hex(CONTENTS)));
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["hash", "--file", "plain.img", "--decompress", "gzip"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Input is not gzip-compressed"));
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["hash", "--file", "plain.img", "--decompress", "rar"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unsupported compression: rar"));
+}
+
+#[test]
+fn test_decompress_batch_reports_decompressed_size() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    write_compressed(temp_dir.path());
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args([
+            "hash",
+            "disk.img.gz",
+            "disk.img.xz",
+            "--decompress",
+            "auto",
+            "--json",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output is not valid JSON");
+    let results = json["results"]
+        .as_array()
+        .expect("results should be an array");
+    assert_eq!(results.len(), 2);
+    for result in results {
+        assert_eq!(result["bytes"], CONTENTS.len(), "{}", result["file_path"]);
+    }
+}
+
+#[test]
+fn test_decompress_does_not_use_cache() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("disk.img.gz"), gzip(CONTENTS)).unwrap();
+    fs::write(
+        temp_dir.path().join("SHA256SUMS"),
+        format!("{}  disk.img\n", sha256_hex(CONTENTS)),
+    )
+    .unwrap();
+
+    for args in [vec!["hash", "disk.img.gz"], vec!["verify", "SHA256SUMS"]] {
+        let output = get_cmd()
+            .current_dir(temp_dir.path())
+            .args(&args)
+            .args(["--decompress", "auto", "--cache", "cache"])
+            .output()
+            .expect("Failed to execute command");
+        assert!(!output.status.success(), "{:?} should fail", args);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+
+        // An exported cache directory is ignored instead
+        let output = get_cmd()
+            .current_dir(temp_dir.path())
+            .env("HASHY_CACHE", "cache")
+            .args(&args)
+            .args(["--decompress", "auto"])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success(), "{:?} failed", args);
+    }
+    // The cache directory is created when a cache is opened
+    assert!(!temp_dir.path().join("cache").exists());
+}
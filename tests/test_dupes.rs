@@ -0,0 +1,186 @@
+mod common;
+
+use common::get_cmd;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Deterministic, non-repeating test data
+fn pattern(len: usize, seed: u8) -> Vec<u8> {
+    (0..len)
+        .map(|i| (i as u32).wrapping_mul(2654435761).to_le_bytes()[1] ^ seed)
+        .collect()
+}
+
+/// Create a tree with one group of large duplicates, one group of small
+/// duplicates and files that only match on size or on their first and last
+/// blocks
+fn create_tree(dir: &Path) {
+    let big = pattern(100_000, 0);
+    fs::create_dir(dir.join("sub")).expect("Failed to create directory");
+    fs::write(dir.join("big1.bin"), &big).expect("Failed to write test file");
+    fs::write(dir.join("sub/big2.bin"), &big).expect("Failed to write test file");
+
+    // Same size and same first/last block, different middle
+    let mut middle = big.clone();
+    middle[50_000] ^= 0xff;
+    fs::write(dir.join("middle.bin"), &middle).expect("Failed to write test file");
+
+    // Same size, different contents
+    fs::write(dir.join("other.bin"), pattern(100_000, 7)).expect("Failed to write test file");
+
+    fs::write(dir.join("small1.txt"), "hello\n").expect("Failed to write test file");
+    fs::write(dir.join("small2.txt"), "hello\n").expect("Failed to write test file");
+    fs::write(dir.join("empty1"), "").expect("Failed to write test file");
+    fs::write(dir.join("empty2"), "").expect("Failed to write test file");
+}
+
+#[test]
+fn test_dupes_text_output() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    create_tree(temp_dir.path());
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .arg("dupes")
+        .arg(".")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let groups: Vec<&str> = stdout.split("\n\n").collect();
+
+    // Largest savings first, empty files skipped by default
+    assert_eq!(groups.len(), 3, "unexpected output: {}", stdout);
+    assert!(groups[0].contains("(100000 bytes each)\n  ./big1.bin\n  ./sub/big2.bin"));
+    assert!(groups[1].ends_with("(6 bytes each)\n  ./small1.txt\n  ./small2.txt"));
+    assert_eq!(
+        groups[2].trim_end(),
+        "Summary: 2 groups, 2 duplicate files, 100006 bytes reclaimable"
+    );
+    assert!(!stdout.contains("middle.bin"));
+    assert!(!stdout.contains("other.bin"));
+    assert!(!stdout.contains("empty"));
+}
+
+#[test]
+fn test_dupes_json_output() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    create_tree(temp_dir.path());
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .arg("dupes")
+        .arg("--json")
+        .arg("--min-size")
+        .arg("0")
+        .arg("--algo")
+        .arg("sha256")
+        .arg(".")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output is not valid JSON");
+
+    assert_eq!(json["algo"], "sha256");
+    assert_eq!(json["summary"]["groups"], 3);
+    assert_eq!(json["summary"]["duplicates"], 3);
+    assert_eq!(json["summary"]["reclaimable_bytes"], 100_006);
+
+    let groups = json["groups"].as_array().unwrap();
+    assert_eq!(groups[1]["size"], 6);
+    assert_eq!(
+        groups[1]["digest"],
+        "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"
+    );
+    assert_eq!(
+        groups[2]["files"],
+        serde_json::json!(["./empty1", "./empty2"])
+    );
+}
+
+#[test]
+fn test_dupes_delete_script() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    create_tree(temp_dir.path());
+    fs::write(temp_dir.path().join("small3's.txt"), "hello\n").expect("Failed to write test file");
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .arg("dupes")
+        .arg("--script")
+        .arg("delete")
+        .arg(".")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("#!/bin/sh\n"));
+    assert!(stdout.contains("rm -f -- './sub/big2.bin'\n"));
+    assert!(stdout.contains("rm -f -- './small2.txt'\n"));
+    assert!(stdout.contains("rm -f -- './small3'\\''s.txt'\n"));
+    // The first file of each group is kept
+    assert!(!stdout.contains("./big1.bin"));
+    assert!(!stdout.contains("./small1.txt"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dupes_hardlink_script_and_existing_links() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    create_tree(temp_dir.path());
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .arg("dupes")
+        .arg("--script")
+        .arg("hardlink")
+        .arg(".")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let script = temp_dir.path().join("dedupe.sh");
+    fs::write(&script, &output.stdout).expect("Failed to write script");
+    let status = std::process::Command::new("sh")
+        .current_dir(temp_dir.path())
+        .arg(&script)
+        .status()
+        .expect("Failed to run script");
+    assert!(status.success());
+    fs::remove_file(&script).expect("Failed to remove script");
+
+    // Hard links to one file are not duplicates of each other
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .arg("dupes")
+        .arg(".")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "No duplicate files found\n"
+    );
+}
+
+#[test]
+fn test_dupes_invalid_script() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+
+    let output = get_cmd()
+        .arg("dupes")
+        .arg("--script")
+        .arg("symlink")
+        .arg(temp_dir.path())
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Unsupported script: symlink. Supported: hardlink, delete"));
+}
@@ -173,3 +173,30 @@ fn test_hash_file_quiet() {
     assert!(output.status.success());
     assert!(String::from_utf8_lossy(&output.stdout).trim().is_empty());
 }
+
+#[test]
+fn test_hash_file_sha3_and_shake() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let file_path = temp_dir.path().join("test.txt");
+    fs::write(&file_path, "abc").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("sha3-256")
+        .arg("--file")
+        .arg(file_path.as_os_str());
+    cmd.assert().success().stdout(predicate::str::contains(
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+    ));
+
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("shake256")
+        .arg("--file")
+        .arg(file_path.as_os_str());
+    cmd.assert().success().stdout(predicate::str::contains(
+        "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739d5a15bef186a5386c75744c0527e1faa9f8726e462a12a4feb06bd8801e751e4",
+    ));
+}
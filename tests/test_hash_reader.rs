@@ -0,0 +1,56 @@
+use hashy::algorithm::Algorithm;
+use hashy::hash::{hash_data, hash_reader};
+use std::io::Cursor;
+
+#[test]
+fn test_hash_reader_matches_hash_data_for_all_algorithms() {
+    // Larger than one 64 KiB chunk so the read loop runs more than once
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+
+    for name in [
+        "sha1",
+        "sha256",
+        "sha512",
+        "blake3",
+        "md5",
+        "sha3-224",
+        "sha3-256",
+        "sha3-384",
+        "sha3-512",
+        "keccak256",
+        "keccak512",
+        "shake128",
+        "shake256",
+    ] {
+        let algorithm: Algorithm = name.parse().unwrap();
+        let (digest, bytes_read) =
+            hash_reader(algorithm.hasher(), Cursor::new(&data)).expect("hash_reader failed");
+
+        assert_eq!(bytes_read, data.len() as u64, "{}", name);
+        assert_eq!(digest, hash_data(algorithm, &data), "{}", name);
+        assert_eq!(digest.len(), algorithm.output_size(), "{}", name);
+    }
+}
+
+#[test]
+fn test_hasher_reports_algorithm_and_size() {
+    let hasher = Algorithm::Shake256
+        .hasher_with_length(Some(100))
+        .expect("SHAKE256 accepts a custom length");
+    assert_eq!(hasher.algorithm(), Algorithm::Shake256);
+    assert_eq!(hasher.output_size(), 100);
+    assert_eq!(hasher.finalize().len(), 100);
+
+    assert!(Algorithm::Sha256.hasher_with_length(Some(16)).is_err());
+}
+
+#[test]
+fn test_hasher_incremental_updates() {
+    let mut hasher = Algorithm::Sha256.hasher();
+    hasher.update(b"hel");
+    hasher.update(b"lo");
+    assert_eq!(
+        hex::encode(hasher.finalize()),
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+}
@@ -0,0 +1,277 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::get_cmd;
+use predicates::prelude::*;
+use std::fs;
+use std::io::Write;
+use std::process::Stdio;
+use tempfile::TempDir;
+
+// RFC 4231 / RFC 2202 test case 2: key "Jefe"
+const RFC_DATA: &str = "what do ya want for nothing?";
+const HMAC_SHA256: &str = "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843";
+const HMAC_SHA512: &str = "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737";
+const HMAC_SHA1: &str = "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79";
+
+/// Write the RFC test key to a temp file and return (dir, key path)
+fn write_key(key: &str) -> (TempDir, std::path::PathBuf) {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let key_path = temp_dir.path().join("key.bin");
+    fs::write(&key_path, key).expect("Failed to write key file");
+    (temp_dir, key_path)
+}
+
+#[test]
+fn test_hmac_sha256_key_file() {
+    let (_dir, key_path) = write_key("Jefe");
+
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--key-file")
+        .arg(key_path.as_os_str())
+        .arg("--text")
+        .arg(RFC_DATA);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Algorithm: hmac-sha256"))
+        .stdout(predicate::str::contains(HMAC_SHA256));
+}
+
+#[test]
+fn test_hmac_sha512_and_sha1() {
+    let (_dir, key_path) = write_key("Jefe");
+
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--algo")
+        .arg("sha512")
+        .arg("--key-file")
+        .arg(key_path.as_os_str())
+        .arg("--text")
+        .arg(RFC_DATA);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(HMAC_SHA512));
+
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--algo")
+        .arg("sha1")
+        .arg("--allow-insecure")
+        .arg("--key-file")
+        .arg(key_path.as_os_str())
+        .arg("--text")
+        .arg(RFC_DATA);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(HMAC_SHA1));
+}
+
+#[test]
+fn test_hmac_key_env() {
+    let mut cmd = get_cmd();
+    cmd.env("HASHY_TEST_KEY", "Jefe")
+        .arg("hmac")
+        .arg("--key-env")
+        .arg("HASHY_TEST_KEY")
+        .arg("--json")
+        .arg("--text")
+        .arg(RFC_DATA);
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Failed to parse JSON output");
+    assert_eq!(json["algo"], "hmac-sha256");
+    assert_eq!(json["digest"], HMAC_SHA256);
+}
+
+#[test]
+fn test_hmac_key_hex_requires_opt_in() {
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--key-hex")
+        .arg("4a656665")
+        .arg("--text")
+        .arg(RFC_DATA);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--insecure-key-arg"));
+
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--key-hex")
+        .arg("4a656665")
+        .arg("--insecure-key-arg")
+        .arg("--format")
+        .arg("hex")
+        .arg("--text")
+        .arg(RFC_DATA);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(format!("{}\n", HMAC_SHA256)));
+}
+
+#[test]
+fn test_hmac_requires_key() {
+    let mut cmd = get_cmd();
+    cmd.arg("hmac").arg("--text").arg(RFC_DATA);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--key-file"));
+}
+
+#[test]
+fn test_hmac_blake3_keyed() {
+    // BLAKE3 reference test vector: keyed_hash of the empty input
+    let (_dir, key_path) = write_key("whats the Elvish word for friend");
+
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--algo")
+        .arg("blake3")
+        .arg("--key-file")
+        .arg(key_path.as_os_str())
+        .arg("--format")
+        .arg("hex")
+        .arg("--text")
+        .arg("");
+
+    cmd.assert().success().stdout(predicate::str::diff(
+        "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26\n",
+    ));
+}
+
+#[test]
+fn test_hmac_blake3_rejects_short_key() {
+    let (_dir, key_path) = write_key("Jefe");
+
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--algo")
+        .arg("blake3")
+        .arg("--key-file")
+        .arg(key_path.as_os_str())
+        .arg("--text")
+        .arg("abc");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("32-byte key"));
+}
+
+#[test]
+fn test_hmac_file_and_stdin() {
+    let (dir, key_path) = write_key("Jefe");
+    let data_path = dir.path().join("data.txt");
+    fs::write(&data_path, RFC_DATA).expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--key-file")
+        .arg(key_path.as_os_str())
+        .arg("--file")
+        .arg(data_path.as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(HMAC_SHA256));
+
+    let mut cmd = get_cmd();
+    let mut child = cmd
+        .arg("hmac")
+        .arg("--key-file")
+        .arg(key_path.as_os_str())
+        .arg("--format")
+        .arg("hex")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .as_mut()
+        .unwrap()
+        .write_all(RFC_DATA.as_bytes())
+        .unwrap();
+    drop(child.stdin.take());
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), HMAC_SHA256);
+}
+
+#[test]
+fn test_hmac_batch_mode() {
+    let (dir, key_path) = write_key("Jefe");
+    let file1 = dir.path().join("one.txt");
+    let file2 = dir.path().join("two.txt");
+    fs::write(&file1, RFC_DATA).expect("Failed to write test file");
+    fs::write(&file2, "other").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--key-file")
+        .arg(key_path.as_os_str())
+        .arg(file1.as_os_str())
+        .arg(file2.as_os_str());
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("one.txt") && lines[0].ends_with(HMAC_SHA256));
+    assert!(lines[1].contains("two.txt"));
+}
+
+#[test]
+fn test_hmac_verify_ok_and_failed() {
+    let (_dir, key_path) = write_key("Jefe");
+
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--key-file")
+        .arg(key_path.as_os_str())
+        .arg("--verify")
+        .arg(HMAC_SHA256.to_uppercase())
+        .arg("--text")
+        .arg(RFC_DATA);
+    cmd.assert().success().stdout(predicate::str::diff("OK\n"));
+
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--key-file")
+        .arg(key_path.as_os_str())
+        .arg("--verify")
+        .arg(HMAC_SHA1)
+        .arg("--text")
+        .arg(RFC_DATA);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::diff("FAILED\n"))
+        .stderr(predicate::str::contains("MAC verification failed"));
+}
+
+#[test]
+fn test_hmac_unsupported_algorithm() {
+    let (_dir, key_path) = write_key("Jefe");
+
+    let mut cmd = get_cmd();
+    cmd.arg("hmac")
+        .arg("--algo")
+        .arg("sha3-256")
+        .arg("--key-file")
+        .arg(key_path.as_os_str())
+        .arg("--text")
+        .arg("abc");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("not supported"));
+}
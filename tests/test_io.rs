@@ -0,0 +1,226 @@
+mod common;
+
+use common::get_cmd;
+use hashy::algorithm::Algorithm;
+use hashy::hash::{hash_data, hash_file, hash_file_mapped, Hasher};
+use hashy::io::{hash_file_io, hash_file_with_hashers, IoMode, MMAP_THRESHOLD, PARALLEL_THRESHOLD};
+use std::fs;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+const MODES: [IoMode; 4] = [IoMode::Auto, IoMode::Read, IoMode::Mmap, IoMode::Uring];
+
+fn data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn test_io_modes_agree() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+
+    // Empty, smaller than one read, around the mmap threshold, and a size
+    // that leaves a partial last block for io_uring
+    let threshold = MMAP_THRESHOLD as usize;
+    for len in [0, 1000, threshold - 1, threshold, 3 * 1024 * 1024 + 12_345] {
+        let contents = data(len);
+        let path = temp_dir.path().join(format!("{}.bin", len));
+        fs::write(&path, &contents).expect("Failed to write file");
+        let path = path.to_str().unwrap();
+
+        for mode in MODES {
+            if mode == IoMode::Uring && !cfg!(target_os = "linux") {
+                continue;
+            }
+            let (digests, bytes) =
+                hash_file_io(&[Algorithm::Sha256, Algorithm::Blake3], path, None, mode)
+                    .unwrap_or_else(|e| panic!("{:?} on {} bytes: {:#}", mode, len, e));
+            assert_eq!(bytes, len as u64);
+            assert_eq!(digests[0], hash_data(Algorithm::Sha256, &contents));
+            assert_eq!(digests[1], hash_data(Algorithm::Blake3, &contents));
+        }
+    }
+}
+
+#[test]
+fn test_library_default_reads() {
+    // Memory maps are opt-in: the CLI selects auto, library callers read
+    assert_eq!(IoMode::default(), IoMode::Read);
+}
+
+#[test]
+fn test_parallel_blake3_matches_sequential() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    // More than one 64 MiB parallel chunk, with a partial last one
+    let contents = data(PARALLEL_THRESHOLD as usize * 4 + 12_345);
+    let path = temp_dir.path().join("huge.img");
+    fs::write(&path, &contents).expect("Failed to write file");
+    let path = path.to_str().unwrap();
+
+    let mut expected = Algorithm::Blake3.hasher_with_length(Some(64)).unwrap();
+    expected.update(&contents);
+    let expected = expected.finalize();
+
+    // From the global pool, a batch-sized pool and a single worker
+    for threads in [None, Some(4), Some(1)] {
+        let hash = || hash_file_io(&[Algorithm::Blake3], path, Some(64), IoMode::Mmap).unwrap();
+        let (digests, _) = match threads {
+            Some(n) => rayon::ThreadPoolBuilder::new()
+                .num_threads(n)
+                .build()
+                .unwrap()
+                .install(hash),
+            None => hash(),
+        };
+        assert_eq!(digests[0], expected, "{:?} threads", threads);
+    }
+
+    // Every file of a batch, whatever its size, gets the sequential digest
+    let output = get_cmd()
+        .args(["hash", path, path, "--algo", "blake3"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let digest = hex::encode(hash_data(Algorithm::Blake3, &contents));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout)
+            .matches(&digest)
+            .count(),
+        2
+    );
+}
+
+/// Wraps a real hasher and counts the calls that take the parallel path
+struct RecordingHasher {
+    inner: Box<dyn Hasher>,
+    parallel_updates: Arc<AtomicUsize>,
+}
+
+impl Hasher for RecordingHasher {
+    fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    fn update_parallel(&mut self, data: &[u8]) {
+        self.parallel_updates.fetch_add(1, Ordering::Relaxed);
+        self.inner.update_parallel(data);
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.inner.finalize()
+    }
+
+    fn output_size(&self) -> usize {
+        self.inner.output_size()
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        self.inner.algorithm()
+    }
+}
+
+#[test]
+fn test_large_mapped_blake3_takes_parallel_path() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let contents = data(PARALLEL_THRESHOLD as usize);
+    let path = temp_dir.path().join("large.img");
+    fs::write(&path, &contents).expect("Failed to write file");
+    let path = path.to_str().unwrap();
+    let expected = hash_data(Algorithm::Blake3, &contents);
+
+    for (mode, parallel) in [
+        (IoMode::Auto, true),
+        (IoMode::Mmap, true),
+        (IoMode::Read, false),
+    ] {
+        let parallel_updates = Arc::new(AtomicUsize::new(0));
+        let hasher = RecordingHasher {
+            inner: Algorithm::Blake3.hasher(),
+            parallel_updates: Arc::clone(&parallel_updates),
+        };
+        let (digests, _) = hash_file_with_hashers(vec![Box::new(hasher)], path, mode).unwrap();
+        assert_eq!(digests[0], expected, "{:?}", mode);
+        assert_eq!(
+            parallel_updates.load(Ordering::Relaxed) > 0,
+            parallel,
+            "{:?}",
+            mode
+        );
+    }
+
+    // The library opt-in maps the file; hash_file reads it on one thread
+    assert_eq!(
+        hash_file_mapped(Algorithm::Blake3, path, None).unwrap(),
+        expected
+    );
+    assert_eq!(hash_file(Algorithm::Blake3, path).unwrap(), expected);
+}
+
+#[test]
+fn test_hash_io_flag() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let contents = data(2 * 1024 * 1024);
+    fs::write(temp_dir.path().join("disk.img"), &contents).expect("Failed to write file");
+    fs::write(temp_dir.path().join("small.txt"), b"small\n").expect("Failed to write file");
+    let expected = hex::encode(hash_data(Algorithm::Sha256, &contents));
+
+    for mode in ["auto", "read", "mmap"] {
+        let output = get_cmd()
+            .current_dir(temp_dir.path())
+            .args([
+                "hash", "--file", "disk.img", "--io", mode, "--format", "hex",
+            ])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success(), "--io {}", mode);
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), expected);
+    }
+
+    // Batch mode and verify read every file with the given mode
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args([
+            "hash",
+            "disk.img",
+            "small.txt",
+            "--io",
+            "mmap",
+            "--manifest",
+            "SHA256SUMS",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&expected));
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["verify", "SHA256SUMS", "--io", "read"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_io_flag_rejections() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("small.txt"), b"small\n").expect("Failed to write file");
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["hash", "--file", "small.txt", "--io", "dma"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unsupported I/O mode: dma"));
+
+    // STDIN is always read in chunks
+    let output = get_cmd()
+        .args(["hash", "--io", "mmap"])
+        .stdin(Stdio::null())
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--io requires file inputs"));
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::get_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_blake3_extended_length() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("blake3")
+        .arg("--length")
+        .arg("64")
+        .arg("--format")
+        .arg("hex")
+        .arg("--text")
+        .arg("abc");
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(output.status.success());
+
+    // The XOF output extends the default 32-byte digest
+    let digest = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    assert_eq!(digest.len(), 128);
+    assert!(digest.starts_with("6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"));
+}
+
+#[test]
+fn test_blake3_truncated_length() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("blake3")
+        .arg("--length")
+        .arg("8")
+        .arg("--format")
+        .arg("hex")
+        .arg("--text")
+        .arg("abc");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("6437b3ac38465133\n"));
+}
+
+#[test]
+fn test_shake128_custom_length() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("shake128")
+        .arg("--length")
+        .arg("16")
+        .arg("--format")
+        .arg("hex")
+        .arg("--text")
+        .arg("abc");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("5881092dd818bf5cf8a3ddb793fbcba7\n"));
+}
+
+#[test]
+fn test_length_rejected_for_fixed_output() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("sha256")
+        .arg("--length")
+        .arg("16")
+        .arg("--text")
+        .arg("abc");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("fixed output size"));
+}
+
+#[test]
+fn test_length_zero_rejected() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("blake3")
+        .arg("--length")
+        .arg("0")
+        .arg("--text")
+        .arg("abc");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("at least 1 byte"));
+}
+
+#[test]
+fn test_length_above_maximum_rejected() {
+    for length in ["65537", "18446744073709551615"] {
+        let mut cmd = get_cmd();
+        cmd.args([
+            "hash", "--algo", "shake256", "--length", length, "--text", "abc",
+        ]);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("at most 65536 bytes"));
+    }
+
+    let mut cmd = get_cmd();
+    cmd.args([
+        "hash", "--algo", "blake3", "--length", "65536", "--text", "abc",
+    ]);
+    cmd.assert().success();
+}
+
+#[test]
+fn test_length_reported_in_json() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("shake256")
+        .arg("--length")
+        .arg("20")
+        .arg("--json")
+        .arg("--text")
+        .arg("abc");
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Failed to parse JSON output");
+    assert_eq!(json["length"], 20);
+    assert_eq!(json["digest"].as_str().unwrap().len(), 40);
+}
+
+#[test]
+fn test_length_absent_from_default_json() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash").arg("--json").arg("--text").arg("abc");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"length\"").not());
+}
+
+#[test]
+fn test_verify_with_custom_length() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let file_path = temp_dir.path().join("data.bin");
+    fs::write(&file_path, "abc").expect("Failed to write test file");
+
+    let manifest_path = temp_dir.path().join("checksums.txt");
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("blake3")
+        .arg("--length")
+        .arg("48")
+        .arg("-r")
+        .arg("--manifest")
+        .arg(manifest_path.as_os_str())
+        .arg(file_path.as_os_str());
+    cmd.assert().success();
+
+    // Without --length the 96-character digests don't match blake3's default size
+    let mut cmd = get_cmd();
+    cmd.arg("verify")
+        .arg("--algo")
+        .arg("blake3")
+        .arg(manifest_path.as_os_str());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("data.bin: OK").not())
+        .stderr(predicate::str::contains(
+            "no properly formatted checksum lines found",
+        ));
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify")
+        .arg("--algo")
+        .arg("blake3")
+        .arg("--length")
+        .arg("48")
+        .arg(manifest_path.as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("data.bin: OK"));
+}
@@ -0,0 +1,253 @@
+mod common;
+
+use common::get_cmd;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Write `count` files into `dir/release` and build a tree next to them.
+/// Returns the printed root.
+fn build_tree(dir: &Path, count: usize) -> String {
+    fs::create_dir_all(dir.join("release")).expect("Failed to create directory");
+    for i in 0..count {
+        fs::write(
+            dir.join(format!("release/file{:02}.txt", i)),
+            format!("contents {}\n", i),
+        )
+        .expect("Failed to write test file");
+    }
+
+    let output = get_cmd()
+        .current_dir(dir)
+        .args(["merkle", "build", "-o", "release/TREE", "release"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(
+        output.status.success(),
+        "build failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Prove `file` and verify it against `root`; returns whether verify passed
+fn prove_and_verify(dir: &Path, file: &str, root: &str) -> bool {
+    let output = get_cmd()
+        .current_dir(dir)
+        .args([
+            "merkle",
+            "prove",
+            "--tree",
+            "release/TREE",
+            file,
+            "-o",
+            "proof.json",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    get_cmd()
+        .current_dir(dir)
+        .args([
+            "merkle",
+            "verify",
+            "--proof",
+            "proof.json",
+            "--root",
+            root,
+            file,
+        ])
+        .output()
+        .expect("Failed to execute command")
+        .status
+        .success()
+}
+
+fn sha256(data: &[u8]) -> Vec<u8> {
+    Sha256::digest(data).to_vec()
+}
+
+#[test]
+fn test_merkle_root_is_domain_separated() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = build_tree(temp_dir.path(), 3);
+
+    // leaf = H(0x00 || len(path) || path || digest), node = H(0x01 || left || right)
+    let leaf = |i: usize| {
+        let path = format!("file{:02}.txt", i);
+        let mut data = vec![0u8];
+        data.extend_from_slice(&(path.len() as u64).to_be_bytes());
+        data.extend_from_slice(path.as_bytes());
+        data.extend_from_slice(&sha256(format!("contents {}\n", i).as_bytes()));
+        sha256(&data)
+    };
+    let node = |left: &[u8], right: &[u8]| sha256(&[&[1u8][..], left, right].concat());
+
+    let expected = node(&node(&leaf(0), &leaf(1)), &leaf(2));
+    assert_eq!(root, hex::encode(expected));
+}
+
+#[test]
+fn test_merkle_proves_every_leaf() {
+    for count in 1..=7 {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let root = build_tree(temp_dir.path(), count);
+
+        for i in 0..count {
+            let file = format!("release/file{:02}.txt", i);
+            assert!(
+                prove_and_verify(temp_dir.path(), &file, &root),
+                "leaf {} of {} did not verify",
+                i,
+                count
+            );
+        }
+    }
+}
+
+#[test]
+fn test_merkle_verify_output() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = build_tree(temp_dir.path(), 5);
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args([
+            "merkle",
+            "prove",
+            "--tree",
+            "release/TREE",
+            "release/file03.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let proof: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Proof is not valid JSON");
+    assert_eq!(proof["algorithm"], "sha256");
+    assert_eq!(proof["path"], "file03.txt");
+    assert_eq!(proof["index"], 3);
+    assert_eq!(proof["leaf_count"], 5);
+    assert_eq!(proof["proof"].as_array().unwrap().len(), 3);
+    fs::write(temp_dir.path().join("proof.json"), &output.stdout).expect("Failed to write proof");
+
+    // The file can be checked from anywhere, e.g. after downloading it
+    fs::copy(
+        temp_dir.path().join("release/file03.txt"),
+        temp_dir.path().join("downloaded.txt"),
+    )
+    .expect("Failed to copy file");
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["merkle", "verify", "--proof", "proof.json", "--root", &root])
+        .arg("downloaded.txt")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "downloaded.txt: OK (file03.txt, leaf 4 of 5)\n"
+    );
+}
+
+#[test]
+fn test_merkle_verify_rejects_tampering() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = build_tree(temp_dir.path(), 4);
+    let dir = temp_dir.path();
+
+    assert!(prove_and_verify(dir, "release/file01.txt", &root));
+
+    // A different file with the same proof
+    let output = get_cmd()
+        .current_dir(dir)
+        .args(["merkle", "verify", "--proof", "proof.json", "--root", &root])
+        .arg("release/file02.txt")
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("release/file02.txt: FAILED (contents differ from file01.txt)"));
+
+    // Another root
+    let other_root = "00".repeat(32);
+    let output = get_cmd()
+        .current_dir(dir)
+        .args([
+            "merkle",
+            "verify",
+            "--proof",
+            "proof.json",
+            "--root",
+            &other_root,
+        ])
+        .arg("release/file01.txt")
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("FAILED (not included in root"));
+
+    // A forged proof that claims the matching digest for modified contents
+    fs::write(dir.join("release/file01.txt"), "forged\n").expect("Failed to write test file");
+    let mut proof: serde_json::Value =
+        serde_json::from_slice(&fs::read(dir.join("proof.json")).unwrap()).unwrap();
+    proof["digest"] = hex::encode(sha256(b"forged\n")).into();
+    fs::write(dir.join("proof.json"), proof.to_string()).expect("Failed to write proof");
+
+    let output = get_cmd()
+        .current_dir(dir)
+        .args(["merkle", "verify", "--proof", "proof.json", "--root", &root])
+        .arg("release/file01.txt")
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("FAILED (not included in root"));
+}
+
+#[test]
+fn test_merkle_prove_errors() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    build_tree(temp_dir.path(), 2);
+    let dir = temp_dir.path();
+
+    let output = get_cmd()
+        .current_dir(dir)
+        .args([
+            "merkle",
+            "prove",
+            "--tree",
+            "release/TREE",
+            "release/missing.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("missing.txt is not a leaf of the Merkle tree"));
+
+    // Editing a leaf of the tree file is detected
+    let tree_path = dir.join("release/TREE");
+    let tree = fs::read_to_string(&tree_path).expect("Failed to read tree");
+    let first_digest = hex::encode(sha256(b"contents 0\n"));
+    fs::write(&tree_path, tree.replace(&first_digest, &"ab".repeat(32)))
+        .expect("Failed to write tree");
+
+    let output = get_cmd()
+        .current_dir(dir)
+        .args([
+            "merkle",
+            "prove",
+            "--tree",
+            "release/TREE",
+            "release/file01.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is corrupt"));
+}
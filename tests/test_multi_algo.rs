@@ -0,0 +1,170 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::get_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+const HELLO_SHA512: &str = "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca72323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043";
+const HELLO_BLAKE3: &str = "ea8f163db38682925e4491c5e58d4bb3506ef8c14eb78a86e908c5624a67200f";
+const HELLO_MD5: &str = "5d41402abc4b2a76b9719d911017c592";
+
+#[test]
+fn test_multi_algo_comma_separated_text() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("sha256,sha512,blake3")
+        .arg("--text")
+        .arg("hello");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Algorithms: sha256, sha512, blake3",
+        ))
+        .stdout(predicate::str::contains(format!(
+            "sha256: {}",
+            HELLO_SHA256
+        )))
+        .stdout(predicate::str::contains(format!(
+            "sha512: {}",
+            HELLO_SHA512
+        )))
+        .stdout(predicate::str::contains(format!(
+            "blake3: {}",
+            HELLO_BLAKE3
+        )));
+}
+
+#[test]
+fn test_multi_algo_repeated_flag_and_insecure_gating() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("-a")
+        .arg("md5")
+        .arg("-a")
+        .arg("sha256")
+        .arg("--text")
+        .arg("hello");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--allow-insecure"));
+
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("-a")
+        .arg("md5")
+        .arg("-a")
+        .arg("sha256")
+        .arg("--allow-insecure")
+        .arg("--format")
+        .arg("hex")
+        .arg("--text")
+        .arg("hello");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "md5: {}\nsha256: {}\n",
+        HELLO_MD5, HELLO_SHA256
+    )));
+}
+
+#[test]
+fn test_multi_algo_json_digest_map() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("sha256,blake3")
+        .arg("--json")
+        .arg("--text")
+        .arg("hello");
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Failed to parse JSON output");
+    assert_eq!(json["algo"], "sha256,blake3");
+    assert_eq!(json["digest"], HELLO_SHA256);
+    assert_eq!(json["digests"]["sha256"], HELLO_SHA256);
+    assert_eq!(json["digests"]["blake3"], HELLO_BLAKE3);
+}
+
+#[test]
+fn test_single_algo_json_has_no_digest_map() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash").arg("--json").arg("--text").arg("hello");
+
+    let output = cmd.output().expect("Failed to execute command");
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Failed to parse JSON output");
+    assert!(json.get("digests").is_none());
+}
+
+#[test]
+fn test_multi_algo_batch_tagged_lines() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let file = temp_dir.path().join("hello.txt");
+    fs::write(&file, "hello").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("--algo")
+        .arg("sha256,sha512")
+        .arg("hello.txt");
+
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "SHA256 (hello.txt) = {}\nSHA512 (hello.txt) = {}\n",
+        HELLO_SHA256, HELLO_SHA512
+    )));
+}
+
+#[test]
+fn test_multi_algo_manifest_per_algorithm() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("hello.txt"), "hello").expect("Failed to write test file");
+    let manifest = temp_dir.path().join("{algo}.sums");
+
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("sha256,sha512")
+        .arg("-r")
+        .arg("--manifest")
+        .arg(manifest.as_os_str())
+        .arg(temp_dir.path().as_os_str());
+    cmd.assert().success();
+
+    let sha256 = fs::read_to_string(temp_dir.path().join("sha256.sums"))
+        .expect("Failed to read sha256 manifest");
+    let sha512 = fs::read_to_string(temp_dir.path().join("sha512.sums"))
+        .expect("Failed to read sha512 manifest");
+    assert_eq!(sha256, format!("{}  hello.txt\n", HELLO_SHA256));
+    assert_eq!(sha512, format!("{}  hello.txt\n", HELLO_SHA512));
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify")
+        .arg("--algo")
+        .arg("sha512")
+        .arg(temp_dir.path().join("sha512.sums").as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hello.txt: OK"));
+}
+
+#[test]
+fn test_multi_algo_rejects_raw_format() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--algo")
+        .arg("sha256,sha512")
+        .arg("--format")
+        .arg("raw")
+        .arg("--text")
+        .arg("hello");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("multiple algorithms"));
+}
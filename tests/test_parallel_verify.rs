@@ -0,0 +1,99 @@
+mod common;
+
+use common::get_cmd;
+use sha2::{Digest, Sha256};
+use std::fs;
+use tempfile::TempDir;
+
+/// Create `count` small files plus a SHA256SUMS manifest listing them in order
+fn create_tree(count: usize) -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let mut manifest = String::new();
+    for i in 0..count {
+        let name = format!("file{:03}.txt", i);
+        let content = format!("content {}", i);
+        fs::write(temp_dir.path().join(&name), &content).expect("Failed to write test file");
+        manifest.push_str(&format!(
+            "{}  {}\n",
+            hex::encode(Sha256::digest(content.as_bytes())),
+            name
+        ));
+    }
+    fs::write(temp_dir.path().join("SHA256SUMS"), manifest).expect("Failed to write manifest");
+    temp_dir
+}
+
+#[test]
+fn test_parallel_verify_preserves_manifest_order() {
+    let temp_dir = create_tree(200);
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--jobs")
+        .arg("8")
+        .arg("SHA256SUMS");
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 200);
+    for (i, line) in lines.iter().enumerate() {
+        assert_eq!(*line, format!("file{:03}.txt: OK", i));
+    }
+}
+
+#[test]
+fn test_parallel_verify_stops_at_first_failure_in_order() {
+    let temp_dir = create_tree(200);
+    fs::write(temp_dir.path().join("file050.txt"), "tampered").expect("Failed to write file");
+    fs::write(temp_dir.path().join("file120.txt"), "tampered").expect("Failed to write file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("-j")
+        .arg("4")
+        .arg("SHA256SUMS");
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    // 50 OK lines, then the FAILED line
+    assert_eq!(lines.len(), 51, "Unexpected output: {}", stdout);
+    assert_eq!(lines[49], "file049.txt: OK");
+    assert_eq!(lines[50], "file050.txt: FAILED");
+    assert!(!stdout.contains("file120.txt"));
+}
+
+#[test]
+fn test_parallel_verify_matches_single_job_output() {
+    let temp_dir = create_tree(100);
+    fs::remove_file(temp_dir.path().join("file010.txt")).expect("Failed to remove file");
+    fs::write(temp_dir.path().join("file090.txt"), "tampered").expect("Failed to write file");
+
+    let run = |jobs: &str| {
+        let mut cmd = get_cmd();
+        cmd.current_dir(temp_dir.path())
+            .arg("verify")
+            .arg("--continue-on-error")
+            .arg("--jobs")
+            .arg(jobs)
+            .arg("SHA256SUMS");
+        cmd.output().expect("Failed to execute command")
+    };
+
+    let single = run("1");
+    let parallel = run("16");
+    assert!(!single.status.success());
+    assert_eq!(single.stdout, parallel.stdout);
+    assert_eq!(single.stderr, parallel.stderr);
+    let stdout = String::from_utf8_lossy(&parallel.stdout);
+    assert_eq!(stdout.matches(": OK\n").count(), 98);
+    assert!(stdout.contains("file010.txt: FAILED open or read\n"));
+    assert!(stdout.contains("file090.txt: FAILED\n"));
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use common::get_cmd;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write_pieces(dir: &Path, file: &str, piece_size: &str) -> serde_json::Value {
+    let output = get_cmd()
+        .current_dir(dir)
+        .args(["hash", "--file", file, "--piece-size", piece_size, "--json"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(
+        output.status.success(),
+        "hash failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    fs::write(dir.join("pieces.json"), &output.stdout).expect("Failed to write piece list");
+    serde_json::from_slice(&output.stdout).expect("Output is not valid JSON")
+}
+
+#[test]
+fn test_piece_digests() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let data: Vec<u8> = (0..2500u32).map(|i| (i % 251) as u8).collect();
+    fs::write(temp_dir.path().join("disk.img"), &data).expect("Failed to write file");
+
+    let json = write_pieces(temp_dir.path(), "disk.img", "1000");
+    assert_eq!(json["digest"], hex::encode(Sha256::digest(&data)));
+    assert_eq!(json["bytes"], 2500);
+    assert_eq!(json["piece_size"], 1000);
+
+    let pieces = json["pieces"].as_array().unwrap();
+    assert_eq!(pieces.len(), 3);
+    for (piece, block) in pieces.iter().zip(data.chunks(1000)) {
+        assert_eq!(piece["length"], block.len());
+        assert_eq!(piece["digest"], hex::encode(Sha256::digest(block)));
+    }
+    assert_eq!(pieces[2]["offset"], 2000);
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["hash", "--file", "disk.img", "--piece-size", "1000"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Piece size: 1000 bytes (3 pieces)"));
+    assert!(stdout.contains(&format!(
+        "2000-2499: {}",
+        hex::encode(Sha256::digest(&data[2000..]))
+    )));
+}
+
+#[test]
+fn test_verify_pieces_reports_corrupted_ranges() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let path = temp_dir.path().join("disk.img");
+    let mut data = vec![0u8; 5000];
+    fs::write(&path, &data).expect("Failed to write file");
+    write_pieces(temp_dir.path(), "disk.img", "1000");
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["verify", "--pieces", "pieces.json"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("disk.img: OK (5 pieces)"));
+
+    // Corrupt pieces 2 and 3, and piece 5
+    data[1500] = 1;
+    data[2999] = 1;
+    data[4000] = 1;
+    fs::write(&path, &data).expect("Failed to write file");
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["verify", "--pieces", "pieces.json"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("disk.img: bytes 1000-2999 FAILED (pieces 2-3)"));
+    assert!(stdout.contains("disk.img: bytes 4000-4999 FAILED (piece 5)"));
+    assert!(!stdout.contains("bytes 0-"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("3 of 5 pieces did NOT match"));
+}
+
+#[test]
+fn test_verify_pieces_other_file_length() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let data = vec![7u8; 3000];
+    fs::write(temp_dir.path().join("disk.img"), &data).expect("Failed to write file");
+    write_pieces(temp_dir.path(), "disk.img", "1000");
+
+    fs::write(temp_dir.path().join("short.img"), &data[..1000]).expect("Failed to write file");
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["verify", "--pieces", "pieces.json", "short.img"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(
+        "short.img: bytes 1000-2999 MISSING (pieces 2-3, file is shorter than recorded)"
+    ));
+
+    fs::write(temp_dir.path().join("long.img"), vec![7u8; 3500]).expect("Failed to write file");
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["verify", "--pieces", "pieces.json", "long.img"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("long.img: bytes 3000-3499 UNEXPECTED (piece 4, file is longer than recorded)"));
+}
+
+#[test]
+fn test_piece_size_rejections() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("disk.img"), b"data").expect("Failed to write file");
+
+    for args in [
+        vec!["--piece-size", "0"],
+        vec!["--piece-size", "2", "--algo", "sha256,blake3"],
+    ] {
+        let output = get_cmd()
+            .current_dir(temp_dir.path())
+            .args(["hash", "--file", "disk.img"])
+            .args(&args)
+            .output()
+            .expect("Failed to execute command");
+        assert!(!output.status.success(), "{:?} should fail", args);
+    }
+}
+
+#[test]
+fn test_verify_pieces_rejects_unsupported_flags() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("disk.img"), b"data").expect("Failed to write file");
+    write_pieces(temp_dir.path(), "disk.img", "2");
+
+    for args in [
+        vec!["--continue-on-error"],
+        vec!["--cache", "cache"],
+        vec!["--io", "read"],
+        vec!["--decompress", "auto"],
+    ] {
+        let output = get_cmd()
+            .current_dir(temp_dir.path())
+            .args(["verify", "--pieces", "pieces.json"])
+            .args(&args)
+            .output()
+            .expect("Failed to execute command");
+        assert!(!output.status.success(), "{:?} should fail", args);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+    }
+
+    // An exported cache directory is not an explicit --cache
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .env("HASHY_CACHE", "cache")
+        .args(["verify", "--pieces", "pieces.json"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(
+        output.status.success(),
+        "verify failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
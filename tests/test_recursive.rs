@@ -0,0 +1,316 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::get_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+/// Create a small release tree:
+///   a.txt, sub/b.txt, sub/deep/c.log, .hidden/d.txt, .env
+fn create_tree() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("sub/deep")).expect("Failed to create dirs");
+    fs::create_dir_all(root.join(".hidden")).expect("Failed to create dirs");
+    fs::write(root.join("a.txt"), "hello").expect("Failed to write test file");
+    fs::write(root.join("sub/b.txt"), "world").expect("Failed to write test file");
+    fs::write(root.join("sub/deep/c.log"), "log").expect("Failed to write test file");
+    fs::write(root.join(".hidden/d.txt"), "hidden").expect("Failed to write test file");
+    fs::write(root.join(".env"), "secret").expect("Failed to write test file");
+    temp_dir
+}
+
+#[test]
+fn test_recursive_hash_sha256sum_style() {
+    let temp_dir = create_tree();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("--recursive")
+        .arg(".");
+
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "Unexpected output: {}", stdout);
+    assert_eq!(
+        lines[0],
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824  ./a.txt"
+    );
+    assert!(lines[1].ends_with("  ./sub/b.txt"));
+    assert!(lines[2].ends_with("  ./sub/deep/c.log"));
+}
+
+#[test]
+fn test_recursive_exclude_and_include() {
+    let temp_dir = create_tree();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("-r")
+        .arg("--exclude")
+        .arg("*.log")
+        .arg(".");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("a.txt"))
+        .stdout(predicate::str::contains("sub/b.txt"))
+        .stdout(predicate::str::contains("c.log").not());
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("-r")
+        .arg("--include")
+        .arg("*.log")
+        .arg(".");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("sub/deep/c.log"))
+        .stdout(predicate::str::contains("a.txt").not());
+}
+
+#[test]
+fn test_recursive_exclude_directory() {
+    let temp_dir = create_tree();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("-r")
+        .arg("--exclude")
+        .arg("sub")
+        .arg(".");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("a.txt"))
+        .stdout(predicate::str::contains("sub/").not());
+}
+
+#[test]
+fn test_recursive_max_depth() {
+    let temp_dir = create_tree();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("-r")
+        .arg("--max-depth")
+        .arg("2")
+        .arg(".");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("sub/b.txt"))
+        .stdout(predicate::str::contains("c.log").not());
+}
+
+#[test]
+fn test_recursive_hidden_files() {
+    let temp_dir = create_tree();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("-r")
+        .arg("--hidden")
+        .arg(".");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("./.env"))
+        .stdout(predicate::str::contains("./.hidden/d.txt"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_recursive_symlink_policy() {
+    let temp_dir = create_tree();
+    std::os::unix::fs::symlink("a.txt", temp_dir.path().join("link.txt"))
+        .expect("Failed to create symlink");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("-r")
+        .arg(".");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("link.txt").not());
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("-r")
+        .arg("--follow-symlinks")
+        .arg(".");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824  ./link.txt",
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_recursive_walk_error_fails_only_its_entry() {
+    let temp_dir = create_tree();
+    std::os::unix::fs::symlink("nowhere", temp_dir.path().join("sub/broken.txt"))
+        .expect("Failed to create symlink");
+
+    // Without --continue-on-error the walk error fails the run like an
+    // unreadable file
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .args(["hash", "-r", "--follow-symlinks", "sub"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("sub/broken.txt"));
+
+    // With it, the other files are still hashed in walk order
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path()).args([
+        "hash",
+        "-r",
+        "--follow-symlinks",
+        "--continue-on-error",
+        "sub",
+    ]);
+    cmd.assert().success().stdout(predicate::str::diff(
+        "486ea46224d1bb4fb680f34f7c9ad96a8f24ec88be73ea8e5a6c65260e9cb8a7  sub/b.txt\n\
+         sub/broken.txt  ERROR: No such file or directory (os error 2)\n\
+         836ff184e7b41b1e13cb5fd89fa1de98dbbab99e9d2918913ff43b86a5c7c213  sub/deep/c.log\n\
+         Summary: 2 succeeded, 1 failed\n",
+    ));
+}
+
+#[test]
+fn test_recursive_manifest_roundtrip_with_verify() {
+    let temp_dir = create_tree();
+    let manifest_path = temp_dir.path().join("SHA256SUMS");
+
+    // Run from outside the tree so paths must be rewritten relative to the manifest
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("-r")
+        .arg("--manifest")
+        .arg(manifest_path.as_os_str())
+        .arg(temp_dir.path().as_os_str());
+    cmd.assert().success();
+
+    let manifest = fs::read_to_string(&manifest_path).expect("Failed to read manifest");
+    assert!(manifest
+        .contains("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824  a.txt\n"));
+    assert!(manifest.contains("  sub/b.txt\n"));
+    assert!(manifest.contains("  sub/deep/c.log\n"));
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify").arg(manifest_path.as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("sub/deep/c.log: OK"));
+}
+
+#[test]
+fn test_recursive_manifest_outside_tree() {
+    let temp_dir = create_tree();
+    let out_dir = temp_dir.path().join("sub/out");
+    fs::create_dir(&out_dir).expect("Failed to create dirs");
+
+    // Entries climb out of the manifest's directory instead of being absolute
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path().join("sub/deep"))
+        .arg("hash")
+        .arg("-r")
+        .arg("--manifest")
+        .arg("../out/SHA256SUMS")
+        .arg(".");
+    cmd.assert().success();
+
+    let manifest = fs::read_to_string(out_dir.join("SHA256SUMS")).expect("Failed to read manifest");
+    assert_eq!(
+        manifest,
+        "836ff184e7b41b1e13cb5fd89fa1de98dbbab99e9d2918913ff43b86a5c7c213  ../deep/c.log\n"
+    );
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify")
+        .arg(out_dir.join("SHA256SUMS").as_os_str());
+    cmd.assert().success();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_recursive_manifest_escapes_file_names() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("back\\slash"), "a").expect("Failed to write test file");
+    fs::write(temp_dir.path().join("new\nline"), "b").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("-r")
+        .arg("--manifest")
+        .arg("SHA256SUMS")
+        .arg(".");
+    cmd.assert().success();
+
+    // Same escaping as sha256sum: a leading backslash, then `\\` and `\n`
+    let manifest =
+        fs::read_to_string(temp_dir.path().join("SHA256SUMS")).expect("Failed to read manifest");
+    assert_eq!(
+        manifest,
+        "\\ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9807785afee48bb  back\\\\slash\n\
+         \\3e23e8160039594a33894f6564e1b1348bbd7a0088d42c4acb73eeaed59c009d  new\\nline\n"
+    );
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("SHA256SUMS");
+    cmd.assert().success();
+}
+
+#[test]
+fn test_recursive_manifest_is_not_hashed() {
+    let temp_dir = create_tree();
+    let manifest_path = temp_dir.path().join("SHA256SUMS");
+    fs::write(&manifest_path, "stale").expect("Failed to write manifest");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("-r")
+        .arg("--manifest")
+        .arg("SHA256SUMS")
+        .arg(".");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("SHA256SUMS").not());
+
+    let manifest = fs::read_to_string(&manifest_path).expect("Failed to read manifest");
+    assert_eq!(manifest.lines().count(), 3);
+    assert!(!manifest.contains("SHA256SUMS"));
+}
+
+#[test]
+fn test_recursive_requires_paths() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash").arg("--recursive");
+    cmd.assert().failure();
+}
+
+#[test]
+fn test_exclude_requires_recursive() {
+    let temp_dir = create_tree();
+
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--exclude")
+        .arg("*.log")
+        .arg(temp_dir.path().join("a.txt").as_os_str());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--recursive"));
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::get_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+const HELLO_SHA512: &str = "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca72323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043";
+const HELLO_BLAKE3: &str = "ea8f163db38682925e4491c5e58d4bb3506ef8c14eb78a86e908c5624a67200f";
+
+/// Create a temp directory containing hello.txt ("hello")
+fn create_hello() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("hello.txt"), "hello").expect("Failed to write test file");
+    temp_dir
+}
+
+#[test]
+fn test_tag_output_single_file() {
+    let temp_dir = create_hello();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("--tag")
+        .arg("--file")
+        .arg("hello.txt");
+
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "SHA256 (hello.txt) = {}\n",
+        HELLO_SHA256
+    )));
+}
+
+#[test]
+fn test_tag_output_batch_uppercase() {
+    let temp_dir = create_hello();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("--tag")
+        .arg("--algo")
+        .arg("blake3")
+        .arg("--uppercase")
+        .arg("hello.txt");
+
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "BLAKE3 (hello.txt) = {}\n",
+        HELLO_BLAKE3.to_uppercase()
+    )));
+}
+
+#[test]
+fn test_tag_conflicts_with_json() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash").arg("--tag").arg("--json").arg("-");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_tag_manifest_mixes_algorithms_and_verifies() {
+    let temp_dir = create_hello();
+    let manifest_path = temp_dir.path().join("CHECKSUMS");
+
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--tag")
+        .arg("--algo")
+        .arg("sha256,sha512,blake3")
+        .arg("-r")
+        .arg("--manifest")
+        .arg(manifest_path.as_os_str())
+        .arg(temp_dir.path().as_os_str());
+    cmd.assert().success();
+
+    let manifest = fs::read_to_string(&manifest_path).expect("Failed to read manifest");
+    assert_eq!(
+        manifest,
+        format!(
+            "SHA256 (hello.txt) = {}\nSHA512 (hello.txt) = {}\nBLAKE3 (hello.txt) = {}\n",
+            HELLO_SHA256, HELLO_SHA512, HELLO_BLAKE3
+        )
+    );
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify").arg(manifest_path.as_os_str());
+    let output = cmd.output().expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("hello.txt: OK").count(), 3);
+}
+
+#[test]
+fn test_verify_tagged_lines_from_other_tools() {
+    let temp_dir = create_hello();
+    let manifest_path = temp_dir.path().join("sums.txt");
+    fs::write(
+        &manifest_path,
+        format!(
+            "# shasum --tag\nSHA512 (hello.txt) = {}\n# openssl dgst\nSHA2-256(hello.txt)= {}\n",
+            HELLO_SHA512, HELLO_SHA256
+        ),
+    )
+    .expect("Failed to write manifest");
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify").arg(manifest_path.as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("hello.txt: OK\nhello.txt: OK\n"));
+}
+
+#[test]
+fn test_verify_tagged_mismatch_and_unknown_tag() {
+    let temp_dir = create_hello();
+    let manifest_path = temp_dir.path().join("sums.txt");
+    fs::write(
+        &manifest_path,
+        format!(
+            "SHA256 (hello.txt) = {}\nWHIRLPOOL (hello.txt) = 00\n",
+            HELLO_BLAKE3
+        ),
+    )
+    .expect("Failed to write manifest");
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify")
+        .arg("--continue-on-error")
+        .arg(manifest_path.as_os_str());
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::diff(
+            "hello.txt: FAILED\nhello.txt: FAILED\n",
+        ))
+        .stderr(predicate::str::contains(
+            "sums.txt: 2: Unsupported algorithm tag: WHIRLPOOL",
+        ));
+}
+
+#[test]
+fn test_verify_tagged_insecure_requires_flag() {
+    let temp_dir = create_hello();
+    let manifest_path = temp_dir.path().join("sums.txt");
+    fs::write(
+        &manifest_path,
+        "MD5 (hello.txt) = 5d41402abc4b2a76b9719d911017c592\n",
+    )
+    .expect("Failed to write manifest");
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify").arg(manifest_path.as_os_str());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--allow-insecure"));
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify")
+        .arg("--allow-insecure")
+        .arg(manifest_path.as_os_str());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("hello.txt: OK"));
+}
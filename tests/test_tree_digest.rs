@@ -0,0 +1,156 @@
+mod common;
+
+use common::get_cmd;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Hex tree digest of `dir` with extra arguments
+fn tree_digest(dir: &Path, args: &[&str]) -> String {
+    let output = get_cmd()
+        .arg("hash")
+        .arg("--tree")
+        .arg(dir)
+        .args(["--format", "hex"])
+        .args(args)
+        .output()
+        .expect("Failed to execute command");
+    assert!(
+        output.status.success(),
+        "hash --tree failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+fn write(path: &Path, contents: &str) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("Failed to create directory");
+    }
+    fs::write(path, contents).expect("Failed to write test file");
+}
+
+#[test]
+fn test_tree_digest_format() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    write(&root.join("b.txt"), "bee\n");
+    write(&root.join("a/c.txt"), "sea\n");
+    write(&root.join("a.txt"), "ay\n");
+
+    // Entries in byte order of their paths: "a" < "a.txt" < "a/c.txt" < "b.txt"
+    let mut data = b"hashy-tree-v1\0".to_vec();
+    data.extend_from_slice(b"da\0");
+    for (path, contents) in [("a.txt", "ay\n"), ("a/c.txt", "sea\n"), ("b.txt", "bee\n")] {
+        data.push(b'f');
+        data.extend_from_slice(path.as_bytes());
+        data.push(0);
+        data.extend_from_slice(&Sha256::digest(contents.as_bytes()));
+    }
+
+    assert_eq!(tree_digest(root, &[]), hex::encode(Sha256::digest(&data)));
+}
+
+#[test]
+fn test_tree_digest_is_deterministic() {
+    let first = TempDir::new().expect("Failed to create temp directory");
+    let second = TempDir::new().expect("Failed to create temp directory");
+
+    // Same tree, created in a different order and in a different place
+    write(&first.path().join("src/main.rs"), "fn main() {}\n");
+    write(&first.path().join("README"), "readme\n");
+    write(&first.path().join(".config"), "hidden\n");
+    write(&second.path().join(".config"), "hidden\n");
+    write(&second.path().join("README"), "readme\n");
+    write(&second.path().join("src/main.rs"), "fn main() {}\n");
+
+    let digest = tree_digest(first.path(), &[]);
+    assert_eq!(digest, tree_digest(second.path(), &[]));
+
+    // Names, contents and empty directories all count
+    fs::create_dir(second.path().join("empty")).expect("Failed to create directory");
+    let with_empty_dir = tree_digest(second.path(), &[]);
+    assert_ne!(digest, with_empty_dir);
+    assert_eq!(digest, tree_digest(second.path(), &["--exclude", "empty"]));
+
+    fs::rename(
+        second.path().join("README"),
+        second.path().join("README.md"),
+    )
+    .expect("Failed to rename file");
+    let renamed = tree_digest(second.path(), &[]);
+    assert_ne!(with_empty_dir, renamed);
+
+    write(&second.path().join("README.md"), "changed\n");
+    assert_ne!(renamed, tree_digest(second.path(), &[]));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_tree_digest_permissions_and_symlinks() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    write(&root.join("run.sh"), "#!/bin/sh\n");
+    write(&root.join("a.txt"), "a\n");
+    write(&root.join("b.txt"), "b\n");
+    symlink("a.txt", root.join("link")).expect("Failed to create symlink");
+
+    let plain = tree_digest(root, &[]);
+    let plain_no_permissions = tree_digest(root, &["--no-permissions"]);
+    assert_eq!(plain, plain_no_permissions);
+
+    fs::set_permissions(root.join("run.sh"), fs::Permissions::from_mode(0o755))
+        .expect("Failed to set permissions");
+    assert_ne!(plain, tree_digest(root, &[]));
+    assert_eq!(
+        plain_no_permissions,
+        tree_digest(root, &["--no-permissions"])
+    );
+
+    let targets_ignored = tree_digest(root, &["--no-symlink-targets"]);
+    fs::remove_file(root.join("link")).expect("Failed to remove symlink");
+    symlink("b.txt", root.join("link")).expect("Failed to create symlink");
+    assert_ne!(
+        tree_digest(root, &["--no-permissions"]),
+        plain_no_permissions
+    );
+    assert_eq!(
+        targets_ignored,
+        tree_digest(root, &["--no-symlink-targets"])
+    );
+}
+
+#[test]
+fn test_tree_digest_json_and_errors() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    write(&temp_dir.path().join("a.txt"), "hello");
+    write(&temp_dir.path().join("b/c.txt"), "world!");
+
+    let output = get_cmd()
+        .arg("hash")
+        .arg("--tree")
+        .arg(temp_dir.path())
+        .arg("--json")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output is not valid JSON");
+    assert_eq!(json["source"], "tree");
+    assert_eq!(json["algo"], "sha256");
+    assert_eq!(json["bytes"], 11);
+    assert_eq!(json["digest"], tree_digest(temp_dir.path(), &[]));
+
+    let output = get_cmd()
+        .arg("hash")
+        .arg("--tree")
+        .arg(temp_dir.path().join("a.txt"))
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Not a directory"));
+}
@@ -32,8 +32,7 @@ fn test_verify_ok_sha256sum_style() {
 
     cmd.assert()
         .success()
-        .stdout(predicate::str::contains("data.txt: OK"))
-        .stdout(predicate::str::contains("All files verified successfully."));
+        .stdout(predicate::str::diff("data.txt: OK\n"));
 }
 
 #[test]
@@ -61,7 +60,8 @@ fn test_verify_mismatch() {
 
     cmd.assert()
         .failure()
-        .stdout(predicate::str::contains("file.txt: FAILED (mismatch)"));
+        .stdout(predicate::str::diff("file.txt: FAILED\n"))
+        .stderr(predicate::str::contains("Error").not());
 }
 
 #[test]
@@ -88,11 +88,12 @@ fn test_verify_missing_file_and_continue() {
 
     cmd.assert()
         .failure()
-        .stdout(predicate::str::contains("present.txt: OK"))
-        .stdout(predicate::str::contains(
-            "missing.txt: FAILED (Failed to open file",
+        .stdout(predicate::str::diff(
+            "present.txt: OK\nmissing.txt: FAILED open or read\n",
         ))
-        .stdout(predicate::str::contains("Summary: 1 succeeded, 1 failed"));
+        .stderr(predicate::str::contains(
+            "hashy: missing.txt: No such file or directory",
+        ));
 }
 
 #[test]
@@ -125,3 +126,26 @@ fn test_verify_verbose_output() {
         .stderr(predicate::str::contains("Verifying file: v.txt"))
         .stdout(predicate::str::contains("v.txt: OK"));
 }
+
+#[test]
+fn test_verify_sha3_512_manifest() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("abc.txt"), "abc").expect("Failed to write test file");
+
+    let manifest_path = temp_dir.path().join("checksums.txt");
+    fs::write(
+        &manifest_path,
+        "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0  abc.txt\n",
+    )
+    .expect("Failed to write manifest");
+
+    let mut cmd = get_cmd();
+    cmd.arg("verify")
+        .arg("--algo")
+        .arg("sha3-512")
+        .arg(manifest_path.as_os_str());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("abc.txt: OK"));
+}
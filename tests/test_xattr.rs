@@ -0,0 +1,215 @@
+// Extended attributes are only supported on Unix
+#![cfg(unix)]
+
+mod common;
+
+use assert_cmd::prelude::*;
+use common::get_cmd;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use tempfile::TempDir;
+
+/// Hash the given files with --write-xattr
+fn write_xattrs(dir: &Path, algo: &str, files: &[&str]) {
+    let mut cmd = get_cmd();
+    cmd.current_dir(dir)
+        .arg("hash")
+        .arg("--write-xattr")
+        .arg("--algo")
+        .arg(algo)
+        .args(files);
+    cmd.assert().success();
+}
+
+/// Rewrite a file while keeping its modification time
+fn overwrite_keeping_mtime(path: &Path, contents: &str) {
+    let mtime = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .expect("Failed to read mtime");
+    fs::write(path, contents).expect("Failed to write test file");
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|f| f.set_modified(mtime))
+        .expect("Failed to restore mtime");
+}
+
+#[test]
+fn test_xattr_roundtrip() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("a.txt"), "hello").expect("Failed to write test file");
+    fs::write(temp_dir.path().join("b.txt"), "world").expect("Failed to write test file");
+    write_xattrs(temp_dir.path(), "sha256,blake3", &["a.txt", "b.txt"]);
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--xattr")
+        .arg(".");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("./a.txt: OK"))
+        .stdout(predicate::str::contains("./b.txt: OK"))
+        .stdout(predicate::str::contains("All files verified successfully."));
+}
+
+#[test]
+fn test_xattr_detects_corruption() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let file = temp_dir.path().join("a.txt");
+    fs::write(&file, "hello").expect("Failed to write test file");
+    write_xattrs(temp_dir.path(), "sha256", &["a.txt"]);
+
+    // Same mtime, different contents: silent corruption
+    overwrite_keeping_mtime(&file, "jello");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--xattr")
+        .arg("a.txt");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("a.txt: FAILED (sha256 mismatch)"))
+        .stdout(predicate::str::contains(
+            "expected: 2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+        ))
+        .stderr(predicate::str::contains(
+            "1 computed checksum did NOT match",
+        ));
+}
+
+#[test]
+fn test_xattr_stops_at_first_failure_in_order() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let names: Vec<String> = (0..100).map(|i| format!("file{:03}.txt", i)).collect();
+    for name in &names {
+        fs::write(temp_dir.path().join(name), name).expect("Failed to write test file");
+    }
+    let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+    write_xattrs(temp_dir.path(), "sha256", &refs);
+    overwrite_keeping_mtime(&temp_dir.path().join("file030.txt"), "tampered");
+    overwrite_keeping_mtime(&temp_dir.path().join("file070.txt"), "tampered");
+
+    let output = get_cmd()
+        .current_dir(temp_dir.path())
+        .args(["verify", "--xattr", "."])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[29], "./file029.txt: OK");
+    assert!(lines[30].starts_with("./file030.txt: FAILED"), "{}", stdout);
+    assert!(!stdout.contains("file031.txt"));
+    assert!(!stdout.contains("file070.txt"));
+}
+
+#[test]
+fn test_xattr_flags_stale_digest() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let file = temp_dir.path().join("a.txt");
+    fs::write(&file, "hello").expect("Failed to write test file");
+    write_xattrs(temp_dir.path(), "sha256", &["a.txt"]);
+
+    fs::write(&file, "edited later").expect("Failed to write test file");
+    fs::File::options()
+        .write(true)
+        .open(&file)
+        .and_then(|f| {
+            f.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60))
+        })
+        .expect("Failed to set mtime");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--xattr")
+        .arg("a.txt");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "a.txt: STALE (modified after its sha256 digest was stored)",
+        ))
+        .stderr(predicate::str::contains(
+            "1 file was modified after hashing",
+        ));
+}
+
+#[test]
+fn test_xattr_missing_attribute_and_algo_filter() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("a.txt"), "hello").expect("Failed to write test file");
+    fs::write(temp_dir.path().join("plain.txt"), "plain").expect("Failed to write test file");
+    write_xattrs(temp_dir.path(), "blake3", &["a.txt"]);
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--continue-on-error")
+        .arg("--xattr")
+        .arg("a.txt")
+        .arg("plain.txt");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("a.txt: OK"))
+        .stdout(predicate::str::contains(
+            "plain.txt: FAILED (no user.hashy.* attribute)",
+        ))
+        .stdout(predicate::str::contains("Summary: 1 succeeded, 1 failed"));
+
+    // Only the sha256 attribute is considered, and a.txt has none
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--algo")
+        .arg("sha256")
+        .arg("--xattr")
+        .arg("a.txt");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("a.txt: FAILED (no user.hashy.*"));
+}
+
+#[test]
+fn test_xattr_extendable_output_length() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    fs::write(temp_dir.path().join("a.txt"), "hello").expect("Failed to write test file");
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("hash")
+        .arg("--write-xattr")
+        .arg("--algo")
+        .arg("shake128")
+        .arg("--length")
+        .arg("16")
+        .arg("--file")
+        .arg("a.txt");
+    cmd.assert().success();
+
+    let mut cmd = get_cmd();
+    cmd.current_dir(temp_dir.path())
+        .arg("verify")
+        .arg("--xattr")
+        .arg("a.txt");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("a.txt: OK"));
+}
+
+#[test]
+fn test_write_xattr_requires_file_input() {
+    let mut cmd = get_cmd();
+    cmd.arg("hash")
+        .arg("--write-xattr")
+        .arg("--file")
+        .arg("-")
+        .stdin(Stdio::null());
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "--write-xattr requires file inputs",
+    ));
+}